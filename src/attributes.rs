@@ -0,0 +1,159 @@
+use crate::lints::*;
+use crate::nodes::*;
+use crate::syntax_errors::*;
+use crate::tokens::TokenContext;
+
+/// Every attribute the language knows about. Features that read attributes add
+/// their names here so that they aren't reported as unknown.
+pub const KNOWN_ATTRIBUTES: &[&str] = &["inline", "deprecated", "test", "derive", "cfg", "allow", "warn", "deny"];
+
+/// Finds the first attribute called `name`
+#[allow(dead_code)]
+pub fn find<'a>(attributes: &'a [Attribute], name: &str) -> Option<&'a Attribute> {
+    attributes.iter().find(|attribute| attribute.meta.name() == Some(name))
+}
+
+/// Warns about every attribute in the program that isn't in
+/// `KNOWN_ATTRIBUTES` and every lint named by `allow`, `warn` or `deny` that
+/// doesn't exist, including those of fields and of the items inside modules,
+/// traits and instances. Lint attributes apply to the item they are on and
+/// everything inside it
+pub fn check_attributes(items: &[Item], lints: &LintLevels) -> Result<Vec<SyntaxWarning>, SyntaxErrorCollector> {
+    let mut checker = AttributeChecker { warnings: Vec::new(), errors: SyntaxErrorCollector::new() };
+    checker.check_items(items, lints);
+    if checker.errors.is_empty() { Ok(checker.warnings) } else { Err(checker.errors) }
+}
+
+struct AttributeChecker {
+    warnings: Vec<SyntaxWarning>,
+    errors: SyntaxErrorCollector,
+}
+
+impl AttributeChecker {
+    fn check_items(&mut self, items: &[Item], lints: &LintLevels) {
+        for item in items {
+            let lints = lints.with_attributes(item.attributes());
+            self.check(item.attributes(), &lints);
+            match item {
+                Item::Module { items: Some(items), .. } | Item::Trait { items, .. } | Item::Instance { items, .. } => {
+                    self.check_items(items, &lints);
+                },
+                Item::Struct { fields, .. } => {
+                    for field in fields {
+                        self.check(&field.attributes, &lints.with_attributes(&field.attributes));
+                    }
+                },
+                Item::Enum { fields, .. } => {
+                    for field in fields {
+                        self.check(&field.attributes, &lints.with_attributes(&field.attributes));
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn check(&mut self, attributes: &[Attribute], lints: &LintLevels) {
+        for attribute in attributes {
+            let name = attribute.meta.name().unwrap_or_default();
+            if !KNOWN_ATTRIBUTES.contains(&name) {
+                self.warn(SyntaxWarningType::UnknownAttribute(name.to_string()), &attribute.context, lints);
+            }
+            for (_, lint) in lint_attributes(std::slice::from_ref(attribute)) {
+                if lint != "warnings" && Lint::from_name(&lint).is_none() {
+                    self.warn(SyntaxWarningType::UnknownLint(lint), &attribute.context, lints);
+                }
+            }
+        }
+    }
+
+    fn warn(&mut self, warning_type: SyntaxWarningType, context: &TokenContext, lints: &LintLevels) {
+        let warning = SyntaxWarning { warning_type, context: context.clone() };
+        lints.report(warning, &mut self.warnings, &mut self.errors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn check(code: &str) -> Result<Vec<SyntaxWarning>, SyntaxErrorCollector> {
+        let items = Lexer::new("test.px".to_string(), code.to_string()).parse().ok().unwrap();
+        check_attributes(&items, &LintLevels::new())
+    }
+
+    fn warnings(code: &str) -> Vec<SyntaxWarning> {
+        check(code).ok().unwrap()
+    }
+
+    #[test]
+    fn known_attributes() {
+        assert!(warnings("
+            #[inline] #[deprecated(\"use bar\")]
+            fn foo() {}
+
+            #[derive(Eq)]
+            struct P { #[deprecated] x: i32 }
+
+            #[test]
+            fn check() {}
+        ").is_empty());
+    }
+
+    #[test]
+    fn unknown_attributes() {
+        assert!(matches!(
+            &warnings("
+                #[inlined]
+                fn foo() {}
+
+                mod m {
+                    enum E { #[hidden] A }
+                }
+
+                instance Show i32 {
+                    #[fast] fn show(x: i32): str = \"\";
+                }
+            ")[..],
+            [
+                SyntaxWarning { warning_type: SyntaxWarningType::UnknownAttribute(a), .. },
+                SyntaxWarning { warning_type: SyntaxWarningType::UnknownAttribute(b), .. },
+                SyntaxWarning { warning_type: SyntaxWarningType::UnknownAttribute(c), .. },
+            ] if a == "inlined" && b == "hidden" && c == "fast"
+        ));
+    }
+
+    #[test]
+    fn lint_attributes_apply_inside_items() {
+        let result = check("
+            #[allow(unknown_attributes)]
+            mod quiet {
+                #[inlined] fn foo() {}
+                struct S { #[hidden] x: i32 }
+            }
+
+            #[deny(unknown_attributes)]
+            struct Loud {
+                #[allow(unknown_attributes)] #[hidden] a: i32,
+                #[hidden] b: i32,
+            }
+
+            #[warn(unused_things)]
+            fn bar() {}
+        ");
+
+        let Err(collector) = result else { panic!("expected a denied warning") };
+        assert!(matches!(&collector.errors[..], [
+            SyntaxError { error_type: SyntaxErrorType::DeniedLint(SyntaxWarningType::UnknownAttribute(name)), context, suggestion: _ },
+        ] if name == "hidden" && context.line == 11));
+    }
+
+    #[test]
+    fn unknown_lints() {
+        assert!(matches!(
+            &warnings("#[allow(unreachable_patterns, unused_things)] fn foo() {}")[..],
+            [SyntaxWarning { warning_type: SyntaxWarningType::UnknownLint(name), .. }] if name == "unused_things"
+        ));
+    }
+}
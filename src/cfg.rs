@@ -0,0 +1,182 @@
+use crate::nodes::*;
+use crate::syntax_errors::*;
+use std::collections::HashSet;
+
+/// The options set with `--cfg` on the command line, which decide whether the
+/// items annotated with `#[cfg(...)]` are part of the program. A predicate is
+/// either an option such as `debug`, an option with a value such as
+/// `feature = "x"`, or `all(..)`, `any(..)` and `not(..)` of other predicates.
+pub struct Config {
+    options: HashSet<(String, Option<String>)>,
+}
+
+impl Config {
+    pub fn new() -> Config {
+        Config { options: HashSet::new() }
+    }
+
+    /// Reads options written as `name` or `name="value"`, failing with the
+    /// first option whose name isn't an identifier
+    pub fn from_options(options: &[String]) -> Result<Config, String> {
+        let mut config = Config::new();
+        for option in options {
+            let (name, value) = match option.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (option.trim(), None),
+            };
+            if !is_identifier(name) {
+                return Err(option.clone());
+            }
+            config.set(name, value);
+        }
+        Ok(config)
+    }
+
+    pub fn set(&mut self, name: &str, value: Option<&str>) {
+        self.options.insert((name.to_string(), value.map(str::to_string)));
+    }
+
+    pub fn evaluate(&self, predicate: &Meta) -> Result<bool, SyntaxErrorType> {
+        match predicate {
+            Meta::Word(name) => Ok(self.options.contains(&(name.clone(), None))),
+            Meta::NameValue(name, Expression::StringLiteral { value, context: _ }) => {
+                Ok(self.options.contains(&(name.clone(), Some(value.clone()))))
+            },
+            Meta::List(name, predicates) if name == "all" || name == "any" => {
+                let values = predicates.iter()
+                    .map(|predicate| self.evaluate(predicate))
+                    .collect::<Result<Vec<bool>, SyntaxErrorType>>()?;
+                Ok(if name == "all" { values.iter().all(|value| *value) } else { values.iter().any(|value| *value) })
+            },
+            Meta::List(name, predicates) if name == "not" => match &predicates[..] {
+                [predicate] => Ok(!self.evaluate(predicate)?),
+                _ => Err(SyntaxErrorType::InvalidCfgPredicate),
+            },
+            _ => Err(SyntaxErrorType::InvalidCfgPredicate),
+        }
+    }
+
+    /// Whether something with these attributes is enabled, which is when every
+    /// `cfg` attribute among them holds
+    fn enabled(&self, attributes: &[Attribute], errors: &mut SyntaxErrorCollector) -> bool {
+        let mut enabled = true;
+        for attribute in attributes.iter().filter(|attribute| attribute.meta.name() == Some("cfg")) {
+            let result = match &attribute.meta {
+                Meta::List(_, predicates) if predicates.len() == 1 => self.evaluate(&predicates[0]),
+                _ => Err(SyntaxErrorType::InvalidCfgPredicate),
+            };
+            match result {
+                Ok(value) => enabled &= value,
+                Err(error_type) => errors.add_errors(SyntaxErrorCollector::from_error(error_type, attribute.context.clone())),
+            }
+        }
+        enabled
+    }
+
+    /// Removes every disabled item, field and variant. Modules whose items
+    /// haven't been loaded yet are stripped once they are.
+    pub fn strip(&self, items: Vec<Item>) -> Result<Vec<Item>, SyntaxErrorCollector> {
+        let mut errors = SyntaxErrorCollector::new();
+        let items = self.strip_items(items, &mut errors);
+        if errors.is_empty() { Ok(items) } else { Err(errors) }
+    }
+
+    fn strip_items(&self, items: Vec<Item>, errors: &mut SyntaxErrorCollector) -> Vec<Item> {
+        let mut enabled = Vec::new();
+        for mut item in items {
+            if !self.enabled(item.attributes(), errors) {
+                continue;
+            }
+
+            match &mut item {
+                Item::Module { items: Some(items), .. } | Item::Trait { items, .. } | Item::Instance { items, .. } => {
+                    *items = self.strip_items(std::mem::take(items), errors);
+                },
+                Item::Struct { fields, .. } => fields.retain(|field| self.enabled(&field.attributes, errors)),
+                Item::Enum { fields, .. } => fields.retain(|field| self.enabled(&field.attributes, errors)),
+                _ => {},
+            }
+            enabled.push(item);
+        }
+        enabled
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_alphabetic() || ch == '_')
+        && name.chars().all(|ch| ch.is_alphanumeric() || ch == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn strip(code: &str, options: &[&str]) -> Result<Vec<Item>, SyntaxErrorCollector> {
+        let options: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+        let items = Lexer::new("test.px".to_string(), code.to_string()).parse()?;
+        Config::from_options(&options).ok().unwrap().strip(items)
+    }
+
+    fn names(items: &[Item]) -> Vec<String> {
+        items.iter().filter_map(|item| match item {
+            Item::Function { header, body: _ } => Some(header.name.clone()),
+            Item::Struct { name, .. } | Item::Enum { name, .. } | Item::Module { name, .. } => Some(name.clone()),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn strip_items() {
+        let code = "
+            #[cfg(debug)] fn log() {}
+            #[cfg(not(debug))] fn quiet() {}
+            #[cfg(feature = \"fast\")] fn fast() {}
+            #[cfg(all(debug, any(unix, windows)))] fn trace() {}
+            mod m { #[cfg(debug)] fn inner() {} }
+        ";
+
+        assert_eq!(names(&strip(code, &[]).ok().unwrap()), vec!["quiet", "m"]);
+        assert_eq!(names(&strip(code, &["debug", "feature=\"fast\"", "unix"]).ok().unwrap()), vec!["log", "fast", "trace", "m"]);
+        assert!(matches!(&strip(code, &["debug"]).ok().unwrap()[1], Item::Module { items: Some(items), .. } if items.len() == 1));
+    }
+
+    #[test]
+    fn strip_fields_and_variants() {
+        let items = strip("
+            struct P { x: i32, #[cfg(debug)] id: i32 }
+            enum E { A, #[cfg(not(debug))] B }
+        ", &[]).ok().unwrap();
+
+        assert!(matches!(&items[..], [Item::Struct { fields, .. }, Item::Enum { fields: variants, .. }]
+            if fields.len() == 1 && variants.len() == 2));
+    }
+
+    #[test]
+    fn invalid_predicates() {
+        let result = strip("
+            #[cfg(debug, unix)] fn a() {}
+            #[cfg(not(a, b))] fn b() {}
+            #[cfg(version = 2)] fn c() {}
+        ", &[]);
+
+        assert!(matches!(result, Err(collector) if collector.errors.len() == 3
+            && collector.errors.iter().all(|error| matches!(error.error_type, SyntaxErrorType::InvalidCfgPredicate))));
+    }
+
+    #[test]
+    fn disabled_items_are_parsed() {
+        assert!(strip("#[cfg(windows)] fn f() { let = 1; }", &[]).is_err());
+    }
+
+    #[test]
+    fn invalid_options() {
+        let options = |options: &[&str]| Config::from_options(&options.iter().map(|option| option.to_string()).collect::<Vec<_>>());
+
+        assert!(options(&["debug", "feature=\"x\"", "_unix2"]).is_ok());
+        assert_eq!(options(&["debug", "bad thing"]).err(), Some(String::from("bad thing")));
+        assert_eq!(options(&[""]).err(), Some(String::new()));
+        assert_eq!(options(&["=\"x\""]).err(), Some(String::from("=\"x\"")));
+        assert_eq!(options(&["2fast"]).err(), Some(String::from("2fast")));
+    }
+}
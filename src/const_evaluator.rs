@@ -0,0 +1,471 @@
+use crate::interpreter::TreeWalker;
+use crate::nodes::*;
+use crate::runtime_errors::{ProgramError, RuntimeErrorType};
+use crate::static_analyzer::LiteralTypes;
+use crate::syntax_errors::*;
+use crate::tokens::TokenContext;
+use crate::values::*;
+use std::collections::HashMap;
+
+/// The most calls and loop iterations the evaluation of one constant may take
+const STEP_LIMIT: usize = 1_000_000;
+
+/// Computes the values of `const` items before the program runs. Constants may
+/// use literals, operators, other constants and calls to pure functions, where
+/// a function is pure if it doesn't print, read statics or call anything
+/// impure itself. Statics are only initialized once the program reads them,
+/// but cycles between them are found here too.
+pub struct ConstEvaluator<'a> {
+    items: &'a [Item],
+    literals: &'a LiteralTypes,
+    constants: HashMap<&'a str, (&'a Expression, &'a TokenContext)>,
+    statics: HashMap<&'a str, (&'a Expression, &'a TokenContext)>,
+    functions: HashMap<&'a str, (&'a FunctionHeader, &'a Block)>,
+    summaries: HashMap<String, Option<Summary>>,
+    visited: HashMap<String, bool>,
+    visited_statics: HashMap<String, bool>,
+    order: Vec<String>,
+    errors: SyntaxErrorCollector,
+}
+
+/// What evaluating an expression depends on: the constants and statics it
+/// reads and the reasons, if any, why it can't be evaluated ahead of time
+#[derive(Clone, Default)]
+struct Summary {
+    dependencies: Vec<String>,
+    statics: Vec<String>,
+    problems: Vec<(SyntaxErrorType, TokenContext)>,
+}
+
+impl<'a> ConstEvaluator<'a> {
+    pub fn new(items: &'a [Item], literals: &'a LiteralTypes) -> ConstEvaluator<'a> {
+        let mut constants = HashMap::new();
+        let mut statics = HashMap::new();
+        let mut functions = HashMap::new();
+
+        for item in items {
+            match item {
+                Item::ConstItem { name, type_: _, value, context, public: _, attributes: _ } => {
+                    constants.insert(name.as_str(), (value, context));
+                },
+                Item::StaticItem { name, type_: _, value, context, public: _, attributes: _ } => {
+                    statics.insert(name.as_str(), (value, context));
+                },
+                Item::Function { header, body } => {
+                    functions.insert(header.name.as_str(), (header, body));
+                },
+                _ => {},
+            }
+        }
+
+        ConstEvaluator {
+            items,
+            literals,
+            constants,
+            statics,
+            functions,
+            summaries: HashMap::new(),
+            visited: HashMap::new(),
+            visited_statics: HashMap::new(),
+            order: Vec::new(),
+            errors: SyntaxErrorCollector::new(),
+        }
+    }
+
+    pub fn evaluate(mut self) -> Result<Vec<(String, Value)>, ProgramError> {
+        for item in self.items {
+            match item {
+                Item::ConstItem { name, .. } => self.visit(name, &mut Vec::new()),
+                Item::StaticItem { name, .. } => self.visit_static(name, &mut Vec::new()),
+                _ => {},
+            }
+        }
+        if !self.errors.is_empty() {
+            return Err(self.errors.into());
+        }
+
+        // Every constant only depends on the ones before it in `order`
+        let mut tree_walker = TreeWalker::new();
+        tree_walker.set_literal_types(self.literals.clone());
+        tree_walker.scope_in();
+        let declarations = self.items.iter().filter(|item| {
+            matches!(item, Item::Function { .. } | Item::Struct { .. } | Item::Trait { .. } | Item::Instance { .. })
+        });
+        tree_walker.declare_items(declarations.cloned().collect())?;
+
+        let mut values = Vec::new();
+        for name in &self.order {
+            let (expression, context) = self.constants[name.as_str()];
+            tree_walker.limit_steps(STEP_LIMIT, context);
+            let value = match tree_walker.evaluate(expression, context) {
+                Ok(value) => value,
+                // Recursion that never ends usually runs out of stack before
+                // it runs out of steps
+                Err(ProgramError::Runtime(error)) if matches!(error.error_type, RuntimeErrorType::StackOverflow) => {
+                    return Err(SyntaxErrorCollector::from_error(SyntaxErrorType::ConstantEvaluationLimit, context.clone()).into());
+                },
+                Err(error) => return Err(error),
+            };
+            tree_walker.add_value(name.clone(), value.clone());
+            values.push((name.clone(), value));
+        }
+        Ok(values)
+    }
+
+    /// Checks a constant and everything it depends on, depth first, so that
+    /// constants end up in `order` after their dependencies
+    fn visit(&mut self, name: &str, stack: &mut Vec<String>) {
+        match self.visited.get(name) {
+            Some(true) => return,
+            Some(false) => {
+                let start = stack.iter().position(|visiting| visiting == name).unwrap_or(0);
+                let mut cycle = stack[start..].to_vec();
+                cycle.push(name.to_string());
+                let (_, context) = self.constants[name];
+                self.errors.add_errors(SyntaxErrorCollector::from_error(SyntaxErrorType::ConstantCycle(cycle), context.clone()));
+                return;
+            },
+            None => {},
+        }
+
+        let (expression, _) = self.constants[name];
+        self.visited.insert(name.to_string(), false);
+        stack.push(name.to_string());
+
+        let mut summary = Summary::default();
+        self.walk(expression, &mut Vec::new(), &mut summary);
+        for (error_type, context) in summary.problems {
+            self.errors.add_errors(SyntaxErrorCollector::from_error(error_type, context));
+        }
+        for dependency in summary.dependencies {
+            self.visit(&dependency, stack);
+        }
+
+        stack.pop();
+        self.visited.insert(name.to_string(), true);
+        self.order.push(name.to_string());
+    }
+
+    /// Checks that a static doesn't read itself while it is initialized,
+    /// through other statics or the functions its initializer calls. Unlike
+    /// constants, statics may read anything else
+    fn visit_static(&mut self, name: &str, stack: &mut Vec<String>) {
+        match self.visited_statics.get(name) {
+            Some(true) => return,
+            Some(false) => {
+                let start = stack.iter().position(|visiting| visiting == name).unwrap_or(0);
+                let mut cycle = stack[start..].to_vec();
+                cycle.push(name.to_string());
+                let (_, context) = self.statics[name];
+                self.errors.add_errors(SyntaxErrorCollector::from_error(SyntaxErrorType::StaticCycle(cycle), context.clone()));
+                return;
+            },
+            None => {},
+        }
+
+        let (expression, _) = self.statics[name];
+        self.visited_statics.insert(name.to_string(), false);
+        stack.push(name.to_string());
+
+        let mut summary = Summary::default();
+        self.walk(expression, &mut Vec::new(), &mut summary);
+        for dependency in summary.statics {
+            self.visit_static(&dependency, stack);
+        }
+
+        stack.pop();
+        self.visited_statics.insert(name.to_string(), true);
+    }
+
+    /// Summarizes a function's body. Recursive calls see an empty summary
+    /// while the function is still being walked.
+    fn summarize(&mut self, name: &str) -> Summary {
+        if let Some(summary) = self.summaries.get(name) {
+            return summary.clone().unwrap_or_default();
+        }
+
+        let (header, body) = self.functions[name];
+        self.summaries.insert(name.to_string(), None);
+        let mut locals = header.parameters.clone().unwrap_or_default();
+        let mut summary = Summary::default();
+        self.walk_block(body, &mut locals, &mut summary);
+        self.summaries.insert(name.to_string(), Some(summary.clone()));
+        summary
+    }
+
+    fn call(&mut self, name: &str, context: &TokenContext, summary: &mut Summary) {
+        if self.functions.contains_key(name) {
+            let callee = self.summarize(name);
+            summary.dependencies.extend(callee.dependencies);
+            summary.statics.extend(callee.statics);
+            if !callee.problems.is_empty() {
+                summary.problems.push((SyntaxErrorType::ImpureFunctionCall(name.to_string()), context.clone()));
+            }
+        } else {
+            summary.problems.push((SyntaxErrorType::ImpureFunctionCall(name.to_string()), context.clone()));
+        }
+    }
+
+    fn walk_block(&mut self, block: &Block, locals: &mut Vec<String>, summary: &mut Summary) {
+        let scope = locals.len();
+        for statement in &block.statements {
+            match statement {
+                Statement::LetStatement { pattern, type_: _, type_context: _, expression, context: _ }
+                | Statement::ConstStatement { pattern, type_: _, type_context: _, expression, context: _ } => {
+                    self.walk(expression, locals, summary);
+                    locals.extend(pattern.bindings());
+                },
+                Statement::ExpressionStatement { expression } => self.walk(expression, locals, summary),
+                Statement::DeferStatement { body, context: _ } => self.walk_block(body, locals, summary),
+            }
+        }
+        self.walk(&block.expression, locals, summary);
+        locals.truncate(scope);
+    }
+
+    fn walk(&mut self, expression: &Expression, locals: &mut Vec<String>, summary: &mut Summary) {
+        match expression {
+            Expression::Variable { name, context } => {
+                if locals.contains(name) {
+                    return;
+                }
+                if self.constants.contains_key(name.as_str()) {
+                    summary.dependencies.push(name.clone());
+                } else if self.statics.contains_key(name.as_str()) {
+                    summary.statics.push(name.clone());
+                    summary.problems.push((SyntaxErrorType::NonConstantExpression, context.clone()));
+                } else if self.functions.contains_key(name.as_str()) {
+                    // A function used as a value may be called later on
+                    let function = self.summarize(name);
+                    summary.dependencies.extend(function.dependencies);
+                    summary.statics.extend(function.statics);
+                }
+            },
+            Expression::CallExpression { callee, arguments, context } => {
+                match callee.as_ref() {
+                    Expression::Variable { name, context: _ } if !locals.contains(name) && !self.constants.contains_key(name.as_str()) => {
+                        self.call(name, context, summary);
+                    },
+                    Expression::FieldExpression { left, right, context: _ } if right.parse::<usize>().is_err() => {
+                        self.walk(left, locals, summary);
+                        self.call(right, context, summary);
+                    },
+                    _ => self.walk(callee, locals, summary),
+                }
+                for argument in arguments {
+                    self.walk(argument, locals, summary);
+                }
+            },
+            Expression::ClosureExpression { parameters, contexts: _, types: _, return_type: _, body, context: _ } => {
+                let scope = locals.len();
+                locals.extend(parameters.iter().cloned());
+                self.walk(body, locals, summary);
+                locals.truncate(scope);
+            },
+            Expression::MatchExpression { discriminant, branches, context: _ } => {
+                self.walk(discriminant, locals, summary);
+                for branch in branches {
+                    let scope = locals.len();
+                    locals.extend(branch.pattern.bindings());
+                    if let Some(guard) = &branch.guard {
+                        self.walk(guard, locals, summary);
+                    }
+                    self.walk(&branch.consequent, locals, summary);
+                    locals.truncate(scope);
+                }
+            },
+            Expression::ForExpression { label: _, pattern, iterator, body, context: _ } => {
+                self.walk(iterator, locals, summary);
+                let scope = locals.len();
+                locals.extend(pattern.bindings());
+                self.walk_block(body, locals, summary);
+                locals.truncate(scope);
+            },
+            Expression::IfExpression { condition, body, alternate, context: _ } => {
+                self.walk(condition, locals, summary);
+                self.walk_block(body, locals, summary);
+                self.walk_block(alternate, locals, summary);
+            },
+            Expression::WhileExpression { label: _, condition, body, context: _ } => {
+                self.walk(condition, locals, summary);
+                self.walk_block(body, locals, summary);
+            },
+            Expression::LoopExpression { label: _, body } | Expression::BlockExpression { body } => {
+                self.walk_block(body, locals, summary);
+            },
+            Expression::TryExpression { expression } => self.walk(expression, locals, summary),
+            Expression::CatchExpression { expression, result } => {
+                self.walk(expression, locals, summary);
+                self.walk(result, locals, summary);
+            },
+            Expression::ArrayExpression { type_: _, elements, context: _ } | Expression::TupleExpression { elements } => {
+                for element in elements {
+                    self.walk(element, locals, summary);
+                }
+            },
+            Expression::ArrayRepeatExpression { value: left, length: right, context: _ }
+            | Expression::IndexExpression { indexed: left, argument: right, context: _ } => {
+                self.walk(left, locals, summary);
+                self.walk(right, locals, summary);
+            },
+            Expression::FieldExpression { left, right: _, context: _ } => self.walk(left, locals, summary),
+            Expression::TypeCastExpression { value, type_: _ } => self.walk(value, locals, summary),
+            Expression::ReturnExpression { returned } | Expression::BreakExpression { label: _, returned, context: _ } => {
+                if let Some(returned) = returned {
+                    self.walk(returned, locals, summary);
+                }
+            },
+            Expression::StructExpression { struct_: _, fields, base, context: _ } => {
+                for field in fields {
+                    self.walk(&field.expression, locals, summary);
+                }
+                if let Some(base) = base {
+                    self.walk(base, locals, summary);
+                }
+            },
+            Expression::AssignExpression { target, op: _, value, context: _ } => {
+                self.walk(target, locals, summary);
+                self.walk(value, locals, summary);
+            },
+            Expression::BinaryOp { op: _, left, right, context: _ } => {
+                self.walk(left, locals, summary);
+                self.walk(right, locals, summary);
+            },
+            Expression::UnaryOp { op: _, child, context: _ } => self.walk(child, locals, summary),
+            Expression::ContinueExpression { .. }
+            | Expression::PathExpression { .. }
+            | Expression::IntLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::CharLiteral { .. }
+            | Expression::BooleanLiteral { .. } => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::STACK_SIZE;
+    use crate::lexer::Lexer;
+    use crate::runtime_errors::*;
+
+    fn evaluate(code: &str) -> Result<Vec<(String, Value)>, ProgramError> {
+        let items = Lexer::new("test.px".to_string(), code.to_string()).parse().ok().unwrap();
+        ConstEvaluator::new(&items, &LiteralTypes::default()).evaluate()
+    }
+
+    fn error_types(result: Result<Vec<(String, Value)>, ProgramError>) -> Vec<SyntaxErrorType> {
+        let Err(ProgramError::Syntax(collector)) = result else { panic!("expected syntax errors") };
+        collector.errors.into_iter().map(|error| error.error_type).collect()
+    }
+
+    #[test]
+    fn evaluate_arithmetic() {
+        let values = evaluate("const A: isize = 2 * (3 + 4);").ok().unwrap();
+
+        assert!(matches!(&values[..], [(name, Value { type_: _, value: ValueData::IntegerValue(14) })] if name == "A"));
+    }
+
+    #[test]
+    fn evaluate_dependencies_in_any_order() {
+        let values = evaluate("
+            const B: isize = A * 2;
+            const A: isize = 5;
+        ").ok().unwrap();
+
+        assert!(matches!(&values[..], [
+            (a, Value { type_: _, value: ValueData::IntegerValue(5) }),
+            (b, Value { type_: _, value: ValueData::IntegerValue(10) }),
+        ] if a == "A" && b == "B"));
+    }
+
+    #[test]
+    fn evaluate_pure_function_call() {
+        let values = evaluate("
+            fn factorial(n: isize): isize = if n <= 1 { 1 } else { n * factorial(n - 1) };
+            const F: isize = factorial(LIMIT);
+            const LIMIT: isize = 5;
+        ").ok().unwrap();
+
+        assert!(matches!(&values[..], [_, (f, Value { type_: _, value: ValueData::IntegerValue(120) })] if f == "F"));
+    }
+
+    #[test]
+    fn evaluate_cycle() {
+        let errors = error_types(evaluate("
+            const A: isize = B + 1;
+            const B: isize = through_function();
+            fn through_function(): isize = A;
+        "));
+
+        assert!(matches!(&errors[..], [SyntaxErrorType::ConstantCycle(cycle)]
+            if cycle == &vec!["A".to_string(), "B".to_string(), "A".to_string()]));
+    }
+
+    #[test]
+    fn evaluate_static_cycle() {
+        let errors = error_types(evaluate("
+            static A: isize = B + 1;
+            static B: isize = read_c();
+            static C: isize = { println(1); A };
+            static D: isize = A + C;
+            fn read_c(): isize = C;
+        "));
+
+        assert!(matches!(&errors[..], [SyntaxErrorType::StaticCycle(cycle)]
+            if cycle == &vec!["A".to_string(), "B".to_string(), "C".to_string(), "A".to_string()]));
+    }
+
+    #[test]
+    fn evaluate_impure_call() {
+        let errors = error_types(evaluate("
+            fn noisy(): isize { println(1); 1 }
+            const A: isize = noisy();
+        "));
+
+        assert!(matches!(&errors[..], [SyntaxErrorType::ImpureFunctionCall(name)] if name == "noisy"));
+    }
+
+    #[test]
+    fn evaluate_static_reference() {
+        let errors = error_types(evaluate("
+            static S: isize = 1;
+            const A: isize = S;
+        "));
+
+        assert!(matches!(&errors[..], [SyntaxErrorType::NonConstantExpression]));
+    }
+
+    #[test]
+    fn evaluate_limit() {
+        let errors = error_types(evaluate("
+            fn spin(): isize { loop {} }
+            const A: isize = spin();
+        "));
+        assert!(matches!(&errors[..], [SyntaxErrorType::ConstantEvaluationLimit]));
+
+        let checker = std::thread::Builder::new().stack_size(STACK_SIZE).spawn(|| {
+            let result = evaluate("
+                const INF: isize = forever(1);
+                fn forever(n: isize): isize = forever(n);
+            ");
+            let Err(ProgramError::Syntax(collector)) = result else { panic!("expected syntax errors") };
+            assert!(matches!(&collector.errors[..], [SyntaxError { error_type: SyntaxErrorType::ConstantEvaluationLimit, context, .. }]
+                if context.line == 2));
+        });
+        checker.unwrap().join().unwrap();
+    }
+
+    #[test]
+    fn evaluate_runtime_error() {
+        let result = evaluate("
+            fn half(n: isize): isize = n / 0;
+            const A: isize = half(4);
+        ");
+
+        let Err(ProgramError::Runtime(error)) = result else { panic!("expected a runtime error") };
+        assert!(matches!(*error, RuntimeError { error_type: RuntimeErrorType::DivisionByZero, stack, .. }
+            if matches!(&stack[..], [Frame { function, .. }] if function == "half")));
+    }
+}
@@ -0,0 +1,761 @@
+use crate::attributes::KNOWN_ATTRIBUTES;
+use crate::lints::LINTS;
+use crate::lexer::{Lexer, LexerError, LexerErrorType, ProgramContext};
+use crate::read_file;
+use crate::runtime_errors::*;
+use crate::syntax_errors::*;
+use crate::tokens::{Token, TokenContent, TokenContext};
+use std::collections::HashMap;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const GREEN: &str = "\x1b[1;32m";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    /// Information that isn't a problem itself
+    Note,
+}
+
+/// An error or warning about a place in the source, with everything needed to
+/// show it to the user
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The stable identifier of the kind of problem, if it has one
+    pub code: Option<String>,
+    pub message: String,
+    pub context: TokenContext,
+    /// Shown next to the underline of `context`
+    pub label: Option<String>,
+    /// Other places that help explain the message
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    /// Edits that would fix the problem
+    pub suggestions: Vec<Suggestion>,
+    /// The functions that were running when a runtime error happened, with
+    /// where each of them was, innermost first
+    pub stack: Vec<(String, TokenContext)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub context: TokenContext,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn from_error(error: &SyntaxError) -> Diagnostic {
+        let mut diagnostic = Diagnostic::new(Severity::Error, error.error_type.to_string(), &error.context);
+        diagnostic.code = Some(error.error_type.code().to_string());
+        // The source of an expansion isn't where the error is shown, so it
+        // can't be edited to fix it
+        if error.context.backtrace().is_empty() {
+            diagnostic.suggestions.extend(error.suggestion.clone());
+        }
+        match &error.error_type {
+            SyntaxErrorType::MissingMainFunction => {
+                diagnostic.notes.push(String::from("every program starts by calling `fn main()`"));
+            },
+            SyntaxErrorType::PrivateItem { item: _, module } => {
+                diagnostic.notes.push(format!("items are only visible outside of `{}` if they are marked `pub`", module));
+            },
+            SyntaxErrorType::InvalidBreakValue => {
+                diagnostic.notes.push(String::from("`while` and `for` loops always have the value `()`"));
+            },
+            SyntaxErrorType::StaticCycle(_) | SyntaxErrorType::ConstantCycle(_) => {
+                diagnostic.notes.push(String::from("a value can't be computed from itself"));
+            },
+            SyntaxErrorType::DuplicateDefinition { name, previous } if (&previous.filename, previous.index) != (&error.context.filename, error.context.index) => {
+                diagnostic.labels.push(Label {
+                    context: Self::source_context(previous),
+                    message: format!("`{}` is first defined here", name),
+                });
+            },
+            SyntaxErrorType::DeniedLint(warning_type) => {
+                diagnostic.explain_warning(warning_type);
+                diagnostic.notes.push(format!("the `{}` lint is set to `deny`", warning_type.lint().name()));
+            },
+            _ => {},
+        }
+        diagnostic
+    }
+
+    pub fn from_runtime_error(error: &RuntimeError) -> Diagnostic {
+        let mut diagnostic = Diagnostic::new(Severity::Error, error.error_type.to_string(), &error.context);
+        diagnostic.code = Some(error.error_type.code().to_string());
+        diagnostic.stack = error.backtrace().into_iter()
+            .map(|(function, context)| (function.to_string(), Self::source_context(context)))
+            .collect();
+        if let RuntimeErrorType::UnwrapNone = error.error_type {
+            diagnostic.notes.push(String::from("match on the value to handle `None`"));
+        }
+        diagnostic
+    }
+
+    pub fn from_warning(warning: &SyntaxWarning) -> Diagnostic {
+        let mut diagnostic = Diagnostic::new(Severity::Warning, warning.warning_type.to_string(), &warning.context);
+        diagnostic.code = Some(warning.warning_type.code().to_string());
+        diagnostic.explain_warning(&warning.warning_type);
+        diagnostic
+    }
+
+    /// A note that isn't about any place in the source
+    pub fn note(message: String) -> Diagnostic {
+        Diagnostic::new(Severity::Note, message, &TokenContext::new(String::new(), 0, 0, 0))
+    }
+
+    fn explain_warning(&mut self, warning_type: &SyntaxWarningType) {
+        match warning_type {
+            SyntaxWarningType::UnknownAttribute(_) => {
+                self.notes.push(format!("the known attributes are {}", KNOWN_ATTRIBUTES.join(", ")));
+            },
+            SyntaxWarningType::UnreachablePattern { covered_by } => self.labels.push(Label {
+                context: covered_by.clone(),
+                message: String::from("this arm already matches these values"),
+            }),
+            SyntaxWarningType::UnknownLint(_) => {
+                let lints: Vec<&str> = LINTS.iter().map(|lint| lint.name()).collect();
+                self.notes.push(format!("the lints are {}, and `warnings` names all of them", lints.join(", ")));
+            },
+        }
+    }
+
+    /// A diagnostic at `context`. Code produced by a macro doesn't appear in
+    /// the source, so it is shown at the invocation the code came from
+    fn new(severity: Severity, message: String, context: &TokenContext) -> Diagnostic {
+        let backtrace = context.backtrace();
+        let (context, label) = match backtrace.last() {
+            Some((name, call)) => (call.clone(), Some(format!("in this expansion of `{}!`", name))),
+            None => (context.clone(), None),
+        };
+        let notes = backtrace.iter().rev().skip(1)
+            .map(|(name, _)| format!("in the expansion of `{}!` inside it", name))
+            .collect();
+
+        Diagnostic { severity, code: None, message, context, label, labels: Vec::new(), notes, suggestions: Vec::new(), stack: Vec::new() }
+    }
+
+    /// Where code at `context` appears in the source, which is the outermost
+    /// macro invocation if it was produced by a macro
+    fn source_context(context: &TokenContext) -> TokenContext {
+        match context.backtrace().last() {
+            Some((_, call)) => call.clone(),
+            None => context.clone(),
+        }
+    }
+}
+
+/// Collects the errors, warnings and notes of a run in the order they are
+/// found
+pub struct DiagnosticSink {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl DiagnosticSink {
+    pub fn new() -> DiagnosticSink {
+        DiagnosticSink { diagnostics: Vec::new() }
+    }
+
+    pub fn error(&mut self, error: &SyntaxError) {
+        self.diagnostics.push(Diagnostic::from_error(error));
+    }
+
+    pub fn runtime_error(&mut self, error: &RuntimeError) {
+        self.diagnostics.push(Diagnostic::from_runtime_error(error));
+    }
+
+    /// Every error that stopped the program
+    pub fn program_error(&mut self, error: &ProgramError) {
+        match error {
+            ProgramError::Syntax(collector) => {
+                for error in &collector.errors {
+                    self.error(error);
+                }
+            },
+            ProgramError::Runtime(error) => self.runtime_error(error),
+        }
+    }
+
+    pub fn warning(&mut self, warning: &SyntaxWarning) {
+        self.diagnostics.push(Diagnostic::from_warning(warning));
+    }
+
+    pub fn note(&mut self, message: String) {
+        self.diagnostics.push(Diagnostic::note(message));
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.severity == severity).count()
+    }
+
+    /// Adds a note counting the errors and warnings, if there were any
+    pub fn summarize(&mut self) {
+        let plural = |count: usize, name: &str| format!("{} {}{}", count, name, if count == 1 { "" } else { "s" });
+        let counts: Vec<String> = [(Severity::Error, "error"), (Severity::Warning, "warning")].into_iter()
+            .map(|(severity, name)| (self.count(severity), name))
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, name)| plural(count, name))
+            .collect();
+        if !counts.is_empty() {
+            self.note(format!("{} emitted", counts.join(" and ")));
+        }
+    }
+}
+
+/// Turns diagnostics into text with the lines of source they point at
+pub struct Renderer {
+    color: bool,
+    sources: HashMap<String, Option<Vec<String>>>,
+}
+
+impl Severity {
+    fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+impl Renderer {
+    pub fn new(color: bool) -> Renderer {
+        Renderer { color, sources: HashMap::new() }
+    }
+
+    /// Uses `code` as the contents of `filename` instead of reading the file
+    pub fn add_source(&mut self, filename: &str, code: &str) {
+        self.sources.insert(filename.to_string(), Some(code.lines().map(str::to_string).collect()));
+    }
+
+    pub fn render(&mut self, diagnostic: &Diagnostic) -> String {
+        let color = match diagnostic.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => GREEN,
+        };
+        let name = match &diagnostic.code {
+            Some(code) => format!("{}[{}]", diagnostic.severity.name(), code),
+            None => diagnostic.severity.name().to_string(),
+        };
+        let mut text = format!("{}: {}\n", self.paint(&name, color), self.paint(&diagnostic.message, BOLD));
+
+        // Every line number shares the width of the longest one
+        let lines = std::iter::once(&diagnostic.context).chain(diagnostic.labels.iter().map(|label| &label.context));
+        let width = lines.map(|context| context.line.to_string().len()).max().unwrap_or(1);
+        let gutter = " ".repeat(width);
+
+        // Labels on the line of the diagnostic are drawn under the same copy
+        // of it
+        let context = &diagnostic.context;
+        let (same_line, other_lines): (Vec<&Label>, Vec<&Label>) = diagnostic.labels.iter()
+            .partition(|label| !context.filename.is_empty() && (&label.context.filename, label.context.line) == (&context.filename, context.line));
+        if !context.filename.is_empty() {
+            text += &format!("{}{} {}:{}:{}\n", gutter, self.paint("-->", BLUE), context.filename, context.line, context.column);
+            let primary = Mark { context, label: diagnostic.label.as_deref(), underline: '^', color };
+            let marks: Vec<Mark> = std::iter::once(primary)
+                .chain(same_line.iter().map(|label| Mark::secondary(label)))
+                .collect();
+            text += &self.snippet(&marks, width);
+        }
+        for label in other_lines {
+            let context = &label.context;
+            if context.filename != diagnostic.context.filename {
+                text += &format!("{}{} {}:{}:{}\n", gutter, self.paint(":::", BLUE), context.filename, context.line, context.column);
+            }
+            text += &self.snippet(&[Mark::secondary(label)], width);
+        }
+        for note in &diagnostic.notes {
+            text += &format!("{} {} {}: {}\n", gutter, self.paint("=", BLUE), self.paint("note", BOLD), note);
+        }
+        for suggestion in &diagnostic.suggestions {
+            text += &format!("{} {} {}: {}\n", gutter, self.paint("=", BLUE), self.paint("help", BOLD), suggestion.message());
+        }
+        if !diagnostic.stack.is_empty() {
+            text += &format!("{}\n", self.paint("stack backtrace:", BOLD));
+        }
+        let frames = diagnostic.stack.iter().map(|(function, context)| (function.as_str(), context));
+        for (index, function, context, repeated) in collapse_frames(frames) {
+            text += &format!("{:>4}: {}\n", index, self.paint(function, BOLD));
+            text += &format!("        {} {}:{}:{}\n", self.paint("at", BLUE), context.filename, context.line, context.column);
+            if repeated > 0 {
+                text += &format!("      [previous frame repeated {} times]\n", repeated);
+            }
+        }
+        text
+    }
+
+    /// The diagnostic as a single line of JSON for editors and other tools
+    pub fn render_json(&mut self, diagnostic: &Diagnostic) -> String {
+        let code = match &diagnostic.code {
+            Some(code) => json_string(code),
+            None => String::from("null"),
+        };
+        let spans: Vec<String> = diagnostic.labels.iter()
+            .map(|label| self.json_span(&label.context, None, Some(&label.message)))
+            .collect();
+        let fixes: Vec<String> = diagnostic.suggestions.iter()
+            .map(|suggestion| format!(
+                "{{\"message\":{},\"replacement\":{},\"applicable\":{},\"span\":{}}}",
+                json_string(&suggestion.message()),
+                json_string(&suggestion.replacement),
+                suggestion.applicable,
+                self.json_span(&suggestion.context, Some(suggestion.original.chars().count()), None),
+            ))
+            .collect();
+        let notes: Vec<String> = diagnostic.notes.iter().map(|note| json_string(note)).collect();
+        let stack: Vec<String> = diagnostic.stack.iter()
+            .map(|(function, context)| format!("{{\"function\":{},\"span\":{}}}", json_string(function), self.json_span(context, None, None)))
+            .collect();
+
+        format!(
+            "{{\"severity\":{},\"code\":{},\"message\":{},\"span\":{},\"related\":[{}],\"notes\":[{}],\"fixes\":[{}],\"stack\":[{}]}}\n",
+            json_string(diagnostic.severity.name()),
+            code,
+            json_string(&diagnostic.message),
+            self.json_span(&diagnostic.context, None, diagnostic.label.as_deref()),
+            spans.join(","),
+            notes.join(","),
+            fixes.join(","),
+            stack.join(","),
+        )
+    }
+
+    /// Where the `length` characters at `context` start and end, or `null` if
+    /// they aren't in a file. Without a length the span covers the token at
+    /// `context`. Ends are exclusive
+    fn json_span(&mut self, context: &TokenContext, length: Option<usize>, label: Option<&str>) -> String {
+        if context.filename.is_empty() {
+            return String::from("null");
+        }
+        let length = match (length, self.line(&context.filename, context.line)) {
+            (Some(length), _) => length,
+            (None, Some(line)) => Self::token_length(&line, context.column.saturating_sub(1)),
+            (None, None) => 1,
+        };
+        let label = match label {
+            Some(label) => json_string(label),
+            None => String::from("null"),
+        };
+        format!(
+            "{{\"file\":{},\"line_start\":{},\"column_start\":{},\"line_end\":{},\"column_end\":{},\"label\":{}}}",
+            json_string(&context.filename), context.line, context.column, context.line, context.column + length, label,
+        )
+    }
+
+    /// A source line with the tokens of `marks`, which are all on that line,
+    /// underlined. The rightmost label is written after the underlines and
+    /// the others hang below them
+    fn snippet(&mut self, marks: &[Mark], width: usize) -> String {
+        let Some(first) = marks.first() else {
+            return String::new();
+        };
+        let Some(line) = self.line(&first.context.filename, first.context.line) else {
+            return String::new();
+        };
+        let bar = self.paint("|", BLUE);
+        let mut marks: Vec<(usize, usize, &Mark)> = marks.iter()
+            .map(|mark| {
+                let start = mark.context.column.saturating_sub(1);
+                (start, Self::token_length(&line, start), mark)
+            })
+            .collect();
+        marks.sort_by_key(|(start, _, _)| *start);
+
+        let mut text = format!(
+            "{:w$} {}\n{} {} {}\n",
+            "", bar,
+            self.paint(&format!("{:>w$}", first.context.line, w = width), BLUE), bar, line,
+            w = width,
+        );
+        let underlines: Vec<(usize, usize, String)> = marks.iter()
+            .map(|(start, length, mark)| (*start, *length, self.paint(&mark.underline.to_string().repeat(*length), mark.color)))
+            .collect();
+        let (_, _, last) = marks[marks.len() - 1];
+        let label = match last.label {
+            Some(label) => format!(" {}", self.paint(label, last.color)),
+            None => String::new(),
+        };
+        text += &format!("{:w$} {} {}{}\n", "", bar, Self::row(&line, &underlines), label, w = width);
+
+        let hanging: Vec<(usize, &Mark)> = marks[..marks.len() - 1].iter()
+            .filter(|(_, _, mark)| mark.label.is_some())
+            .map(|(start, _, mark)| (*start, *mark))
+            .collect();
+        if !hanging.is_empty() {
+            let connectors: Vec<(usize, usize, String)> = hanging.iter()
+                .map(|(start, mark)| (*start, 1, self.paint("|", mark.color)))
+                .collect();
+            text += &format!("{:w$} {} {}\n", "", bar, Self::row(&line, &connectors), w = width);
+            for index in (0..hanging.len()).rev() {
+                let (start, mark) = hanging[index];
+                let label = mark.label.unwrap_or_default();
+                let mut items = connectors[..index].to_vec();
+                items.push((start, label.chars().count(), self.paint(label, mark.color)));
+                text += &format!("{:w$} {} {}\n", "", bar, Self::row(&line, &items), w = width);
+            }
+        }
+        text
+    }
+
+    /// Each text in `items` placed at its column of `line`, given with its
+    /// width without colors. The space before it keeps the tabs of `line` so
+    /// the columns line up
+    fn row(line: &str, items: &[(usize, usize, String)]) -> String {
+        let mut row = String::new();
+        let mut column = 0;
+        for (start, length, text) in items {
+            if *start > column {
+                row.extend(line.chars().skip(column).take(start - column).map(|ch| if ch == '\t' { '\t' } else { ' ' }));
+                row += &" ".repeat(start.saturating_sub(column.max(line.chars().count())));
+            }
+            row += text;
+            column = column.max(start + length);
+        }
+        row
+    }
+
+    /// The number of characters in the token that starts `start` characters
+    /// into `line`, or 1 if there is no token there. An attribute counts as
+    /// a single token
+    fn token_length(line: &str, start: usize) -> usize {
+        let mut lexer = Lexer::new(String::new(), line.to_string());
+        lexer.context.index = start;
+        match lexer.next(ProgramContext::NormalContext) {
+            Ok(Some(Token { content: TokenContent::HashOperator, context })) if context.index == start => {
+                Self::attribute_length(&mut lexer).map_or(1, |end| end - start)
+            },
+            Ok(Some(token)) if token.context.index == start => (lexer.context.index - start).max(1),
+            // A literal too large to lex is still a whole token
+            Err(LexerError { error_type: LexerErrorType::IntegerTooLarge(_), context }) if context.index == start => {
+                lexer.context.index - start
+            },
+            _ => 1,
+        }
+    }
+
+    /// Where the attribute whose `#` was just lexed ends, if the rest of it
+    /// is on the same line
+    fn attribute_length(lexer: &mut Lexer) -> Option<usize> {
+        let mut depth = 0;
+        loop {
+            match lexer.next(ProgramContext::NormalContext).ok()??.content {
+                TokenContent::LeftSquareBracketOperator => depth += 1,
+                TokenContent::RightSquareBracketOperator if depth == 1 => return Some(lexer.context.index),
+                TokenContent::RightSquareBracketOperator => depth -= 1,
+                _ if depth == 0 => return None,
+                _ => {},
+            }
+        }
+    }
+
+    fn line(&mut self, filename: &str, line: usize) -> Option<String> {
+        let source = self.sources.entry(filename.to_string()).or_insert_with(|| {
+            read_file::read_file(filename.to_string()).ok().map(|code| code.lines().map(str::to_string).collect())
+        });
+        source.as_ref()?.get(line.checked_sub(1)?).cloned()
+    }
+
+    fn paint(&self, text: &str, color: &str) -> String {
+        match self.color {
+            true => format!("{}{}{}", color, text, RESET),
+            false => text.to_string(),
+        }
+    }
+}
+
+/// A token to underline in a snippet
+struct Mark<'a> {
+    context: &'a TokenContext,
+    label: Option<&'a str>,
+    underline: char,
+    color: &'static str,
+}
+
+impl<'a> Mark<'a> {
+    fn secondary(label: &'a Label) -> Mark<'a> {
+        Mark { context: &label.context, label: Some(&label.message), underline: '-', color: BLUE }
+    }
+}
+
+/// `text` as a quoted JSON string
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            '\n' => quoted += "\\n",
+            '\r' => quoted += "\\r",
+            '\t' => quoted += "\\t",
+            ch if (ch as u32) < 0x20 => quoted += &format!("\\u{:04x}", ch as u32),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{TreeWalker, STACK_SIZE};
+    use crate::lexer::Lexer;
+
+    fn diagnose(code: &str) -> Vec<Diagnostic> {
+        let mut tree_walker = TreeWalker::new();
+        let result = tree_walker.interpret(Lexer::new(String::from("test.px"), code.to_string()).parse());
+
+        let warnings = tree_walker.warnings.iter().map(Diagnostic::from_warning);
+        let mut sink = DiagnosticSink::new();
+        if let Err(error) = &result {
+            sink.program_error(error);
+        }
+        warnings.chain(sink.diagnostics).collect()
+    }
+
+    fn render(code: &str, color: bool) -> String {
+        let mut renderer = Renderer::new(color);
+        renderer.add_source("test.px", code);
+        diagnose(code).iter().map(|diagnostic| renderer.render(diagnostic)).collect()
+    }
+
+    fn render_json(code: &str) -> String {
+        let mut renderer = Renderer::new(false);
+        renderer.add_source("test.px", code);
+        diagnose(code).iter().map(|diagnostic| renderer.render_json(diagnostic)).collect()
+    }
+
+    #[test]
+    fn render_error() {
+        let code = "fn main() {\n    let total = 1 + missing;\n}\n";
+
+        assert_eq!(render(code, false), "\
+error[PX0020]: `missing` is not defined
+ --> test.px:2:21
+  |
+2 |     let total = 1 + missing;
+  |                     ^^^^^^^
+");
+    }
+
+    #[test]
+    fn render_labels_and_notes() {
+        let code = "fn main() {\n    let a = match 1 {\n        _ => 1,\n        2 => 2,\n    };\n}\n";
+
+        assert_eq!(render(code, false), "\
+warning[PX0076]: unreachable pattern
+ --> test.px:4:9
+  |
+4 |         2 => 2,
+  |         ^
+  |
+3 |         _ => 1,
+  |         - this arm already matches these values
+");
+    }
+
+    #[test]
+    fn render_labels_on_one_line() {
+        let code = "fn main() {\n    let a = match 1 { _ => 1, 2 => 2 };\n}\n";
+
+        assert_eq!(render(code, false), "\
+warning[PX0076]: unreachable pattern
+ --> test.px:2:31
+  |
+2 |     let a = match 1 { _ => 1, 2 => 2 };
+  |                       -       ^
+  |                       |
+  |                       this arm already matches these values
+");
+    }
+
+    #[test]
+    fn render_macro_expansion() {
+        let code = "macro first { ($a:expr) => { $a[3] } }\nmacro outer { ($a:expr) => { first!($a) } }\nfn main() { outer!([1]); }";
+
+        assert_eq!(render(code, false), "\
+error[PX0046]: index 3 is out of bounds for a length of 1
+ --> test.px:3:13
+  |
+3 | fn main() { outer!([1]); }
+  |             ^^^^^ in this expansion of `outer!`
+  = note: in the expansion of `first!` inside it
+stack backtrace:
+   0: main
+        at test.px:3:13
+");
+    }
+
+    #[test]
+    fn render_color() {
+        let rendered = render("fn main() { 'a' + 1; }", true);
+
+        assert!(rendered.starts_with("\x1b[1;31merror[PX0024]\x1b[0m: \x1b[1mthis operator can't be used with `char` and `isize`\x1b[0m\n"));
+        assert!(rendered.contains("\x1b[1;34m|\x1b[0m                 \x1b[1;31m^\x1b[0m\n"));
+    }
+
+    #[test]
+    fn render_without_location() {
+        assert_eq!(render("fn helper() {}", false), "\
+error[PX0019]: no `main` function was found
+  = note: every program starts by calling `fn main()`
+");
+    }
+
+    #[test]
+    fn render_json_error() {
+        let code = "fn main() {\n    let total = 1 + missing;\n}\n";
+
+        assert_eq!(render_json(code), concat!(
+            r#"{"severity":"error","code":"PX0020","message":"`missing` is not defined","#,
+            r#""span":{"file":"test.px","line_start":2,"column_start":21,"line_end":2,"column_end":28,"label":null},"#,
+            r#""related":[],"notes":[],"fixes":[],"stack":[]}"#, "\n",
+        ));
+    }
+
+    #[test]
+    fn render_json_related_spans() {
+        let code = "fn main() {\n    let a = match 1 {\n        _ => 1,\n        2 => 2,\n    };\n}\n";
+
+        assert_eq!(render_json(code), concat!(
+            r#"{"severity":"warning","code":"PX0076","message":"unreachable pattern","#,
+            r#""span":{"file":"test.px","line_start":4,"column_start":9,"line_end":4,"column_end":10,"label":null},"#,
+            r#""related":[{"file":"test.px","line_start":3,"column_start":9,"line_end":3,"column_end":10,"label":"this arm already matches these values"}],"#,
+            r#""notes":[],"fixes":[],"stack":[]}"#, "\n",
+        ));
+    }
+
+    #[test]
+    fn render_json_attribute() {
+        let code = "#[allow(unreachable_patterns)]\n#[deprecated(\"use `g`\")] #[inlined]\nfn main() {}\n";
+
+        assert!(render_json(code).contains(
+            r#""span":{"file":"test.px","line_start":2,"column_start":26,"line_end":2,"column_end":36,"label":null}"#
+        ));
+        assert_eq!(Renderer::token_length("#[allow(unreachable_patterns)]", 0), 30);
+        assert_eq!(Renderer::token_length("#[deprecated(\"a ] b\")] fn f()", 0), 22);
+        assert_eq!(Renderer::token_length("#[cfg(", 0), 1);
+    }
+
+    #[test]
+    fn render_json_without_location() {
+        assert_eq!(render_json("fn helper() {}"), concat!(
+            r#"{"severity":"error","code":"PX0019","message":"no `main` function was found","span":null,"related":[],"#,
+            r#""notes":["every program starts by calling `fn main()`"],"fixes":[],"stack":[]}"#, "\n",
+        ));
+    }
+
+    #[test]
+    fn escape_json_strings() {
+        assert_eq!(json_string("a \"b\"\\\n\u{1}"), r#""a \"b\"\\\n\u0001""#);
+    }
+
+    #[test]
+    fn summarize_diagnostics() {
+        let code = "#[inlined]\nfn main() {\n    let a = match 1 { _ => 1, 2 => 2 };\n    println(missing);\n}\n";
+        let mut sink = DiagnosticSink::new();
+        for diagnostic in diagnose(code) {
+            sink.diagnostics.push(diagnostic);
+        }
+        sink.summarize();
+
+        let mut renderer = Renderer::new(false);
+        let rendered = renderer.render(sink.diagnostics.last().unwrap());
+        assert_eq!(rendered, "note: 1 error and 2 warnings emitted\n");
+    }
+
+    #[test]
+    fn render_denied_lint() {
+        let code = "#[deny(unknown_attributes)]\n#[inlined]\nfn main() {}\n";
+
+        assert_eq!(render(code, false), "\
+error[PX0075]: unknown attribute `inlined`
+ --> test.px:2:1
+  |
+2 | #[inlined]
+  | ^^^^^^^^^^
+  = note: the known attributes are inline, deprecated, test, derive, cfg, allow, warn, deny
+  = note: the `unknown_attributes` lint is set to `deny`
+");
+    }
+
+    #[test]
+    fn render_runtime_error() {
+        let code = "fn f(n: isize): isize = n / 0;\nfn main() {\n    f(1);\n}\n";
+
+        assert_eq!(render(code, false), "\
+error[PX0028]: division by zero
+ --> test.px:1:27
+  |
+1 | fn f(n: isize): isize = n / 0;
+  |                           ^
+stack backtrace:
+   0: f
+        at test.px:1:27
+   1: main
+        at test.px:3:5
+");
+        assert!(render_json(code).contains(
+            r#""stack":[{"function":"f","span":{"file":"test.px","line_start":1,"column_start":27,"line_end":1,"column_end":28,"label":null}},"#
+        ));
+    }
+
+    #[test]
+    fn render_repeated_frames() {
+        // Recursing until the stack overflows needs more than the default
+        // stack of a test thread
+        let checker = std::thread::Builder::new().stack_size(STACK_SIZE).spawn(|| {
+            let code = "fn f(n: isize): isize = f(n + 1);\nfn main() {\n    f(1);\n}\n";
+
+            assert!(render(code, false).ends_with("\
+stack backtrace:
+   0: f
+        at test.px:1:25
+      [previous frame repeated 998 times]
+ 999: main
+        at test.px:3:5
+"));
+        });
+        checker.unwrap().join().unwrap();
+    }
+
+    #[test]
+    fn render_suggestion() {
+        let code = "fn main() {\n    let total = 1;\n    println(totl);\n}\n";
+
+        assert_eq!(render(code, false), "\
+error[PX0020]: `totl` is not defined
+ --> test.px:3:13
+  |
+3 |     println(totl);
+  |             ^^^^
+  = help: did you mean `total`?
+");
+        assert!(render_json(code).contains(
+            r#""fixes":[{"message":"did you mean `total`?","replacement":"total","applicable":true,"span":{"file":"test.px","line_start":3,"column_start":13,"line_end":3,"column_end":17,"label":null}}]"#
+        ));
+    }
+
+    #[test]
+    fn render_duplicate_definition() {
+        let code = "fn area(): isize = 1;\nfn area(): isize = 2;\nfn main() {}\n";
+
+        assert_eq!(render(code, false), "\
+error[PX0069]: `area` is defined more than once
+ --> test.px:2:4
+  |
+2 | fn area(): isize = 2;
+  |    ^^^^
+  |
+1 | fn area(): isize = 1;
+  |    ---- `area` is first defined here
+");
+    }
+}
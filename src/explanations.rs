@@ -0,0 +1,749 @@
+/// The long-form description of an error code that `polarix explain` prints
+pub struct Explanation {
+    pub code: &'static str,
+    pub description: &'static str,
+    /// A program that causes the error, or nothing if no program can
+    pub wrong: &'static str,
+    /// The same program with the error fixed
+    pub correct: &'static str,
+}
+
+impl Explanation {
+    pub fn find(code: &str) -> Option<&'static Explanation> {
+        EXPLANATIONS.iter().find(|explanation| explanation.code.eq_ignore_ascii_case(code))
+    }
+
+    pub fn render(&self) -> String {
+        let indent = |code: &str| code.lines().map(|line| format!("    {}\n", line)).collect::<String>();
+        if self.wrong.is_empty() {
+            return format!("{}\n\n{}\n", self.code, self.description);
+        }
+        format!(
+            "{}\n\n{}\n\nErroneous code example:\n\n{}\nCorrected code:\n\n{}",
+            self.code, self.description, indent(self.wrong), indent(self.correct),
+        )
+    }
+}
+
+pub const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "PX0001",
+        description: "\
+A string literal was not closed before the end of the line. Strings can't span
+several lines, so every `\"` that starts a string needs a matching `\"` on the
+same line.",
+        wrong: "fn main() {\n    println(\"hello);\n}",
+        correct: "fn main() {\n    println(\"hello\");\n}",
+    },
+    Explanation {
+        code: "PX0002",
+        description: "\
+A character literal was not closed before the end of the line. A character is
+written between two single quotes, such as `'a'`.",
+        wrong: "fn main() {\n    let c = 'a\n}",
+        correct: "fn main() {\n    let c = 'a';\n}",
+    },
+    Explanation {
+        code: "PX0003",
+        description: "\
+A character literal holds more than one character. A `char` is a single
+character, so text with several characters has to be a string in double
+quotes.",
+        wrong: "fn main() {\n    let c = 'ab';\n}",
+        correct: "fn main() {\n    let c = \"ab\";\n}",
+    },
+    Explanation {
+        code: "PX0004",
+        description: "\
+A character literal has nothing between its quotes. Unlike a string, a `char`
+always holds exactly one character.",
+        wrong: "fn main() {\n    let c = '';\n}",
+        correct: "fn main() {\n    let c = ' ';\n}",
+    },
+    Explanation {
+        code: "PX0005",
+        description: "\
+The lexer found a character that doesn't start any token. Characters outside
+of strings, characters and comments have to be part of a name, a number or an
+operator.",
+        wrong: "fn main() {\n    let x = 1 ` 2;\n}",
+        correct: "fn main() {\n    let x = 1 + 2;\n}",
+    },
+    Explanation {
+        code: "PX0006",
+        description: "\
+An expression was expected but the next token can't start one. This usually
+means an operand is missing, as after the `+` below.",
+        wrong: "fn main() {\n    let x = 1 + ;\n}",
+        correct: "fn main() {\n    let x = 1 + 2;\n}",
+    },
+    Explanation {
+        code: "PX0007",
+        description: "\
+The parser needed a particular token, such as a closing parenthesis or a
+semicolon, and found something else. The message names the token that was
+expected.",
+        wrong: "fn main() {\n    let x = (1 + 2;\n}",
+        correct: "fn main() {\n    let x = (1 + 2);\n}",
+    },
+    Explanation {
+        code: "PX0008",
+        description: "\
+A name was expected, such as after `fn`, `struct` or `.` in a field access, but
+the next token is not an identifier.",
+        wrong: "fn (x: isize): isize = x;\n\nfn main() {}",
+        correct: "fn double(x: isize): isize = x;\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0009",
+        description: "\
+A type was expected, such as after the `:` of a parameter or of a `let`, but the
+next token doesn't start a type.",
+        wrong: "fn double(x:): isize = x * 2;\n\nfn main() {}",
+        correct: "fn double(x: isize): isize = x * 2;\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0010",
+        description: "\
+A pattern was expected, such as after `let` or at the start of a `match` arm,
+but the next token doesn't start a pattern.",
+        wrong: "fn main() {\n    let = 5;\n}",
+        correct: "fn main() {\n    let x = 5;\n}",
+    },
+    Explanation {
+        code: "PX0011",
+        description: "\
+The length of an array type has to be an integer literal, because it is part of
+the type and is needed before the program runs.",
+        wrong: "fn main() {\n    let a: [isize; n] = [1, 2];\n}",
+        correct: "fn main() {\n    let a: [isize; 2] = [1, 2];\n}",
+    },
+    Explanation {
+        code: "PX0012",
+        description: "\
+Only items such as functions, structs, enums, constants and modules can appear
+at the top level of a file. Statements belong inside a function.",
+        wrong: "let x = 5;\n\nfn main() {}",
+        correct: "const X: isize = 5;\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0013",
+        description: "\
+`pub` was put in front of an item that can't be made public. Functions,
+structs, enums, traits, constants, statics and modules can be public, while
+instances, tests, `use` and macros can't.",
+        wrong: "mod shapes {\n    pub fn area(): isize = 1;\n}\n\npub use shapes::area;\n\nfn main() {}",
+        correct: "mod shapes {\n    pub fn area(): isize = 1;\n}\n\nuse shapes::area;\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0014",
+        description: "\
+The brackets of an attribute have to contain a name or a literal, optionally
+followed by `= literal` or a parenthesized list.",
+        wrong: "#[]\nfn main() {}",
+        correct: "#[inline]\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0015",
+        description: "\
+The value after `=` in an attribute has to be a literal such as a string or a
+number.",
+        wrong: "#[deprecated = later]\nfn old() {}\n\nfn main() {}",
+        correct: "#[deprecated = \"use new instead\"]\nfn old() {}\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0016",
+        description: "\
+A repetition `$( ... )` in a macro has to end with `*` for zero or more times,
+`+` for one or more times or `?` for at most once, optionally after a separator
+token.",
+        wrong: "macro sum { ($($x:expr),) => { 0 $(+ $x)* } }\n\nfn main() {}",
+        correct: "macro sum { ($($x:expr),*) => { 0 $(+ $x)* } }\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0017",
+        description: "\
+A label such as `'outer:` can only be put in front of a `loop`, `while` or
+`for`.",
+        wrong: "fn main() {\n    'outer: if true {}\n}",
+        correct: "fn main() {\n    'outer: loop { break 'outer; }\n}",
+    },
+    Explanation {
+        code: "PX0018",
+        description: "\
+The left side of an assignment has to be a place that can hold a value: a
+variable, a field of one or an element of one.",
+        wrong: "fn main() {\n    let x = 1;\n    x + 1 = 2;\n}",
+        correct: "fn main() {\n    let x = 1;\n    x = 2;\n}",
+    },
+    Explanation {
+        code: "PX0019",
+        description: "\
+Every program starts by calling its `main` function, and the root file of the
+program doesn't declare one.",
+        wrong: "fn helper() {}",
+        correct: "fn helper() {}\n\nfn main() {\n    helper();\n}",
+    },
+    Explanation {
+        code: "PX0020",
+        description: "\
+A name was used that no variable, parameter, function, constant or static in
+scope is called. Check the spelling, and that the variable is declared before
+it is used.",
+        wrong: "fn main() {\n    let total = 1;\n    println(totl);\n}",
+        correct: "fn main() {\n    let total = 1;\n    println(total);\n}",
+    },
+    Explanation {
+        code: "PX0021",
+        description: "\
+Only functions and closures can be called, and the value before the
+parentheses is of another type.",
+        wrong: "fn main() {\n    let x = 5;\n    x(1);\n}",
+        correct: "fn main() {\n    let x = |y: isize| y + 5;\n    x(1);\n}",
+    },
+    Explanation {
+        code: "PX0022",
+        description: "\
+A function was called with a different number of arguments than it has
+parameters.",
+        wrong: "fn add(a: isize, b: isize): isize = a + b;\n\nfn main() {\n    add(1);\n}",
+        correct: "fn add(a: isize, b: isize): isize = a + b;\n\nfn main() {\n    add(1, 2);\n}",
+    },
+    Explanation {
+        code: "PX0023",
+        description: "\
+A value has a different type than the place it is used in requires, such as a
+variable that is assigned a value of another type than it was declared with, an
+argument of the wrong type or a branch of an `if` or `match` that produces a
+different type than the others.",
+        wrong: "fn main() {\n    let x = 1;\n    x = true;\n}",
+        correct: "fn main() {\n    let x = 1;\n    x = 2;\n}",
+    },
+    Explanation {
+        code: "PX0024",
+        description: "\
+An operator was used with operands it isn't defined for, such as adding a
+number to a character. Both sides of an arithmetic operator have to be numbers
+of the same type.",
+        wrong: "fn main() {\n    let x = 'a' + 1;\n}",
+        correct: "fn main() {\n    let x = 97 + 1;\n}",
+    },
+    Explanation {
+        code: "PX0025",
+        description: "\
+A field was accessed that the type of the value doesn't have. Structs have the
+fields they are declared with and tuples have one field per element, named by
+its index.",
+        wrong: "struct Point { x: isize, y: isize }\n\nfn main() {\n    let p = Point { x: 1, y: 2 };\n    println(p.z);\n}",
+        correct: "struct Point { x: isize, y: isize }\n\nfn main() {\n    let p = Point { x: 1, y: 2 };\n    println(p.y);\n}",
+    },
+    Explanation {
+        code: "PX0026",
+        description: "\
+The value given to a `let` didn't match its pattern. A `let` has no other
+branch to take, so a value that may not match has to be taken apart with
+`match` instead.",
+        wrong: "fn main() {\n    let (1, y) = (2, 3);\n}",
+        correct: "fn main() {\n    let y = match (2, 3) {\n        (1, y) => y,\n        _ => 0,\n    };\n}",
+    },
+    Explanation {
+        code: "PX0027",
+        description: "\
+No arm of a `match` matched the value. Add an arm for the missing values, or a
+final `_` arm that matches everything.",
+        wrong: "fn main() {\n    let name = match 3 {\n        1 => \"one\",\n        2 => \"two\",\n    };\n}",
+        correct: "fn main() {\n    let name = match 3 {\n        1 => \"one\",\n        2 => \"two\",\n        _ => \"many\",\n    };\n}",
+    },
+    Explanation {
+        code: "PX0028",
+        description: "\
+An integer was divided by zero, or its remainder by zero was taken. Check the
+divisor before dividing when it can be zero.",
+        wrong: "fn main() {\n    let d = 0;\n    println(10 / d);\n}",
+        correct: "fn main() {\n    let d = 0;\n    println(if d == 0 { 0 } else { 10 / d });\n}",
+    },
+    Explanation {
+        code: "PX0029",
+        description: "\
+The result of an integer operation doesn't fit in its type. Check that the
+operands are small enough before operating on them.",
+        wrong: "fn main() {\n    let x = 9223372036854775807;\n    println(x + 1);\n}",
+        correct: "fn main() {\n    let x = 9223372036854775807;\n    println(if x < 9223372036854775807 { x + 1 } else { x });\n}",
+    },
+    Explanation {
+        code: "PX0030",
+        description: "\
+`break` and `continue` can only be used inside a loop, and `return` only inside
+a function. None of them can leave a closure or a deferred block.",
+        wrong: "fn main() {\n    break;\n}",
+        correct: "fn main() {\n    loop { break; }\n}",
+    },
+    Explanation {
+        code: "PX0031",
+        description: "\
+A `break` or `continue` names a label that no loop around it has. Labels are
+only visible inside the loop they are put on.",
+        wrong: "fn main() {\n    'outer: loop {\n        loop { break 'outr; }\n    }\n}",
+        correct: "fn main() {\n    'outer: loop {\n        loop { break 'outer; }\n    }\n}",
+    },
+    Explanation {
+        code: "PX0032",
+        description: "\
+Every alternative of an or-pattern has to bind the same names, because the
+code after the pattern can use them whichever alternative matched.",
+        wrong: "fn main() {\n    let n = match (1, 0) {\n        (x, 0) | (0, y) => x,\n        _ => 0,\n    };\n}",
+        correct: "fn main() {\n    let n = match (1, 0) {\n        (x, 0) | (0, x) => x,\n        _ => 0,\n    };\n}",
+    },
+    Explanation {
+        code: "PX0033",
+        description: "\
+A range pattern has a start after its end, or is an exclusive range whose start
+and end are equal, so no value can match it.",
+        wrong: "fn main() {\n    let small = match 3 {\n        5..1 => true,\n        _ => false,\n    };\n}",
+        correct: "fn main() {\n    let small = match 3 {\n        1..5 => true,\n        _ => false,\n    };\n}",
+    },
+    Explanation {
+        code: "PX0034",
+        description: "\
+Only a `loop` can be left with a value. `while` and `for` loops can end without
+reaching a `break`, so they always have the value `()`.",
+        wrong: "fn main() {\n    let x = while true { break 5; };\n}",
+        correct: "fn main() {\n    let x = loop { break 5; };\n}",
+    },
+    Explanation {
+        code: "PX0035",
+        description: "\
+A bound or an instance names a trait that isn't declared. The built-in traits
+are `Eq`, `Ord` and `Num`.",
+        wrong: "fn same<T: Equal>(a: T): T = a;\n\nfn main() {}",
+        correct: "fn same<T: Eq>(a: T): T = a;\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0036",
+        description: "\
+A generic function was called with a type that doesn't implement a trait its
+type parameter is bound by. Either pass a value of another type or add an
+`instance` of the trait for the type.",
+        wrong: "fn max<T: Ord>(a: T, b: T): T = if a > b { a } else { b };\n\nfn main() {\n    max(true, false);\n}",
+        correct: "fn max<T: Ord>(a: T, b: T): T = if a > b { a } else { b };\n\nfn main() {\n    max(1, 2);\n}",
+    },
+    Explanation {
+        code: "PX0037",
+        description: "\
+Type parameters are inferred from the arguments of a call and from how its
+result is used, and nothing decides one of them.",
+        wrong: "fn first<T>(): isize = 0;\n\nfn main() {\n    first();\n}",
+        correct: "fn first<T>(x: T): isize = 0;\n\nfn main() {\n    first(1);\n}",
+    },
+    Explanation {
+        code: "PX0038",
+        description: "\
+An instance doesn't define a method that its trait declares without a default
+body. Every such method has to be given a body in each instance.",
+        wrong: "trait Describe {\n    fn describe(x: Self): isize;\n}\n\ninstance Describe bool {}\n\nfn main() {}",
+        correct: "trait Describe {\n    fn describe(x: Self): isize;\n}\n\ninstance Describe bool {\n    fn describe(x: bool): isize = if x { 1 } else { 0 };\n}\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0039",
+        description: "\
+Constants are computed before the program runs, so they can only use literals,
+operators, other constants and calls to pure functions. Statics and variables
+have no value yet at that point.",
+        wrong: "static S: isize = 1;\nconst A: isize = S;\n\nfn main() {}",
+        correct: "const S: isize = 1;\nconst A: isize = S;\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0040",
+        description: "\
+A constant calls a function that isn't pure. A function is pure if it doesn't
+print, read a static or call another function that isn't pure.",
+        wrong: "fn noisy(): isize { println(1); 1 }\nconst A: isize = noisy();\n\nfn main() {}",
+        correct: "fn quiet(): isize = 1;\nconst A: isize = quiet();\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0041",
+        description: "\
+Constants depend on each other in a cycle, so none of them can be computed
+first. The message lists every constant in the cycle.",
+        wrong: "const A: isize = B;\nconst B: isize = A;\n\nfn main() {}",
+        correct: "const A: isize = B;\nconst B: isize = 1;\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0042",
+        description: "\
+Statics read each other in a cycle, directly or through the functions their
+initializers call, so one of them would be read while its own initializer is
+running and has no value to give. The message lists every static in the
+cycle.",
+        wrong: "static A: isize = A + 1;\n\nfn main() {\n    println(A);\n}",
+        correct: "static A: isize = 1;\n\nfn main() {\n    println(A);\n}",
+    },
+    Explanation {
+        code: "PX0043",
+        description: "\
+The length of an array has to be zero or more.",
+        wrong: "fn main() {\n    let a = [0; -1];\n}",
+        correct: "fn main() {\n    let a = [0; 1];\n}",
+    },
+    Explanation {
+        code: "PX0044",
+        description: "\
+Only arrays can be indexed with `[]`.",
+        wrong: "fn main() {\n    let x = 5;\n    println(x[0]);\n}",
+        correct: "fn main() {\n    let x = [5];\n    println(x[0]);\n}",
+    },
+    Explanation {
+        code: "PX0045",
+        description: "\
+A `for` loop can only go over the elements of an array.",
+        wrong: "fn main() {\n    for x in 5 {\n        println(x);\n    }\n}",
+        correct: "fn main() {\n    for x in [1, 2, 3, 4, 5] {\n        println(x);\n    }\n}",
+    },
+    Explanation {
+        code: "PX0046",
+        description: "\
+An array was indexed with a number that is negative or not less than its
+length. The elements of an array of length `n` are numbered `0` to `n - 1`.",
+        wrong: "fn main() {\n    let a = [1, 2, 3];\n    println(a[3]);\n}",
+        correct: "fn main() {\n    let a = [1, 2, 3];\n    println(a[2]);\n}",
+    },
+    Explanation {
+        code: "PX0047",
+        description: "\
+A struct literal names a struct that isn't declared.",
+        wrong: "struct Point { x: isize, y: isize }\n\nfn main() {\n    let p = Pont { x: 1, y: 2 };\n}",
+        correct: "struct Point { x: isize, y: isize }\n\nfn main() {\n    let p = Point { x: 1, y: 2 };\n}",
+    },
+    Explanation {
+        code: "PX0048",
+        description: "\
+A struct literal doesn't give a value to every field of the struct. Give the
+missing fields a value, or copy them from another value with `..`.",
+        wrong: "struct Point { x: isize, y: isize }\n\nfn main() {\n    let p = Point { x: 1 };\n}",
+        correct: "struct Point { x: isize, y: isize }\n\nfn main() {\n    let p = Point { x: 1, y: 0 };\n}",
+    },
+    Explanation {
+        code: "PX0049",
+        description: "\
+A struct literal gives the same field a value more than once.",
+        wrong: "struct Point { x: isize, y: isize }\n\nfn main() {\n    let p = Point { x: 1, x: 2, y: 3 };\n}",
+        correct: "struct Point { x: isize, y: isize }\n\nfn main() {\n    let p = Point { x: 1, y: 3 };\n}",
+    },
+    Explanation {
+        code: "PX0050",
+        description: "\
+Constants, statics and functions can't be assigned to. Use a variable for a
+value that changes.",
+        wrong: "const LIMIT: isize = 5;\n\nfn main() {\n    LIMIT = 6;\n}",
+        correct: "fn main() {\n    let limit = 5;\n    limit = 6;\n}",
+    },
+    Explanation {
+        code: "PX0051",
+        description: "\
+A path names a variant that the enum doesn't have.",
+        wrong: "enum Shape { Circle(f64), Empty }\n\nfn main() {\n    let s = Shape::Square(1.0);\n}",
+        correct: "enum Shape { Circle(f64), Empty }\n\nfn main() {\n    let s = Shape::Circle(1.0);\n}",
+    },
+    Explanation {
+        code: "PX0052",
+        description: "\
+A variant was built or matched with a different number of values than it
+carries.",
+        wrong: "enum Shape { Circle(f64), Empty }\n\nfn main() {\n    let s = Shape::Circle(1.0, 2.0);\n}",
+        correct: "enum Shape { Circle(f64), Empty }\n\nfn main() {\n    let s = Shape::Circle(1.0);\n}",
+    },
+    Explanation {
+        code: "PX0053",
+        description: "\
+A path doesn't lead to an item. Every segment before the last has to name a
+module, and the last one an item inside it.",
+        wrong: "mod shapes {\n    pub fn area(): isize = 1;\n}\n\nfn main() {\n    println(shapes::aera());\n}",
+        correct: "mod shapes {\n    pub fn area(): isize = 1;\n}\n\nfn main() {\n    println(shapes::area());\n}",
+    },
+    Explanation {
+        code: "PX0054",
+        description: "\
+`mod name;` loads the module from `name.px` or `name/mod.px` next to the file
+that declares it, and neither file exists. Create one of them, or write the
+module inline.",
+        wrong: "mod shapes;\n\nfn main() {}",
+        correct: "mod shapes {}\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0055",
+        description: "\
+Both `name.px` and `name/mod.px` exist for a module declared with `mod name;`,
+so it isn't clear which one is meant. Remove one of them.",
+        wrong: "// both shapes.px and shapes/mod.px exist\nmod shapes;\n\nfn main() {}",
+        correct: "// only shapes.px exists\nmod shapes;\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0056",
+        description: "\
+Module files include each other in a cycle, which would make the program
+infinitely large. The message lists every file in the cycle.",
+        wrong: "// main.px\nmod shapes;\n\n// shapes.px\nmod main;",
+        correct: "// main.px\nmod shapes;\n\n// shapes.px\npub fn area(): isize = 1;",
+    },
+    Explanation {
+        code: "PX0057",
+        description: "\
+An item was named from outside the module that declares it, and it isn't
+marked `pub`. Items are private to their module and the modules inside it
+unless they are public.",
+        wrong: "mod shapes {\n    fn area(): isize = 1;\n}\n\nfn main() {\n    println(shapes::area());\n}",
+        correct: "mod shapes {\n    pub fn area(): isize = 1;\n}\n\nfn main() {\n    println(shapes::area());\n}",
+    },
+    Explanation {
+        code: "PX0058",
+        description: "\
+A `cfg` attribute has to contain exactly one predicate: an option such as
+`debug`, an option with a string value such as `feature = \"x\"`, or `all(..)`,
+`any(..)` or `not(..)` of other predicates.",
+        wrong: "#[cfg(feature = 1)]\nfn extra() {}\n\nfn main() {}",
+        correct: "#[cfg(feature = \"extra\")]\nfn extra() {}\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0059",
+        description: "\
+A macro was called that isn't declared. A macro can only be called after its
+declaration in the same file.",
+        wrong: "fn main() {\n    println(square!(2));\n}\n\nmacro square { ($a:expr) => { $a * $a } }",
+        correct: "macro square { ($a:expr) => { $a * $a } }\n\nfn main() {\n    println(square!(2));\n}",
+    },
+    Explanation {
+        code: "PX0060",
+        description: "\
+None of the rules of a macro match the tokens it was called with. The rules are
+tried in order and each has to match all of the arguments.",
+        wrong: "macro square { ($a:expr) => { $a * $a } }\n\nfn main() {\n    println(square!(2, 3));\n}",
+        correct: "macro square { ($a:expr) => { $a * $a } }\n\nfn main() {\n    println(square!(2));\n}",
+    },
+    Explanation {
+        code: "PX0061",
+        description: "\
+A transcriber uses a `$` variable that its matcher doesn't bind.",
+        wrong: "macro square { ($a:expr) => { $b * $b } }\n\nfn main() {\n    println(square!(2));\n}",
+        correct: "macro square { ($a:expr) => { $a * $a } }\n\nfn main() {\n    println(square!(2));\n}",
+    },
+    Explanation {
+        code: "PX0062",
+        description: "\
+A variable is used at a different depth of repetition than it was matched at,
+or variables repeated together in a transcriber matched a different number of
+times.",
+        wrong: "macro pairs { ($($a:expr),* ; $($b:expr),*) => { [$(($a, $b)),*] } }\n\nfn main() {\n    let p = pairs!(1, 2; 3);\n}",
+        correct: "macro pairs { ($($a:expr),* ; $($b:expr),*) => { [$(($a, $b)),*] } }\n\nfn main() {\n    let p = pairs!(1, 2; 3, 4);\n}",
+    },
+    Explanation {
+        code: "PX0063",
+        description: "\
+A matcher variable has to be followed by one of the fragment kinds `expr`,
+`ident`, `type`, `literal` or `tt`.",
+        wrong: "macro square { ($a:expression) => { $a * $a } }\n\nfn main() {}",
+        correct: "macro square { ($a:expr) => { $a * $a } }\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0064",
+        description: "\
+A macro expands to a call of itself too many times. Expansions can be nested
+24 deep, so a recursive macro needs a rule that stops the recursion.",
+        wrong: "macro count { ($a:expr) => { count!($a) } }\n\nfn main() {\n    println(count!(1));\n}",
+        correct: "macro count {\n    ($a:expr) => { 1 }\n    ($a:expr, $($rest:expr),+) => { 1 + count!($($rest),+) }\n}\n\nfn main() {\n    println(count!(1));\n}",
+    },
+    Explanation {
+        code: "PX0065",
+        description: "\
+A warning was found whose lint is set to `deny`. Denied warnings used to be
+reported with this code, and now keep the code of the warning instead, such as
+`PX0076` for an unreachable pattern. Lints are denied with `#[deny(lint)]` on
+an item or with `-D lint` on the command line, and lowered again with
+`#[allow(lint)]` or `#[warn(lint)]`.",
+        wrong: "",
+        correct: "",
+    },
+    Explanation {
+        code: "PX0066",
+        description: "\
+The program contains an expression that the interpreter can't run yet, such as
+a type cast. No syntax produces these expressions, so this error only comes up
+when a program is built some other way.",
+        wrong: "",
+        correct: "",
+    },
+    Explanation {
+        code: "PX0067",
+        description: "\
+`unwrap` was called on `None`. `unwrap` takes the value out of a `Some` and
+stops the program if there isn't one, so only use it where the value can't be
+`None`. Otherwise match on the value to handle both variants.",
+        wrong: "enum Option<T> { Some(T), None }\n\nfn main() {\n    let x: Option<isize> = Option::None;\n    println(x.unwrap());\n}",
+        correct: "enum Option<T> { Some(T), None }\n\nfn main() {\n    let x: Option<isize> = Option::None;\n    println(match x {\n        Option::Some(n) => n,\n        Option::None => 0,\n    });\n}",
+    },
+    Explanation {
+        code: "PX0068",
+        description: "\
+The program called `panic`, which stops it with the message it is given. The
+error shows the calls that led to the panic, innermost first.",
+        wrong: "fn check(n: isize) {\n    if n < 0 { panic(\"negative:\", n); }\n}\n\nfn main() {\n    check(-1);\n}",
+        correct: "fn check(n: isize) {\n    if n < 0 { panic(\"negative:\", n); }\n}\n\nfn main() {\n    check(1);\n}",
+    },
+    Explanation {
+        code: "PX0069",
+        description: "\
+A name was declared twice in the same place. Items, the parameters of a
+function and the variables of a single pattern each need a different name.
+Types and traits are named apart from values, so a struct and a function may
+share a name, and `let` may declare a variable again to hide the earlier one.",
+        wrong: "fn area(): isize = 1;\n\nfn area(): isize = 2;\n\nfn main() {}",
+        correct: "fn area(): isize = 1;\n\nfn perimeter(): isize = 2;\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0070",
+        description: "\
+A type was named that no struct, enum or type parameter in scope is called.
+The built-in types are written with keywords, such as `isize` and `bool`,
+apart from `str`.",
+        wrong: "struct Point { x: isize, y: isize }\n\nfn origin(): Pointt = Point { x: 0, y: 0 };\n\nfn main() {}",
+        correct: "struct Point { x: isize, y: isize }\n\nfn origin(): Point = Point { x: 0, y: 0 };\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0071",
+        description: "\
+The type of a variable is inferred from its value and from how it is used,
+and nothing decides it, as with an empty array or a closure parameter that is
+never used. Write the type down in the `let` or on the parameter.",
+        wrong: "fn main() {\n    let empty = [];\n}",
+        correct: "fn main() {\n    let empty: [isize; 0] = [];\n}",
+    },
+    Explanation {
+        code: "PX0072",
+        description: "\
+A number literal is too large or too small for the type it was given, which
+comes from an annotation, a parameter or the other operand of an operator.
+Use a wider type, or a value that fits.",
+        wrong: "fn main() {\n    let byte: u8 = 300;\n}",
+        correct: "fn main() {\n    let byte: u16 = 300;\n}",
+    },
+    Explanation {
+        code: "PX0073",
+        description: "\
+Too many calls were running at once, usually because a recursive function
+never reaches the case that stops it. The error shows the calls that were
+running, innermost first. Check the condition that ends the recursion, or use
+a loop for long computations.",
+        wrong: "fn count(n: isize): isize = 1 + count(n + 1);\n\nfn main() {\n    println(count(0));\n}",
+        correct: "fn count(n: isize): isize = if n == 10 { 0 } else { 1 + count(n + 1) };\n\nfn main() {\n    println(count(0));\n}",
+    },
+    Explanation {
+        code: "PX0074",
+        description: "\
+A constant makes too many calls or loop iterations while it is computed, or
+nests too many calls, so it probably never finishes. Constants are computed
+before the program runs, which can't wait on them forever. Make sure the
+functions the constant calls stop, or compute the value at runtime.",
+        wrong: "fn forever(n: isize): isize = forever(n);\nconst INF: isize = forever(1);\n\nfn main() {}",
+        correct: "fn once(n: isize): isize = n;\nconst ONE: isize = once(1);\n\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0075",
+        description: "\
+An item has an attribute that isn't one of the known attributes, which are
+`inline`, `deprecated`, `test`, `derive`, `cfg`, `allow`, `warn` and `deny`.
+The attribute is ignored, so it is most likely a typo of one of them. This is a
+warning of the `unknown_attributes` lint.",
+        wrong: "#[inlined]\nfn main() {}",
+        correct: "#[inline]\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0076",
+        description: "\
+A match arm can never be reached, because the arms before it already match
+every value its pattern matches. Arms are tried in order, so a catch-all
+pattern such as `_` hides every arm after it. Move the arm above the ones that
+hide it, or remove it. This is a warning of the `unreachable_patterns` lint.",
+        wrong: "fn main() {\n    let n = match 2 {\n        _ => 1,\n        2 => 2,\n    };\n}",
+        correct: "fn main() {\n    let n = match 2 {\n        2 => 2,\n        _ => 1,\n    };\n}",
+    },
+    Explanation {
+        code: "PX0077",
+        description: "\
+`allow`, `warn` or `deny` names a lint that doesn't exist, so the attribute has
+no effect on it. The lints are `unknown_attributes`, `unknown_lints` and
+`unreachable_patterns`, and `warnings` names all of them. This is a warning of
+the `unknown_lints` lint.",
+        wrong: "#[allow(unreachable_pattern)]\nfn main() {}",
+        correct: "#[allow(unreachable_patterns)]\nfn main() {}",
+    },
+    Explanation {
+        code: "PX0078",
+        description: "\
+A statement starts with a single word followed by more code, which only makes
+sense if the word is a keyword such as `while`, `let` or `return`. The word is
+most likely a misspelled keyword.",
+        wrong: "fn main() {\n    let n = 0;\n    whlie n < 3 {\n        n = n + 1;\n    }\n}",
+        correct: "fn main() {\n    let n = 0;\n    while n < 3 {\n        n = n + 1;\n    }\n}",
+    },
+    Explanation {
+        code: "PX0079",
+        description: "\
+An integer literal is larger than any integer type can hold, whatever type it
+is given. Literals are read into 128 bits with a sign, so the largest one
+allowed is 170141183460469231731687303715884105727.",
+        wrong: "fn main() {\n    let big = 999_999_999_999_999_999_999_999_999_999_999_999_999;\n}",
+        correct: "fn main() {\n    let big: u128 = 99_999_999_999_999_999_999_999_999_999_999_999_999;\n}",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfg::Config;
+    use crate::interpreter::{TreeWalker, STACK_SIZE};
+    use crate::lexer::Lexer;
+    use crate::modules::ModuleLoader;
+    use crate::runtime_errors::ProgramError;
+
+    /// The codes of the errors and then the warnings the program causes
+    fn codes(code: &str) -> Vec<&'static str> {
+        let filename = std::env::temp_dir().join("polarix-explanations").join("main.px").display().to_string();
+        let tree = ModuleLoader::load(&filename, Lexer::new(filename.clone(), code.to_string()).parse(), &Config::new());
+        let mut tree_walker = TreeWalker::new();
+        let mut codes = match tree_walker.interpret(tree) {
+            Ok(_) => Vec::new(),
+            Err(ProgramError::Syntax(collector)) => collector.errors.iter().map(|error| error.error_type.code()).collect(),
+            Err(ProgramError::Runtime(error)) => vec![error.error_type.code()],
+        };
+        codes.extend(tree_walker.warnings.iter().map(|warning| warning.warning_type.code()));
+        codes
+    }
+
+    #[test]
+    fn codes_are_ordered() {
+        for (index, explanation) in EXPLANATIONS.iter().enumerate() {
+            assert_eq!(explanation.code, format!("PX{:04}", index + 1));
+        }
+    }
+
+    #[test]
+    fn examples_cause_their_errors() {
+        // These need more than one file, can't be written at all or are no
+        // longer reported
+        let skipped = ["PX0055", "PX0056", "PX0065", "PX0066"];
+        // Expanding a macro 24 times inside itself, and recursing until the
+        // stack overflows, need more than the default stack of a test thread
+        let checker = std::thread::Builder::new().stack_size(STACK_SIZE).spawn(move || {
+            for explanation in EXPLANATIONS.iter().filter(|explanation| !skipped.contains(&explanation.code)) {
+                assert_eq!(codes(explanation.wrong).first(), Some(&explanation.code), "{}", explanation.code);
+                assert_eq!(codes(explanation.correct), Vec::<&str>::new(), "{}", explanation.code);
+            }
+        });
+        checker.unwrap().join().unwrap();
+    }
+
+    #[test]
+    fn find_explanation() {
+        assert!(Explanation::find("px0001").unwrap().render().starts_with("PX0001\n\nA string literal"));
+        assert!(Explanation::find("PX9999").is_none());
+    }
+}
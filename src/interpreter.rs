@@ -1,30 +1,218 @@
+use crate::attributes;
+use crate::const_evaluator::ConstEvaluator;
+use crate::lints::LintLevels;
+use crate::modules::ModuleResolver;
+use crate::runtime_errors::*;
+use crate::static_analyzer::{DeclarationKind, LiteralTypes, NameResolver, NameTable, StaticAnalyzer, TypeChecker};
 use crate::nodes::*;
 use crate::values::*;
 use crate::syntax_errors::*;
-use std::collections::HashMap;
+use crate::tokens::TokenContext;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
 pub struct TreeWalker {
     values: Vec<HashMap<String, Value>>,
+    immutable: Vec<HashSet<String>>,
     types: Vec<HashMap<String, Type>>,
+    functions: HashMap<String, Rc<Function>>,
+    structs: HashMap<String, (Vec<String>, Vec<StructField>)>,
+    enums: HashMap<String, (Vec<String>, Vec<EnumField>)>,
+    traits: HashMap<String, Vec<Item>>,
+    instances: Vec<(String, Type)>,
+    methods: HashMap<String, Vec<(Type, Rc<Function>)>>,
+    statics: HashMap<String, Static>,
+    /// The statics whose initializers are running, outermost first
+    initializing: Vec<String>,
+    /// The declarations the names of the program refer to
+    names: NameTable,
+    /// The types the number literals of the program were given before it ran
+    literals: LiteralTypes,
+    frame_base: usize,
+    /// The number of calls that are running
+    call_depth: usize,
+    /// The calls and loop iterations left, and where to report running out,
+    /// when evaluation is limited
+    steps: Option<(usize, TokenContext)>,
     pub output: String,
+    /// How the warnings found before the program runs are reported
+    pub lints: LintLevels,
+    pub warnings: Vec<SyntaxWarning>,
+}
+
+/// Anything that stops the evaluation of an expression early. Control flow
+/// unwinds through the tree walker until it reaches the construct that
+/// handles it.
+enum Interrupt {
+    Error(ProgramError),
+    Return(Box<Value>),
+    /// Leaves the loop with the label, or the innermost loop if there is none
+    Break(Option<String>, Box<Value>),
+    Continue(Option<String>),
+}
+
+type Evaluation = Result<Value, Interrupt>;
+
+/// Statics are initialized the first time they are read and keep that value
+/// for the rest of the program
+enum Static {
+    Uninitialized(Rc<Expression>, TokenContext),
+    Initializing(TokenContext),
+    Initialized(Value),
+}
+
+/// Traits that the primitive types implement without an instance
+pub const BUILTIN_TRAITS: [&str; 3] = ["Eq", "Ord", "Num"];
+
+/// Whether `type_` implements `trait_`, either as a builtin trait or through
+/// one of `instances`, which pair the name of a trait with a type
+pub fn implements(instances: &[(String, Type)], type_: &Type, trait_: &Trait) -> bool {
+    let builtin = match trait_.name().as_str() {
+        "Eq" => match type_ {
+            Type::Tuple { types } => types.iter().all(|type_| implements(instances, type_, trait_)),
+            Type::Array { type_, length: _ } => implements(instances, type_, trait_),
+            Type::Type { name } => name == "str",
+            Type::Function { .. } | Type::Inferred => false,
+            _ => true,
+        },
+        "Ord" => type_.is_numeric() || matches!(type_, Type::Char) || *type_ == Type::Type { name: String::from("str") },
+        "Num" => type_.is_numeric(),
+        _ => false,
+    };
+
+    builtin || instances.iter().any(|(name, instance_type)| name == trait_.name() && instance_type == type_)
+}
+
+/// Functions that are part of the interpreter rather than the program
+pub const BUILTIN_FUNCTIONS: [&str; 4] = ["print", "println", "panic", "unwrap"];
+
+/// The most calls that can run at once before the program is stopped, which
+/// keeps runaway recursion from overflowing the stack of the interpreter
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+/// The most stack a single call of a function takes in a debug build, with
+/// room to spare for calls nested inside larger expressions
+const STACK_PER_CALL: usize = 128 * 1024;
+
+/// The size of the stack the interpreter runs on, which has room for
+/// `MAX_CALL_DEPTH` calls even in a debug build
+pub const STACK_SIZE: usize = MAX_CALL_DEPTH * STACK_PER_CALL;
+
+impl From<SyntaxErrorCollector> for Interrupt {
+    fn from(error: SyntaxErrorCollector) -> Interrupt {
+        Interrupt::Error(ProgramError::Syntax(error))
+    }
+}
+
+fn error<T>(error_type: SyntaxErrorType, context: &TokenContext) -> Result<T, Interrupt> {
+    Err(SyntaxErrorCollector::from_error(error_type, context.clone()).into())
+}
+
+/// Stops the program with a runtime error, which gathers the stack of calls
+/// as it unwinds through them
+fn fail<T>(error_type: RuntimeErrorType, context: &TokenContext) -> Result<T, Interrupt> {
+    Err(Interrupt::Error(ProgramError::Runtime(Box::new(RuntimeError::new(error_type, context.clone())))))
 }
 
 impl TreeWalker {
     pub fn new() -> TreeWalker {
         TreeWalker {
             values: Vec::new(),
+            immutable: Vec::new(),
             types: Vec::new(),
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            traits: HashMap::new(),
+            instances: Vec::new(),
+            methods: HashMap::new(),
+            statics: HashMap::new(),
+            initializing: Vec::new(),
+            names: NameTable::default(),
+            literals: LiteralTypes::default(),
+            frame_base: 0,
+            call_depth: 0,
+            steps: None,
             output: String::new(),
+            lints: LintLevels::new(),
+            warnings: Vec::new(),
         }
     }
 
     pub fn add_value(&mut self, name: String, value: Value) -> Option<()> {
+        if let Some(immutable) = self.immutable.last_mut() {
+            immutable.remove(&name);
+        }
         self.values.last_mut()?.insert(name, value);
         Some(())
     }
 
+    /// Gives the number literals of the program the types they were checked
+    /// to have
+    pub fn set_literal_types(&mut self, literals: LiteralTypes) {
+        self.literals = literals;
+    }
+
+    /// Stops evaluation with an error at `context` after `steps` more calls
+    /// and loop iterations, so that it can't run forever
+    pub fn limit_steps(&mut self, steps: usize, context: &TokenContext) {
+        self.steps = Some((steps, context.clone()));
+    }
+
+    fn step(&mut self) -> Result<(), Interrupt> {
+        match &mut self.steps {
+            Some((0, context)) => {
+                let context = context.clone();
+                error(SyntaxErrorType::ConstantEvaluationLimit, &context)
+            },
+            Some((steps, _)) => {
+                *steps -= 1;
+                Ok(())
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Adds a value that can't be assigned to
+    pub fn add_constant(&mut self, name: String, value: Value) -> Option<()> {
+        self.add_value(name.clone(), value)?;
+        self.immutable.last_mut()?.insert(name);
+        Some(())
+    }
+
+    /// Replaces the value of the innermost visible variable called `name`
+    fn set_value(&mut self, name: &str, value: Value, context: &TokenContext) -> Result<(), Interrupt> {
+        let frame_base = self.frame_base;
+        let depth = (0..self.values.len()).rev()
+            .filter(|depth| *depth >= frame_base || *depth == 0)
+            .find(|depth| self.values[*depth].contains_key(name));
+
+        let depth = match depth {
+            Some(depth) if self.immutable[depth].contains(name) => {
+                return error(SyntaxErrorType::ImmutableAssignment(name.to_string()), context);
+            },
+            Some(depth) => depth,
+            None if self.statics.contains_key(name) || self.functions.contains_key(name) => {
+                return error(SyntaxErrorType::ImmutableAssignment(name.to_string()), context);
+            },
+            None => return self.undefined(name, context),
+        };
+
+        let current = &self.values[depth][name];
+        if !value.type_.conforms_to(&current.type_) {
+            return fail(RuntimeErrorType::TypeMismatch { expected: current.type_.clone(), found: value.type_ }, context);
+        }
+        self.values[depth].insert(name.to_string(), value);
+        Ok(())
+    }
+
     pub fn get_value(&mut self, name: String) -> Option<&Value> {
-        for scope in self.values.iter().rev() {
+        // A function body only sees its own scopes and the global scope
+        let frame_base = self.frame_base;
+        let visible = self.values.iter().enumerate().rev()
+            .filter(|(depth, _)| *depth >= frame_base || *depth == 0);
+
+        for (_, scope) in visible {
             let value = scope.get(&name);
             if value.is_some() { return value; }
         }
@@ -36,6 +224,7 @@ impl TreeWalker {
         Some(())
     }
 
+    #[allow(dead_code)]
     pub fn get_type(&mut self, name: String) -> Option<&Type> {
         for scope in self.types.iter().rev() {
             let type_ = scope.get(&name);
@@ -46,11 +235,13 @@ impl TreeWalker {
 
     pub fn scope_in(&mut self) {
         self.values.push(HashMap::new());
+        self.immutable.push(HashSet::new());
         self.types.push(HashMap::new());
     }
 
     pub fn scope_out(&mut self) {
         self.values.pop();
+        self.immutable.pop();
         self.types.pop();
     }
 
@@ -58,15 +249,1119 @@ impl TreeWalker {
         self.output += &string;
     }
 
-    pub fn interpret(&mut self, tree: Result<Expression, SyntaxErrorCollector>) -> Result<Value, SyntaxErrorCollector> {
-        self.interpret_expression(tree)
+    pub fn interpret(&mut self, tree: Result<Vec<Item>, SyntaxErrorCollector>) -> Result<Value, ProgramError> {
+        let items = tree?;
+        self.warnings = attributes::check_attributes(&items, &self.lints)?;
+        let items = ModuleResolver::new().resolve(items)?;
+        // Both passes run even if names are missing, so that a misspelled name
+        // doesn't hide the rest of what they find
+        let names = NameResolver::new().resolve(&items);
+        match (names, StaticAnalyzer::new(self.lints.clone()).analyze(&items)) {
+            (Ok(names), Ok(warnings)) => {
+                self.warnings.extend(warnings);
+                self.literals = TypeChecker::new(&names).check(&items)?;
+                self.names = names;
+            },
+            (Err(errors), Ok(warnings)) => {
+                self.warnings.extend(warnings);
+                return Err(errors.into());
+            },
+            (Ok(_), Err(errors)) => return Err(errors.into()),
+            (Err(mut errors), Err(analyzer_errors)) => {
+                errors.add_errors(analyzer_errors);
+                return Err(errors.into());
+            },
+        }
+        self.scope_in();
+
+        let constants = ConstEvaluator::new(&items, &self.literals).evaluate()?;
+        for (name, value) in constants {
+            self.add_constant(name, value);
+        }
+        self.declare_items(items)?;
+
+        let context = match self.functions.get("main") {
+            Some(main) => main.header.context.clone(),
+            None => return Err(SyntaxErrorCollector::from_error(
+                SyntaxErrorType::MissingMainFunction,
+                TokenContext::new(String::new(), 0, 0, 0),
+            ).into()),
+        };
+
+        match self.call_named("main", Vec::new(), &context) {
+            Ok(value) => Ok(value),
+            Err(Interrupt::Error(error)) => Err(error),
+            Err(_) => Err(ProgramError::Runtime(Box::new(RuntimeError::new(RuntimeErrorType::InvalidControlFlow, context)))),
+        }
+    }
+
+    pub fn declare_items(&mut self, items: Vec<Item>) -> Result<(), SyntaxErrorCollector> {
+        let mut errors = SyntaxErrorCollector::new();
+        let mut instances = Vec::new();
+
+        // Traits are declared before any instance so instances can be checked
+        // against them regardless of the order of the program
+        for item in items {
+            match item {
+                Item::Function { header, body } => {
+                    self.functions.insert(header.name.clone(), Rc::new(Function { header, body }));
+                },
+                Item::Struct { name, type_parameters, fields, context: _, public: _, attributes: _ } => {
+                    self.structs.insert(name, (type_parameters, fields));
+                },
+                Item::Enum { name, type_parameters, fields, context: _, public: _, attributes: _ } => {
+                    self.enums.insert(name, (type_parameters, fields));
+                },
+                Item::Trait { name, type_parameters: _, items, context: _, public: _, attributes: _ } => {
+                    self.traits.insert(name, items);
+                },
+                Item::Instance { trait_, type_, items, context, attributes: _ } => instances.push((trait_, type_, items, context)),
+                Item::StaticItem { name, type_: _, value, context, public: _, attributes: _ } => {
+                    self.statics.insert(name, Static::Uninitialized(Rc::new(value), context));
+                },
+                _ => {},
+            }
+        }
+
+        for (trait_, type_, items, context) in instances {
+            if let Err(error) = self.declare_instance(trait_, type_, items, &context) {
+                errors.add_errors(error);
+            }
+        }
+
+        for function in self.functions.values() {
+            for bound in function.header.type_parameters.iter().flat_map(|parameter| &parameter.bounds) {
+                if !self.trait_exists(bound.name()) {
+                    errors.add_errors(SyntaxErrorCollector::from_error(
+                        SyntaxErrorType::UndefinedTrait(bound.name().clone()),
+                        function.header.context.clone(),
+                    ));
+                }
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    fn declare_instance(&mut self, trait_: Trait, type_: Type, items: Vec<Item>, context: &TokenContext) -> Result<(), SyntaxErrorCollector> {
+        let name = trait_.name().clone();
+        let trait_items = match self.traits.get(&name) {
+            Some(trait_items) => trait_items.clone(),
+            None => return Err(SyntaxErrorCollector::from_error(SyntaxErrorType::UndefinedTrait(name), context.clone())),
+        };
+
+        let mut methods: Vec<Function> = items.into_iter().filter_map(|item| match item {
+            Item::Function { header, body } => Some(Function { header, body }),
+            _ => None,
+        }).collect();
+
+        // Methods the instance doesn't define fall back to the trait's default
+        for item in trait_items {
+            let defined = |header: &FunctionHeader| methods.iter().any(|method| method.header.name == header.name);
+            match item {
+                Item::Function { header, body } if !defined(&header) => methods.push(Function { header, body }),
+                Item::FunctionDeclaration { header } if !defined(&header) => {
+                    return Err(SyntaxErrorCollector::from_error(
+                        SyntaxErrorType::MissingTraitMethod { trait_: name, method: header.name },
+                        context.clone(),
+                    ));
+                },
+                _ => {},
+            }
+        }
+
+        for method in methods {
+            self.methods.entry(method.header.name.clone()).or_default().push((type_.clone(), Rc::new(method)));
+        }
+        self.instances.push((name, type_));
+        Ok(())
+    }
+
+    fn trait_exists(&self, name: &str) -> bool {
+        BUILTIN_TRAITS.contains(&name) || self.traits.contains_key(name)
+    }
+
+    fn implements(&self, type_: &Type, trait_: &Trait) -> bool {
+        implements(&self.instances, type_, trait_)
+    }
+
+    /// Binds type parameters to the types of the arguments they appear in and
+    /// checks the resulting types against the parameters' trait bounds
+    fn infer_type_arguments(&self, header: &FunctionHeader, arguments: &[Value], context: &TokenContext) -> Result<HashMap<String, Type>, Interrupt> {
+        let mut bindings = HashMap::new();
+        let names: Vec<&String> = header.type_parameters.iter().map(|parameter| &parameter.name).collect();
+        for (type_, argument) in header.types.iter().zip(arguments) {
+            Self::unify(type_, &argument.type_, &names, &mut bindings, context)?;
+        }
+
+        for parameter in &header.type_parameters {
+            // A type parameter the arguments don't mention was inferred from
+            // how the result is used before the program ran
+            let type_ = match bindings.get(&parameter.name) {
+                Some(type_) => type_,
+                None => continue,
+            };
+            for bound in &parameter.bounds {
+                if !self.implements(type_, bound) {
+                    return error(SyntaxErrorType::UnsatisfiedTraitBound { type_: type_.clone(), trait_: bound.clone() }, context);
+                }
+            }
+        }
+        Ok(bindings)
+    }
+
+    fn unify(declared: &Type, actual: &Type, parameters: &[&String], bindings: &mut HashMap<String, Type>, context: &TokenContext) -> Result<(), Interrupt> {
+        match (declared, actual) {
+            (_, Type::Inferred) => Ok(()),
+            (Type::Type { name }, _) if parameters.contains(&name) => match bindings.get(name) {
+                Some(bound) if !actual.conforms_to(bound) => {
+                    fail(RuntimeErrorType::TypeMismatch { expected: bound.clone(), found: actual.clone() }, context)
+                },
+                Some(_) => Ok(()),
+                None => {
+                    bindings.insert(name.clone(), actual.clone());
+                    Ok(())
+                },
+            },
+            (Type::Tuple { types: x }, Type::Tuple { types: y })
+            | (Type::GenericType { name: _, types: x }, Type::GenericType { name: _, types: y }) if x.len() == y.len() => {
+                for (x, y) in x.iter().zip(y) {
+                    Self::unify(x, y, parameters, bindings, context)?;
+                }
+                Ok(())
+            },
+            (Type::Function { parameters: x, return_type: x_return },
+             Type::Function { parameters: y, return_type: y_return }) if x.len() == y.len() => {
+                for (x, y) in x.iter().zip(y) {
+                    Self::unify(x, y, parameters, bindings, context)?;
+                }
+                Self::unify(x_return, y_return, parameters, bindings, context)
+            },
+            (Type::Array { type_: x, length: _ }, Type::Array { type_: y, length: _ }) => {
+                Self::unify(x, y, parameters, bindings, context)
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Finds the instance method for the type of the first argument
+    fn find_method(&self, name: &str, arguments: &[Value]) -> Option<Rc<Function>> {
+        let receiver = &arguments.first()?.type_;
+        let (_, method) = self.methods.get(name)?.iter().find(|(type_, _)| receiver.conforms_to(type_))?;
+        Some(Rc::clone(method))
+    }
+
+    /// Evaluates a single expression in the global scope
+    pub fn evaluate(&mut self, expression: &Expression, context: &TokenContext) -> Result<Value, ProgramError> {
+        match Self::finish_call(self.interpret_expression(expression), context) {
+            Ok(value) => Ok(value),
+            Err(Interrupt::Error(error)) => Err(error),
+            Err(_) => Err(ProgramError::Runtime(Box::new(RuntimeError::new(RuntimeErrorType::InvalidControlFlow, context.clone())))),
+        }
+    }
+
+    fn call_named(&mut self, name: &str, arguments: Vec<Value>, context: &TokenContext) -> Evaluation {
+        if let Some(callee) = self.lookup(name, context)? {
+            return self.call_value(callee, arguments, context);
+        }
+        if let Some(method) = self.find_method(name, &arguments) {
+            return self.call_function(method, arguments, context);
+        }
+
+        let strings: Vec<String> = arguments.iter().map(|argument| argument.as_string()).collect();
+        match name {
+            "print" | "println" => {
+                self.write(strings.join(" "));
+                if name == "println" { self.write(String::from("\n")); }
+                Ok(Value::unit())
+            },
+            "panic" => fail(RuntimeErrorType::Panic(strings.join(" ")), context),
+            "unwrap" => Self::unwrap(arguments, context),
+            _ => self.undefined(name, context),
+        }
+    }
+
+    /// The value inside a `Some`
+    fn unwrap(arguments: Vec<Value>, context: &TokenContext) -> Evaluation {
+        Self::check_arguments(&[Type::Inferred], &arguments, context)?;
+        let argument = arguments.into_iter().next().unwrap_or_else(Value::unit);
+        match argument.value {
+            ValueData::EnumValue(_, variant, payload) if variant == "Some" && payload.len() == 1 => {
+                Ok(payload.into_iter().next().unwrap_or_else(Value::unit))
+            },
+            ValueData::EnumValue(_, variant, _) if variant == "None" => fail(RuntimeErrorType::UnwrapNone, context),
+            _ => fail(RuntimeErrorType::TypeMismatch {
+                expected: Type::GenericType { name: String::from("Option"), types: vec![Type::Inferred] },
+                found: argument.type_,
+            }, context),
+        }
+    }
+
+    /// An error for the undefined variable `name` that suggests a visible
+    /// name it may be a typo of
+    fn undefined<T>(&self, name: &str, context: &TokenContext) -> Result<T, Interrupt> {
+        let frame_base = self.frame_base;
+        let names: Vec<&str> = self.values.iter().enumerate()
+            .filter(|(depth, _)| *depth >= frame_base || *depth == 0)
+            .flat_map(|(_, values)| values.keys())
+            .chain(self.statics.keys())
+            .chain(self.functions.keys())
+            .chain(self.methods.keys())
+            .map(String::as_str)
+            .chain(BUILTIN_FUNCTIONS)
+            .collect();
+
+        let mut errors = SyntaxErrorCollector::from_error(SyntaxErrorType::UndefinedVariable(name.to_string()), context.clone());
+        errors.suggest_name(name, names);
+        Err(errors.into())
+    }
+
+    /// An error for the missing field `name` of `value` that suggests a field
+    /// it may be a typo of
+    fn no_such_field<T>(value: Value, name: &str, context: &TokenContext) -> Result<T, Interrupt> {
+        let fields: Vec<String> = match &value.value {
+            ValueData::StructValue(_, fields) => fields.iter().map(|(field, _)| field.clone()).collect(),
+            _ => Vec::new(),
+        };
+        let mut errors = SyntaxErrorCollector::from_error(SyntaxErrorType::NoSuchField(value.type_, name.to_string()), context.clone());
+        errors.suggest_name(name, fields.iter().map(String::as_str));
+        Err(errors.into())
+    }
+
+    /// Finds the variable written at `context`, falling back to the functions
+    /// declared in the program so that named functions can be used as values.
+    /// Names bound to an item, a method or a builtin go straight to it instead
+    /// of searching the scopes
+    fn lookup(&mut self, name: &str, context: &TokenContext) -> Result<Option<Value>, Interrupt> {
+        match self.names.value(context).map(|declaration| declaration.kind) {
+            Some(DeclarationKind::Static) => return self.static_value(name).map(Some),
+            Some(DeclarationKind::Function) => return Ok(self.functions.get(name).map(Self::function_value)),
+            Some(DeclarationKind::Method | DeclarationKind::Builtin) => return Ok(None),
+            _ => {},
+        }
+        if let Some(value) = self.get_value(name.to_string()) {
+            return Ok(Some(value.clone()));
+        }
+        if self.statics.contains_key(name) {
+            return self.static_value(name).map(Some);
+        }
+        Ok(self.functions.get(name).map(Self::function_value))
+    }
+
+    fn function_value(function: &Rc<Function>) -> Value {
+        Value::new(function.type_(), ValueData::FunctionValue(Rc::clone(function)))
+    }
+
+    fn static_value(&mut self, name: &str) -> Evaluation {
+        let (expression, context) = match self.statics.get(name) {
+            Some(Static::Initialized(value)) => return Ok(value.clone()),
+            Some(Static::Initializing(context)) => {
+                let start = self.initializing.iter().position(|initializing| initializing == name).unwrap_or(0);
+                let mut cycle = self.initializing[start..].to_vec();
+                cycle.push(name.to_string());
+                return error(SyntaxErrorType::StaticCycle(cycle), context);
+            },
+            Some(Static::Uninitialized(expression, context)) => (Rc::clone(expression), context.clone()),
+            None => return error(SyntaxErrorType::UndefinedVariable(name.to_string()), &TokenContext::new(String::new(), 0, 0, 0)),
+        };
+
+        // Initializers only see the global scope, not the scope of whatever
+        // happened to read the static first
+        self.statics.insert(name.to_string(), Static::Initializing(context.clone()));
+        self.initializing.push(name.to_string());
+        let frame_base = self.frame_base;
+        self.frame_base = self.values.len();
+        let result = Self::finish_call(self.interpret_expression(&expression), &context);
+        self.frame_base = frame_base;
+        self.initializing.pop();
+
+        match result {
+            Ok(value) => {
+                self.statics.insert(name.to_string(), Static::Initialized(value.clone()));
+                Ok(value)
+            },
+            Err(interrupt) => {
+                self.statics.insert(name.to_string(), Static::Uninitialized(expression, context));
+                Err(interrupt)
+            },
+        }
+    }
+
+    fn call_value(&mut self, callee: Value, arguments: Vec<Value>, context: &TokenContext) -> Evaluation {
+        match callee.value {
+            ValueData::FunctionValue(function) => self.call_function(function, arguments, context),
+            ValueData::ClosureValue(closure) => self.call_closure(closure, arguments, context),
+            _ => error(SyntaxErrorType::NotCallable(callee.type_), context),
+        }
+    }
+
+    /// Checks arguments against the declared parameter types. Only function
+    /// types are checked at runtime, since every callable value knows its
+    /// exact signature.
+    fn check_arguments(types: &[Type], arguments: &[Value], context: &TokenContext) -> Result<(), Interrupt> {
+        if types.len() != arguments.len() {
+            return error(SyntaxErrorType::ArgumentCountMismatch {
+                expected: types.len(),
+                found: arguments.len(),
+            }, context);
+        }
+
+        for (type_, argument) in types.iter().zip(arguments) {
+            Self::check_type(type_, argument, context)?;
+        }
+        Ok(())
+    }
+
+    fn check_type(expected: &Type, value: &Value, context: &TokenContext) -> Result<(), Interrupt> {
+        if !Self::checked_conforms(&value.type_, expected) {
+            return fail(RuntimeErrorType::TypeMismatch { expected: expected.clone(), found: value.type_.clone() }, context);
+        }
+        Ok(())
+    }
+
+    /// Only function signatures and array lengths are checked at runtime, since
+    /// the rest was checked before the program ran
+    fn checked_conforms(actual: &Type, expected: &Type) -> bool {
+        match (actual, expected) {
+            (_, Type::Function { .. }) => actual.conforms_to(expected),
+            (Type::Array { type_: x, length: x_length }, Type::Array { type_: y, length: y_length }) => {
+                x_length == y_length && Self::checked_conforms(x, y)
+            },
+            (Type::Inferred, _) => true,
+            (_, Type::Array { .. }) => false,
+            _ => true,
+        }
+    }
+
+    fn call_function(&mut self, function: Rc<Function>, arguments: Vec<Value>, context: &TokenContext) -> Evaluation {
+        let bindings = if function.header.type_parameters.is_empty() {
+            HashMap::new()
+        } else {
+            self.infer_type_arguments(&function.header, &arguments, context)?
+        };
+        let types: Vec<Type> = function.header.types.iter().map(|type_| type_.substitute(&bindings)).collect();
+        Self::check_arguments(&types, &arguments, context)?;
+        let parameters = function.header.parameters.clone().unwrap_or_default();
+        self.enter_call(context)?;
+
+        let frame_base = self.frame_base;
+        self.frame_base = self.values.len();
+        self.scope_in();
+        for (name, type_) in bindings {
+            self.add_type(name, type_);
+        }
+        for (parameter, argument) in parameters.into_iter().zip(arguments) {
+            self.add_value(parameter, argument);
+        }
+
+        let result = self.interpret_block_contents(&function.body);
+        self.scope_out();
+        self.frame_base = frame_base;
+        self.call_depth -= 1;
+
+        Self::called(Self::finish_call(result, context), &function.header.name, context)
+    }
+
+    fn call_closure(&mut self, closure: Rc<Closure>, arguments: Vec<Value>, context: &TokenContext) -> Evaluation {
+        Self::check_arguments(&closure.types, &arguments, context)?;
+        self.enter_call(context)?;
+
+        // Captured values live in their own scope underneath the parameters
+        let frame_base = self.frame_base;
+        self.frame_base = self.values.len();
+        self.scope_in();
+        for (name, value) in &closure.captured {
+            self.add_value(name.clone(), value.clone());
+        }
+        self.scope_in();
+        for (parameter, argument) in closure.parameters.iter().zip(arguments) {
+            self.add_value(parameter.clone(), argument);
+        }
+
+        let result = self.interpret_expression(&closure.body);
+        self.scope_out();
+        self.scope_out();
+        self.frame_base = frame_base;
+        self.call_depth -= 1;
+
+        Self::called(Self::finish_call(result, context), "<closure>", context)
+    }
+
+    /// Counts a call that is about to run, failing if too many already are
+    fn enter_call(&mut self, context: &TokenContext) -> Result<(), Interrupt> {
+        if self.call_depth >= MAX_CALL_DEPTH {
+            return fail(RuntimeErrorType::StackOverflow, context);
+        }
+        self.step()?;
+        self.call_depth += 1;
+        Ok(())
+    }
+
+    fn finish_call(result: Evaluation, context: &TokenContext) -> Evaluation {
+        match result {
+            Ok(value) => Ok(value),
+            Err(Interrupt::Return(value)) => Ok(*value),
+            Err(Interrupt::Break(..)) | Err(Interrupt::Continue(_)) => {
+                fail(RuntimeErrorType::InvalidControlFlow, context)
+            },
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Adds the call of `function` from `context` to the stack of a runtime
+    /// error leaving it
+    fn called(result: Evaluation, function: &str, context: &TokenContext) -> Evaluation {
+        match result {
+            Err(Interrupt::Error(ProgramError::Runtime(mut error))) => {
+                error.stack.push(Frame { function: function.to_string(), context: context.clone() });
+                Err(Interrupt::Error(ProgramError::Runtime(error)))
+            },
+            result => result,
+        }
+    }
+
+    /// Every value visible from the current scope, used to capture the
+    /// environment of a closure when it is created
+    fn visible_values(&self) -> HashMap<String, Value> {
+        let mut visible = HashMap::new();
+        for (depth, scope) in self.values.iter().enumerate() {
+            if depth >= self.frame_base || depth == 0 {
+                visible.extend(scope.iter().map(|(name, value)| (name.clone(), value.clone())));
+            }
+        }
+        visible
+    }
+
+    fn interpret_block(&mut self, block: &Block) -> Evaluation {
+        self.scope_in();
+        let result = self.interpret_block_contents(block);
+        self.scope_out();
+        result
+    }
+
+    fn interpret_block_contents(&mut self, block: &Block) -> Evaluation {
+        let mut deferred = Vec::new();
+        let mut result = self.interpret_statements(block, &mut deferred);
+
+        // Deferred blocks run last to first however the block is left, and
+        // an error in one of them replaces what the block would have done.
+        // Errors in the source are all reported, but a runtime error only
+        // ever reports the first failure
+        for (body, context) in deferred.into_iter().rev() {
+            self.scope_out();
+            let cleanup = match self.interpret_block(body) {
+                Ok(_) => continue,
+                Err(Interrupt::Error(error)) => error,
+                Err(_) => ProgramError::Runtime(Box::new(RuntimeError::new(RuntimeErrorType::InvalidControlFlow, context.clone()))),
+            };
+            result = match (result, cleanup) {
+                (Err(Interrupt::Error(ProgramError::Syntax(mut errors))), ProgramError::Syntax(cleanup)) => {
+                    errors.add_errors(cleanup);
+                    Err(errors.into())
+                },
+                (Err(Interrupt::Error(error)), _) => Err(Interrupt::Error(error)),
+                (_, cleanup) => Err(Interrupt::Error(cleanup)),
+            };
+        }
+        result
+    }
+
+    fn interpret_statements<'a>(&mut self, block: &'a Block, deferred: &mut Vec<(&'a Block, &'a TokenContext)>) -> Evaluation {
+        for statement in &block.statements {
+            match statement {
+                // The variables declared after a deferred block go in a scope
+                // of their own, which is left before the block runs
+                Statement::DeferStatement { body, context } => {
+                    deferred.push((body, context));
+                    self.scope_in();
+                },
+                _ => self.interpret_statement(statement)?,
+            }
+        }
+        self.interpret_expression(&block.expression)
+    }
+
+    fn interpret_statement(&mut self, statement: &Statement) -> Result<(), Interrupt> {
+        match statement {
+            Statement::LetStatement { pattern, type_, type_context: _, expression, context }
+            | Statement::ConstStatement { pattern, type_, type_context: _, expression, context } => {
+                let value = self.interpret_expression(expression)?;
+                if let Some(type_) = type_ {
+                    Self::check_type(type_, &value, context)?;
+                }
+                if !self.destructure(pattern, value) {
+                    return fail(RuntimeErrorType::RefutablePattern, context);
+                }
+                if matches!(statement, Statement::ConstStatement { .. }) {
+                    if let Some(immutable) = self.immutable.last_mut() {
+                        immutable.extend(pattern.bindings());
+                    }
+                }
+                Ok(())
+            },
+            Statement::ExpressionStatement { expression } => {
+                self.interpret_expression(expression)?;
+                Ok(())
+            },
+            // Deferred blocks are scheduled by the block they appear in
+            Statement::DeferStatement { .. } => Ok(()),
+        }
+    }
+
+    /// Binds the parts of `value` to the names in `pattern`, returning whether
+    /// the value matched the pattern at all
+    fn destructure(&mut self, pattern: &Pattern, value: Value) -> bool {
+        match pattern {
+            Pattern::IdentifierPattern { name, context: _ } => {
+                self.add_value(name.clone(), value);
+                true
+            },
+            Pattern::BlankPattern => true,
+            Pattern::TuplePattern { patterns } => match value.value {
+                ValueData::TupleValue(elements) if elements.len() == patterns.len() => {
+                    patterns.iter().zip(elements).all(|(pattern, element)| self.destructure(pattern, element))
+                },
+                _ => false,
+            },
+            Pattern::EnumPattern { path, patterns, context: _ } => match (PathSegment::variant(path), value.value) {
+                (Some((enum_, variant)), ValueData::EnumValue(value_enum, value_variant, payload))
+                    if *enum_ == value_enum && *variant == value_variant && payload.len() == patterns.len() => {
+                    patterns.iter().zip(payload).all(|(pattern, element)| self.destructure(pattern, element))
+                },
+                _ => false,
+            },
+            Pattern::LiteralPattern { literal } => match self.interpret_expression(literal) {
+                Ok(literal) => literal.equals(&value),
+                Err(_) => false,
+            },
+            Pattern::OrPattern { patterns, context: _ } => {
+                patterns.iter().any(|pattern| self.destructure(pattern, value.clone()))
+            },
+            Pattern::BindingPattern { name, pattern, context: _ } => {
+                if !self.destructure(pattern, value.clone()) {
+                    return false;
+                }
+                self.add_value(name.clone(), value);
+                true
+            },
+            Pattern::RangePattern { start, end, inclusive, context: _ } => match (start.as_ref(), end.as_ref(), value.value) {
+                (Expression::IntLiteral { value: start, context: _ }, Expression::IntLiteral { value: end, context: _ }, ValueData::IntegerValue(x)) => {
+                    *start <= x && (x < *end || *inclusive && x == *end)
+                },
+                (Expression::CharLiteral { value: start, .. }, Expression::CharLiteral { value: end, .. }, ValueData::CharValue(x)) => {
+                    *start <= x && (x < *end || *inclusive && x == *end)
+                },
+                _ => false,
+            },
+        }
+    }
+
+    fn interpret_expression(&mut self, expression: &Expression) -> Evaluation {
+        match expression {
+            Expression::IntLiteral { value: x, context } => {
+                let type_ = self.literals.type_(context).cloned().unwrap_or(Type::IntSize);
+                match type_.is_float() {
+                    true => Ok(Value::new(type_, ValueData::FloatValue(*x as f64))),
+                    false => Ok(Value::new(type_, ValueData::IntegerValue(*x))),
+                }
+            },
+            Expression::FloatLiteral { value: x, context } => {
+                let type_ = self.literals.type_(context).cloned().unwrap_or(Type::Float64);
+                Ok(Value::new(type_, ValueData::FloatValue(*x)))
+            },
+            Expression::StringLiteral { value: x, context: _ } => {
+                Ok(Value::new(Type::Type { name: String::from("str") }, ValueData::StringValue(x.clone())))
+            },
+            Expression::CharLiteral { value: x, context: _ } => Ok(Value::new(Type::Char, ValueData::CharValue(*x))),
+            Expression::BooleanLiteral { value: x, context: _ } => Ok(Value::new(Type::Boolean, ValueData::BooleanValue(*x))),
+            Expression::TupleExpression { elements } => {
+                let mut values = Vec::new();
+                for element in elements {
+                    values.push(self.interpret_expression(element)?);
+                }
+                Ok(Value::tuple(values))
+            },
+            Expression::ArrayExpression { type_, elements, context } => {
+                let values = self.interpret_arguments(elements)?;
+                // Every element has to have the type of the first one
+                let mut element_type = type_.clone();
+                for value in &values {
+                    if element_type == Type::Inferred {
+                        element_type = value.type_.clone();
+                    } else if !value.type_.conforms_to(&element_type) {
+                        return fail(RuntimeErrorType::TypeMismatch { expected: element_type, found: value.type_.clone() }, context);
+                    }
+                }
+                Ok(Value::array(element_type, values))
+            },
+            Expression::ArrayRepeatExpression { value, length, context } => {
+                let value = self.interpret_expression(value)?;
+                let length = self.interpret_expression(length)?;
+                match length.value {
+                    ValueData::IntegerValue(length) if length >= 0 => {
+                        Ok(Value::array(value.type_.clone(), vec![value; length as usize]))
+                    },
+                    ValueData::IntegerValue(length) => error(SyntaxErrorType::InvalidArrayLength(length), context),
+                    _ => fail(RuntimeErrorType::TypeMismatch { expected: Type::UIntSize, found: length.type_ }, context),
+                }
+            },
+            Expression::IndexExpression { indexed, argument, context } => {
+                let indexed = self.interpret_expression(indexed)?;
+                let argument = self.interpret_expression(argument)?;
+                let elements = match indexed.value {
+                    ValueData::ArrayValue(elements) => elements,
+                    _ => return error(SyntaxErrorType::NotIndexable(indexed.type_), context),
+                };
+                match argument.value {
+                    ValueData::IntegerValue(index) => match usize::try_from(index).ok().and_then(|index| elements.get(index)) {
+                        Some(element) => Ok(element.clone()),
+                        None => fail(RuntimeErrorType::IndexOutOfBounds { index, length: elements.len() }, context),
+                    },
+                    _ => fail(RuntimeErrorType::TypeMismatch { expected: Type::UIntSize, found: argument.type_ }, context),
+                }
+            },
+            Expression::PathExpression { segments, context } => self.construct_variant(segments, Vec::new(), context),
+            Expression::StructExpression { struct_, fields, base, context } => {
+                self.interpret_struct(struct_, fields, base, context)
+            },
+            Expression::AssignExpression { target, op, value, context } => {
+                let mut value = self.interpret_expression(value)?;
+                if let Some(op) = op {
+                    let current = self.interpret_expression(target)?;
+                    value = self.interpret_binary_op(*op, current, value, context)?;
+                }
+                self.assign(target, value, context)?;
+                Ok(Value::unit())
+            },
+            Expression::Variable { name, context } => match self.lookup(name, context)? {
+                Some(value) => Ok(value),
+                None => self.undefined(name, context),
+            },
+            Expression::ClosureExpression { parameters, contexts: _, types, return_type, body, context: _ } => {
+                let closure = Closure {
+                    parameters: parameters.clone(),
+                    types: types.clone(),
+                    return_type: return_type.clone().unwrap_or(Type::Inferred),
+                    body: *body.clone(),
+                    captured: self.visible_values(),
+                };
+                Ok(Value::new(closure.type_(), ValueData::ClosureValue(Rc::new(closure))))
+            },
+            Expression::BlockExpression { body } => self.interpret_block(body),
+            Expression::IfExpression { condition, body, alternate, context } => {
+                if self.interpret_condition(condition, context)? {
+                    self.interpret_block(body)
+                } else {
+                    self.interpret_block(alternate)
+                }
+            },
+            Expression::WhileExpression { label, condition, body, context } => {
+                while self.interpret_condition(condition, context)? {
+                    if self.interpret_loop_body(body, label)?.is_some() {
+                        break;
+                    }
+                }
+                Ok(Value::unit())
+            },
+            Expression::LoopExpression { label, body } => loop {
+                if let Some(value) = self.interpret_loop_body(body, label)? {
+                    return Ok(value);
+                }
+            },
+            Expression::ForExpression { label, pattern, iterator, body, context } => {
+                let iterator = self.interpret_expression(iterator)?;
+                let elements = match iterator.value {
+                    ValueData::ArrayValue(elements) => elements,
+                    _ => return error(SyntaxErrorType::NotIterable(iterator.type_), context),
+                };
+                for element in elements {
+                    self.scope_in();
+                    let result = match self.destructure(pattern, element) {
+                        true => self.interpret_loop_body(body, label),
+                        false => fail(RuntimeErrorType::RefutablePattern, context),
+                    };
+                    self.scope_out();
+                    if result?.is_some() {
+                        break;
+                    }
+                }
+                Ok(Value::unit())
+            },
+            Expression::MatchExpression { discriminant, branches, context } => {
+                let value = self.interpret_expression(discriminant)?;
+                for branch in branches {
+                    self.scope_in();
+                    let matched = match &branch.guard {
+                        _ if !self.destructure(&branch.pattern, value.clone()) => Ok(false),
+                        Some(guard) => self.interpret_condition(guard, &branch.context),
+                        None => Ok(true),
+                    };
+                    let result = match matched {
+                        Ok(true) => Some(self.interpret_expression(&branch.consequent)),
+                        Ok(false) => None,
+                        Err(interrupt) => Some(Err(interrupt)),
+                    };
+                    self.scope_out();
+                    if let Some(result) = result {
+                        return result;
+                    }
+                }
+                fail(RuntimeErrorType::NonExhaustiveMatch, context)
+            },
+            Expression::ReturnExpression { returned } => Err(Interrupt::Return(Box::new(self.interpret_optional(returned)?))),
+            Expression::BreakExpression { label, returned, context: _ } => {
+                Err(Interrupt::Break(label.clone(), Box::new(self.interpret_optional(returned)?)))
+            },
+            Expression::ContinueExpression { label, context: _ } => Err(Interrupt::Continue(label.clone())),
+            Expression::FieldExpression { left, right, context } => {
+                let value = self.interpret_expression(left)?;
+                match Self::field(&value, right) {
+                    Some(field) => Ok(field.clone()),
+                    None => Self::no_such_field(value, right, context),
+                }
+            },
+            Expression::CallExpression { callee, arguments, context } => match callee.as_ref() {
+                // `x.f(y)` passes `x` as the first argument of `f`
+                Expression::FieldExpression { left, right, context: field_context } if right.parse::<usize>().is_err() => {
+                    let mut values = vec![self.interpret_expression(left)?];
+                    values.extend(self.interpret_arguments(arguments)?);
+                    self.call_named(right, values, field_context)
+                },
+                Expression::Variable { name, context: _ } => {
+                    let values = self.interpret_arguments(arguments)?;
+                    self.call_named(name, values, context)
+                },
+                Expression::PathExpression { segments, context: _ } => {
+                    let values = self.interpret_arguments(arguments)?;
+                    self.construct_variant(segments, values, context)
+                },
+                _ => {
+                    let callee = self.interpret_expression(callee)?;
+                    let values = self.interpret_arguments(arguments)?;
+                    self.call_value(callee, values, context)
+                },
+            },
+            Expression::BinaryOp { op: Operator::AndOperator, left, right, context } => {
+                let value = self.interpret_condition(left, context)? && self.interpret_condition(right, context)?;
+                Ok(Value::new(Type::Boolean, ValueData::BooleanValue(value)))
+            },
+            Expression::BinaryOp { op: Operator::OrOperator, left, right, context } => {
+                let value = self.interpret_condition(left, context)? || self.interpret_condition(right, context)?;
+                Ok(Value::new(Type::Boolean, ValueData::BooleanValue(value)))
+            },
+            Expression::BinaryOp { op, left, right, context } => {
+                let left = self.interpret_expression(left)?;
+                let right = self.interpret_expression(right)?;
+                self.interpret_binary_op(*op, left, right, context)
+            },
+            Expression::UnaryOp { op, child, context } => {
+                let child = self.interpret_expression(child)?;
+                self.interpret_unary_op(*op, child, context)
+            },
+            // These have no syntax yet, so no program contains them
+            Expression::TryExpression { .. } => Self::unsupported("`try` expressions"),
+            Expression::CatchExpression { .. } => Self::unsupported("`catch` expressions"),
+            Expression::TypeCastExpression { .. } => Self::unsupported("type casts"),
+        }
+    }
+
+    fn unsupported(kind: &str) -> Evaluation {
+        error(SyntaxErrorType::UnsupportedExpression(kind.to_string()), &TokenContext::new(String::new(), 0, 0, 0))
+    }
+
+    fn interpret_struct(&mut self, name: &str, fields: &[StructExpressionField], base: &Option<Box<Expression>>, context: &TokenContext) -> Evaluation {
+        let (type_parameters, declared) = match self.structs.get(name) {
+            Some(struct_) => struct_.clone(),
+            None => {
+                let mut errors = SyntaxErrorCollector::from_error(SyntaxErrorType::UndefinedStruct(name.to_string()), context.clone());
+                errors.suggest_name(name, self.structs.keys().map(String::as_str));
+                return Err(errors.into());
+            },
+        };
+        let struct_type = Type::Type { name: name.to_string() };
+
+        let mut values: HashMap<String, Value> = HashMap::new();
+        for field in fields {
+            if !declared.iter().any(|declared| declared.name == field.name) {
+                return error(SyntaxErrorType::NoSuchField(struct_type, field.name.clone()), context);
+            }
+            let value = self.interpret_expression(&field.expression)?;
+            if values.insert(field.name.clone(), value).is_some() {
+                return error(SyntaxErrorType::DuplicateField(field.name.clone()), context);
+            }
+        }
+
+        // Fields that weren't listed are copied from the base
+        if let Some(base) = base {
+            let base = self.interpret_expression(base)?;
+            match base.value {
+                ValueData::StructValue(base_name, base_fields) if base_name == name => {
+                    for (field, value) in base_fields {
+                        values.entry(field).or_insert(value);
+                    }
+                },
+                _ => return fail(RuntimeErrorType::TypeMismatch { expected: struct_type, found: base.type_ }, context),
+            }
+        }
+
+        let mut bindings = HashMap::new();
+        let parameters: Vec<&String> = type_parameters.iter().collect();
+        let mut ordered = Vec::new();
+        for field in &declared {
+            let value = match values.remove(&field.name) {
+                Some(value) => value,
+                None => return error(SyntaxErrorType::MissingField { struct_: name.to_string(), field: field.name.clone() }, context),
+            };
+            Self::unify(&field.type_, &value.type_, &parameters, &mut bindings, context)?;
+            Self::check_type(&field.type_.substitute(&bindings), &value, context)?;
+            ordered.push((field.name.clone(), value));
+        }
+
+        let type_ = if type_parameters.is_empty() {
+            struct_type
+        } else {
+            let types = type_parameters.iter().map(|parameter| bindings.get(parameter).cloned().unwrap_or(Type::Inferred)).collect();
+            Type::GenericType { name: name.to_string(), types }
+        };
+        Ok(Value::new(type_, ValueData::StructValue(name.to_string(), ordered)))
+    }
+
+    fn construct_variant(&mut self, path: &[PathSegment], payload: Vec<Value>, context: &TokenContext) -> Evaluation {
+        let (enum_, variant) = match PathSegment::variant(path) {
+            Some(names) => names,
+            None => return error(SyntaxErrorType::UndefinedVariable(PathSegment::path_as_string(path)), context),
+        };
+        let (type_parameters, fields) = match self.enums.get(enum_) {
+            Some(enum_) => enum_.clone(),
+            None => return error(SyntaxErrorType::UndefinedVariable(PathSegment::path_as_string(path)), context),
+        };
+        let field = match fields.iter().find(|field| field.name == *variant) {
+            Some(field) => field,
+            None => return error(SyntaxErrorType::UndefinedVariant { enum_: enum_.clone(), variant: variant.clone() }, context),
+        };
+        if field.types.len() != payload.len() {
+            return error(SyntaxErrorType::VariantArityMismatch {
+                variant: PathSegment::path_as_string(path),
+                expected: field.types.len(),
+                found: payload.len(),
+            }, context);
+        }
+
+        let mut bindings = HashMap::new();
+        let parameters: Vec<&String> = type_parameters.iter().collect();
+        for (type_, value) in field.types.iter().zip(&payload) {
+            Self::unify(type_, &value.type_, &parameters, &mut bindings, context)?;
+            Self::check_type(&type_.substitute(&bindings), value, context)?;
+        }
+
+        // Type parameters that don't appear in the payload stay unknown, as
+        // in `Option::None`
+        let type_ = if type_parameters.is_empty() {
+            Type::Type { name: enum_.clone() }
+        } else {
+            let types = type_parameters.iter().map(|parameter| bindings.get(parameter).cloned().unwrap_or(Type::Inferred)).collect();
+            Type::GenericType { name: enum_.clone(), types }
+        };
+        Ok(Value::new(type_, ValueData::EnumValue(enum_.clone(), variant.clone(), payload)))
+    }
+
+    /// Finds a named struct field or a numbered tuple field
+    fn field<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+        match &value.value {
+            ValueData::TupleValue(elements) => elements.get(name.parse::<usize>().ok()?),
+            ValueData::StructValue(_, fields) => fields.iter().find(|(field, _)| field == name).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn field_mut<'a>(value: &'a mut Value, name: &str) -> Option<&'a mut Value> {
+        match &mut value.value {
+            ValueData::TupleValue(elements) => elements.get_mut(name.parse::<usize>().ok()?),
+            ValueData::StructValue(_, fields) => fields.iter_mut().find(|(field, _)| field == name).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Stores `value` in the place `target` refers to. Values are copied
+    /// rather than shared, so writing to a field or element replaces the whole
+    /// value of the variable that holds it.
+    fn assign(&mut self, target: &Expression, value: Value, context: &TokenContext) -> Result<(), Interrupt> {
+        let replace = |place: &mut Value, value: Value| {
+            if !value.type_.conforms_to(&place.type_) {
+                return fail(RuntimeErrorType::TypeMismatch { expected: place.type_.clone(), found: value.type_ }, context);
+            }
+            *place = value;
+            Ok(())
+        };
+
+        match target {
+            Expression::Variable { name, context: _ } => self.set_value(name, value, context),
+            Expression::FieldExpression { left, right, context: field_context } => {
+                let mut container = self.interpret_expression(left)?;
+                match Self::field_mut(&mut container, right) {
+                    Some(place) => replace(place, value)?,
+                    None => return Self::no_such_field(container, right, field_context),
+                }
+                self.assign(left, container, context)
+            },
+            Expression::IndexExpression { indexed, argument, context: index_context } => {
+                let mut container = self.interpret_expression(indexed)?;
+                let index = self.interpret_expression(argument)?;
+                let index = match index.value {
+                    ValueData::IntegerValue(index) => index,
+                    _ => return fail(RuntimeErrorType::TypeMismatch { expected: Type::UIntSize, found: index.type_ }, index_context),
+                };
+                match &mut container.value {
+                    ValueData::ArrayValue(elements) => {
+                        let length = elements.len();
+                        match usize::try_from(index).ok().and_then(|index| elements.get_mut(index)) {
+                            Some(place) => replace(place, value)?,
+                            None => return fail(RuntimeErrorType::IndexOutOfBounds { index, length }, index_context),
+                        }
+                    },
+                    _ => return error(SyntaxErrorType::NotIndexable(container.type_), index_context),
+                }
+                self.assign(indexed, container, context)
+            },
+            _ => error(SyntaxErrorType::InvalidAssignmentTarget, context),
+        }
+    }
+
+    /// Runs the body of the loop labeled `label` once, returning the value the
+    /// loop is left with if it was broken out of
+    fn interpret_loop_body(&mut self, body: &Block, label: &Option<String>) -> Result<Option<Value>, Interrupt> {
+        self.step()?;
+        let targets = |target: &Option<String>| target.is_none() || target == label;
+        match self.interpret_block(body) {
+            Ok(_) => Ok(None),
+            Err(Interrupt::Continue(target)) if targets(&target) => Ok(None),
+            Err(Interrupt::Break(target, value)) if targets(&target) => Ok(Some(*value)),
+            Err(interrupt) => Err(interrupt),
+        }
+    }
+
+    fn interpret_optional(&mut self, expression: &Option<Box<Expression>>) -> Evaluation {
+        match expression {
+            Some(expression) => self.interpret_expression(expression),
+            None => Ok(Value::unit()),
+        }
+    }
+
+    fn interpret_arguments(&mut self, arguments: &[Expression]) -> Result<Vec<Value>, Interrupt> {
+        let mut values = Vec::new();
+        for argument in arguments {
+            values.push(self.interpret_expression(argument)?);
+        }
+        Ok(values)
+    }
+
+    fn interpret_condition(&mut self, condition: &Expression, context: &TokenContext) -> Result<bool, Interrupt> {
+        let value = self.interpret_expression(condition)?;
+        match value.value {
+            ValueData::BooleanValue(x) => Ok(x),
+            _ => fail(RuntimeErrorType::TypeMismatch { expected: Type::Boolean, found: value.type_ }, context),
+        }
+    }
+
+    fn interpret_binary_op(&mut self, op: Operator, left: Value, right: Value, context: &TokenContext) -> Evaluation {
+        use ValueData::*;
+
+        let boolean = |x: bool| Ok(Value::new(Type::Boolean, BooleanValue(x)));
+        let overflow = || fail(RuntimeErrorType::IntegerOverflow, context);
+
+        match (op, &left.value, &right.value) {
+            (Operator::EqualOperator, _, _) => boolean(left.equals(&right)),
+            (Operator::NotEqualOperator, _, _) => boolean(!left.equals(&right)),
+
+            (_, IntegerValue(x), IntegerValue(y)) => {
+                let (x, y) = (*x, *y);
+                let result = match op {
+                    Operator::AddOperator => x.checked_add(y),
+                    Operator::SubtractOperator => x.checked_sub(y),
+                    Operator::MultiplyOperator => x.checked_mul(y),
+                    Operator::DivideOperator | Operator::ModuloOperator if y == 0 => {
+                        return fail(RuntimeErrorType::DivisionByZero, context);
+                    },
+                    Operator::DivideOperator => x.checked_div(y),
+                    Operator::ModuloOperator => x.checked_rem(y),
+                    Operator::ExponentOperator => u32::try_from(y).ok().and_then(|y| x.checked_pow(y)),
+                    Operator::BitwiseAndOperator => Some(x & y),
+                    Operator::BitwiseOrOperator => Some(x | y),
+                    Operator::BitwiseXorOperator => Some(x ^ y),
+                    Operator::LeftShiftOperator => u32::try_from(y).ok().and_then(|y| x.checked_shl(y)),
+                    Operator::RightShiftOperator => u32::try_from(y).ok().and_then(|y| x.checked_shr(y)),
+                    Operator::LessThanOperator => return boolean(x < y),
+                    Operator::GreaterThanOperator => return boolean(x > y),
+                    Operator::LessEqualOperator => return boolean(x <= y),
+                    Operator::GreaterEqualOperator => return boolean(x >= y),
+                    _ => return error(SyntaxErrorType::InvalidOperands(left.type_, right.type_), context),
+                };
+                match result {
+                    Some(value) => Self::integer(left.type_, value, context),
+                    None => overflow(),
+                }
+            },
+
+            (_, FloatValue(x), FloatValue(y)) => {
+                let (x, y) = (*x, *y);
+                let result = match op {
+                    Operator::AddOperator => x + y,
+                    Operator::SubtractOperator => x - y,
+                    Operator::MultiplyOperator => x * y,
+                    Operator::DivideOperator => x / y,
+                    Operator::ModuloOperator => x % y,
+                    Operator::ExponentOperator => x.powf(y),
+                    Operator::LessThanOperator => return boolean(x < y),
+                    Operator::GreaterThanOperator => return boolean(x > y),
+                    Operator::LessEqualOperator => return boolean(x <= y),
+                    Operator::GreaterEqualOperator => return boolean(x >= y),
+                    _ => return error(SyntaxErrorType::InvalidOperands(left.type_, right.type_), context),
+                };
+                Ok(Value::new(left.type_, FloatValue(result)))
+            },
+
+            (Operator::AddOperator, StringValue(x), StringValue(y)) => {
+                Ok(Value::new(left.type_.clone(), StringValue(format!("{}{}", x, y))))
+            },
+            (Operator::LessThanOperator, StringValue(x), StringValue(y)) => boolean(x < y),
+            (Operator::GreaterThanOperator, StringValue(x), StringValue(y)) => boolean(x > y),
+            (Operator::LessEqualOperator, StringValue(x), StringValue(y)) => boolean(x <= y),
+            (Operator::GreaterEqualOperator, StringValue(x), StringValue(y)) => boolean(x >= y),
+            (Operator::LessThanOperator, CharValue(x), CharValue(y)) => boolean(x < y),
+            (Operator::GreaterThanOperator, CharValue(x), CharValue(y)) => boolean(x > y),
+            (Operator::LessEqualOperator, CharValue(x), CharValue(y)) => boolean(x <= y),
+            (Operator::GreaterEqualOperator, CharValue(x), CharValue(y)) => boolean(x >= y),
+
+            _ => error(SyntaxErrorType::InvalidOperands(left.type_, right.type_), context),
+        }
     }
-    
-    fn interpret_expression(&mut self, tree: Result<Expression, SyntaxErrorCollector>) -> Result<Value, SyntaxErrorCollector> {
-        match tree {
-            Ok(Expression::IntLiteral { value: x }) => Ok(Value::new(Type::IntSize, ValueData::IntegerValue(x))),
-            Ok(_) => Err(SyntaxErrorCollector::new()),
-            Err(x) => Err(x),
+
+    /// An integer of type `type_`, which overflows if `value` doesn't fit in it
+    fn integer(type_: Type, value: i128, context: &TokenContext) -> Evaluation {
+        match type_.integer_range() {
+            Some((min, max)) if !(min..=max).contains(&value) => fail(RuntimeErrorType::IntegerOverflow, context),
+            _ => Ok(Value::new(type_, ValueData::IntegerValue(value))),
+        }
+    }
+
+    fn interpret_unary_op(&mut self, op: Operator, child: Value, context: &TokenContext) -> Evaluation {
+        match (op, &child.value) {
+            (Operator::NegateOperator, ValueData::IntegerValue(x)) => match x.checked_neg() {
+                Some(x) => Self::integer(child.type_, x, context),
+                None => fail(RuntimeErrorType::IntegerOverflow, context),
+            },
+            (Operator::NegateOperator, ValueData::FloatValue(x)) => {
+                Ok(Value::new(child.type_, ValueData::FloatValue(-x)))
+            },
+            // Only the bits of the type are flipped for unsigned integers
+            (Operator::BitwiseNotOperator, ValueData::IntegerValue(x)) => match child.type_.integer_range() {
+                Some((0, max)) => Ok(Value::new(child.type_, ValueData::IntegerValue(max ^ x))),
+                _ => Ok(Value::new(child.type_, ValueData::IntegerValue(!x))),
+            },
+            (Operator::NotOperator, ValueData::BooleanValue(x)) => {
+                Ok(Value::new(child.type_, ValueData::BooleanValue(!x)))
+            },
+            _ => error(SyntaxErrorType::InvalidOperand(child.type_), context),
         }
     }
 }
@@ -74,6 +1369,21 @@ impl TreeWalker {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::lexer::Lexer;
+
+    fn run(code: &str) -> (Result<Value, ProgramError>, String) {
+        let mut lexer = Lexer::new("test.px".to_string(), code.to_string());
+        let mut tree_walker = TreeWalker::new();
+        let result = tree_walker.interpret(lexer.parse());
+        (result, tree_walker.output)
+    }
+
+    fn runtime_error(result: Result<Value, ProgramError>) -> RuntimeError {
+        match result {
+            Err(ProgramError::Runtime(error)) => *error,
+            _ => panic!("expected a runtime error"),
+        }
+    }
 
     #[test]
     #[should_panic]
@@ -189,4 +1499,1145 @@ mod tests {
         tree_walker.write("hello\n".to_string());
         assert_eq!(tree_walker.output, "test\nhello\n".to_string());
     }
+
+    #[test]
+    fn interpret_return_tuple() {
+        let (result, output) = run("
+            fn divide(x: isize, y: isize): (isize, isize) = (x / y, x % y);
+
+            fn main() {
+                let (quotient, remainder) = divide(17, 5);
+                println(quotient, remainder);
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "3 2\n");
+    }
+
+    #[test]
+    fn interpret_tuple_fields() {
+        let (_, output) = run("
+            fn main() {
+                let pair = ((1, 2), (true, 'c'));
+                println(pair.0.1, pair.1.0, pair.1);
+            }
+        ");
+
+        assert_eq!(output, "2 true (true, c)\n");
+    }
+
+    #[test]
+    fn interpret_match_tuple_pattern() {
+        let (_, output) = run("
+            fn describe(pair: (isize, bool)): isize = match pair {
+                (0, _) => 0,
+                (n, true) => n,
+                (n, false) => -n,
+            };
+
+            fn main() {
+                println(describe((0, true)), describe((4, true)), describe((4, false)));
+            }
+        ");
+
+        assert_eq!(output, "0 4 -4\n");
+    }
+
+    #[test]
+    fn interpret_unit_function() {
+        let (result, _) = run("
+            fn nothing() {}
+
+            fn main(): () = nothing();
+        ");
+
+        assert!(matches!(result, Ok(Value { type_, value: ValueData::TupleValue(x) })
+            if type_ == Type::unit() && x.is_empty()));
+    }
+
+    #[test]
+    fn interpret_function_as_value() {
+        let (_, output) = run("
+            fn add(x: isize, y: isize): isize = x + y;
+
+            fn main() {
+                let do_thing = add;
+                println(do_thing(2, 3));
+            }
+        ");
+
+        assert_eq!(output, "5\n");
+    }
+
+    #[test]
+    fn interpret_higher_order_function() {
+        let (result, output) = run("
+            fn add(x: isize, y: isize): isize = x + y;
+
+            fn apply(f: fn(isize, isize): isize, x: isize, y: isize): isize {
+                f(x, y)
+            }
+
+            fn main() {
+                let offset = 10;
+                println(apply(add, 2, 3), apply(|a: isize, b: isize| a * b + offset, 2, 3));
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "5 16\n");
+    }
+
+    #[test]
+    fn interpret_returned_closure() {
+        let (_, output) = run("
+            fn adder(n: isize): fn(isize): isize = |x: isize| x + n;
+
+            fn main() {
+                println(adder(2)(40));
+            }
+        ");
+
+        assert_eq!(output, "42\n");
+    }
+
+    #[test]
+    fn interpret_function_type_mismatch() {
+        let (result, _) = run("
+            fn negate(x: bool): bool = not x;
+
+            fn apply(f: fn(isize): isize, x: isize): isize = f(x);
+
+            fn main() {
+                apply(negate, 1);
+            }
+        ");
+
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::TypeMismatch { .. }, .. }])));
+    }
+
+    #[test]
+    fn interpret_generic_function() {
+        let (result, output) = run("
+            fn max<T: Ord>(a: T, b: T): T = if a > b { a } else { b };
+
+            fn main() {
+                println(max(3, 7), max('z', 'a'), max(2.5, 1.5));
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "7 z 2.5\n");
+    }
+
+    #[test]
+    fn interpret_inferred_types() {
+        let (result, output) = run("
+            enum Option<T> { Some(T), None }
+
+            fn none<T>(): Option<T> = Option::None;
+
+            fn main() {
+                let scale = |x, factor| x * factor;
+                let nothing: Option<char> = none();
+                let found = match nothing {
+                    Option::Some(c) => c,
+                    Option::None => 'n',
+                };
+                println(scale(2.5, 2.0), found);
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "5 n\n");
+    }
+
+    #[test]
+    fn interpret_typed_literals() {
+        let (result, output) = run("
+            const LIMIT: u8 = 250;
+
+            fn main() {
+                let small = 5;
+                let byte: u8 = small;
+                println(~byte, LIMIT - byte);
+                println(LIMIT + byte + 1);
+            }
+        ");
+
+        assert!(matches!(runtime_error(result).error_type, RuntimeErrorType::IntegerOverflow));
+        assert_eq!(output, "250 245\n");
+
+        let (result, output) = run("
+            fn main() {
+                let largest: u64 = 18446744073709551615;
+                let lowest: i128 = -170141183460469231731687303715884105727;
+                println(largest - 1, lowest, ~largest);
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "18446744073709551614 -170141183460469231731687303715884105727 0\n");
+
+        // Integer literals can be used where a float is expected
+        let (result, output) = run("
+            fn average(x: f64, y: f64): f64 = (x + y) / 2;
+
+            fn main() {
+                let y: f32 = 1;
+                println(y / 4, average(2, 3));
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "0.25 2.5\n");
+    }
+
+    #[test]
+    fn interpret_generic_conflicting_arguments() {
+        let (result, _) = run("
+            fn max<T: Ord>(a: T, b: T): T = if a > b { a } else { b };
+
+            fn main() {
+                max(3, 'a');
+            }
+        ");
+
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::TypeMismatch { expected: Type::Char, found: Type::IntSize }, .. }])));
+    }
+
+    #[test]
+    fn interpret_unsatisfied_bound() {
+        let (result, _) = run("
+            fn max<T: Ord>(a: T, b: T): T = if a > b { a } else { b };
+
+            fn main() {
+                max(true, false);
+            }
+        ");
+
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::UnsatisfiedTraitBound { type_: Type::Boolean, .. }, .. }])));
+    }
+
+    #[test]
+    fn interpret_user_trait_bound() {
+        let (result, output) = run("
+            trait Describe {
+                fn describe(x: Self): isize;
+                fn twice(x: Self): isize = x.describe() * 2;
+            }
+
+            instance Describe bool {
+                fn describe(x: bool): isize = if x { 1 } else { 0 };
+            }
+
+            fn total<T: Describe>(a: T, b: T): isize = a.twice() + b.describe();
+
+            fn main() {
+                println(total(true, true));
+                total(1, 2);
+            }
+        ");
+
+        // The bound is checked before the program runs
+        assert_eq!(output, "");
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::UnsatisfiedTraitBound { type_: Type::IntSize, .. }, .. }])));
+    }
+
+    #[test]
+    fn interpret_unreached_unsatisfied_bound() {
+        let (result, _) = run("
+            trait Show {
+                fn show(x: Self): str;
+            }
+
+            struct P { x: isize }
+
+            fn display<T: Show>(x: T): str = x.show();
+
+            fn main() {
+                if false { display(P { x: 1 }); }
+            }
+        ");
+
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::UnsatisfiedTraitBound { type_: Type::Type { name }, .. }, context, .. }]
+                if name == "P" && context.line == 11)));
+    }
+
+    #[test]
+    fn interpret_undefined_bound() {
+        let (result, _) = run("
+            fn show<T: Missing>(a: T): T = a;
+
+            fn main() {}
+        ");
+
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::UndefinedTrait(name), .. }] if name == "Missing")));
+    }
+
+    #[test]
+    fn interpret_constants() {
+        let (result, output) = run("
+            fn square(x: isize): isize = x * x;
+            const AREA: isize = square(SIDE);
+            const SIDE: isize = 4;
+
+            fn main() {
+                println(AREA);
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "16\n");
+    }
+
+    #[test]
+    fn interpret_static_single_initialization() {
+        let (result, output) = run("
+            fn load(): isize {
+                println(\"loading\");
+                42
+            }
+
+            static ANSWER: isize = load();
+
+            fn main() {
+                println(\"start\");
+                println(ANSWER + ANSWER);
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "start\nloading\n84\n");
+    }
+
+    #[test]
+    fn interpret_static_cycle() {
+        let (result, _) = run("
+            static A: isize = B;
+            static B: isize = A;
+
+            fn main() {
+                println(A);
+            }
+        ");
+
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::StaticCycle(cycle), .. }] if cycle == &["A", "B", "A"])));
+
+        // The cycle is found before the program runs, even if nothing reads
+        // the statics
+        let (result, output) = run("
+            static A: isize = through_function();
+            fn through_function(): isize = A + 1;
+
+            fn main() {
+                println(1);
+            }
+        ");
+
+        assert_eq!(output, "");
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::StaticCycle(cycle), context, .. }]
+                if cycle == &["A", "A"] && context.line == 2)));
+    }
+
+    #[test]
+    fn interpret_arrays() {
+        let (result, output) = run("
+            fn main() {
+                let a: [i32; 3] = [1, 2, 3];
+                let grid = [[0; 2]; 3];
+                println(a, grid, a[2], grid[1][0]);
+                println(a == [1, 2, 3], [] == []);
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "[1, 2, 3] [[0, 0], [0, 0], [0, 0]] 3 0\ntrue true\n");
+    }
+
+    #[test]
+    fn interpret_array_out_of_bounds() {
+        let (result, output) = run("
+            fn main() {
+                let a = [1, 2, 3];
+                let i = 3;
+                println(a[0]);
+                println(a[i]);
+            }
+        ");
+
+        assert_eq!(output, "1\n");
+        assert!(matches!(runtime_error(result), RuntimeError {
+            error_type: RuntimeErrorType::IndexOutOfBounds { index: 3, length: 3 },
+            context,
+            stack: _,
+        } if context.line == 6 && context.column == 26));
+    }
+
+    #[test]
+    fn interpret_array_length_mismatch() {
+        let (result, _) = run("
+            fn first(a: [isize; 2]): isize = a[0];
+
+            fn main() {
+                let n = 3;
+                first([0; n]);
+            }
+        ");
+
+        assert!(matches!(runtime_error(result), RuntimeError {
+            error_type: RuntimeErrorType::TypeMismatch {
+                expected: Type::Array { length: 2, .. },
+                found: Type::Array { length: 3, .. },
+            },
+            ..
+        }));
+    }
+
+    #[test]
+    fn interpret_structs() {
+        let (result, output) = run("
+            struct Point { x: isize, y: isize }
+
+            fn main() {
+                let x = 1;
+                let p = Point { x, y: 2 };
+                let q = Point { y: 5, ..p };
+                println(p, q.x, q.y);
+                println(p == Point { y: 2, x: 1 }, p == q);
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "Point { x: 1, y: 2 } 1 5\ntrue false\n");
+    }
+
+    #[test]
+    fn interpret_struct_writes() {
+        let (result, output) = run("
+            struct Point { x: isize, y: isize }
+            struct Shape { points: [Point; 2] }
+
+            fn main() {
+                let p = Point { x: 0, y: 0 };
+                let shape = Shape { points: [p; 2] };
+                p.x = 3;
+                shape.points[1].y += 4;
+                println(p, shape.points[0], shape.points[1]);
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "Point { x: 3, y: 0 } Point { x: 0, y: 0 } Point { x: 0, y: 4 }\n");
+    }
+
+    #[test]
+    fn interpret_generic_struct() {
+        let (result, output) = run("
+            struct Pair<T> { first: T, second: T }
+
+            fn main() {
+                let pair: Pair<bool> = Pair { first: true, second: false };
+                println(pair.second);
+                Pair { first: 1, second: true };
+            }
+        ");
+
+        assert_eq!(output, "");
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::TypeMismatch { expected: Type::Boolean, found: Type::IntSize }, .. }])));
+    }
+
+    #[test]
+    fn interpret_missing_field() {
+        let (result, _) = run("
+            struct Point { x: isize, y: isize }
+
+            fn main() {
+                Point { x: 1 };
+            }
+        ");
+
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::MissingField { struct_, field }, .. }]
+                if struct_ == "Point" && field == "y")));
+
+        // The fields are checked before the program runs
+        let (result, output) = run("
+            struct Point { x: isize, y: isize }
+
+            fn main() {
+                println(1);
+                if false {
+                    Point { x: 1, y: 2, y: 3 };
+                }
+            }
+        ");
+
+        assert_eq!(output, "");
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::DuplicateField(field), context, .. }]
+                if field == "y" && context.line == 7)));
+    }
+
+    #[test]
+    fn interpret_enums() {
+        let (result, output) = run("
+            enum Shape { Circle(f64), Rectangle(f64, f64), Empty }
+
+            fn area(shape: Shape): f64 = match shape {
+                Shape::Circle(r) => 3.0 * r * r,
+                Shape::Rectangle(w, h) => w * h,
+                Shape::Empty => 0.0,
+            };
+
+            fn main() {
+                let shapes = [Shape::Circle(1.0), Shape::Rectangle(2.0, 3.0), Shape::Empty];
+                println(shapes[0], shapes[2], area(shapes[1]));
+                println(shapes[2] == Shape::Empty, shapes[0] == Shape::Circle(2.0));
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "Shape::Circle(1) Shape::Empty 6\ntrue false\n");
+    }
+
+    #[test]
+    fn interpret_nested_enum_patterns() {
+        let (result, output) = run("
+            enum Option<T> { Some(T), None }
+
+            fn main() {
+                let pair = Option::Some((Option::Some(1), Option::None));
+                let Option::Some((Option::Some(x), y)) = pair;
+                println(x, y);
+
+                let none: Option<isize> = Option::None;
+                match none {
+                    Option::Some(_) => println(\"some\"),
+                    Option::None => println(\"none\"),
+                }
+                let Option::Some(z) = none;
+            }
+        ");
+
+        assert_eq!(output, "1 Option::None\nnone\n");
+        assert!(matches!(runtime_error(result), RuntimeError { error_type: RuntimeErrorType::RefutablePattern, .. }));
+    }
+
+    #[test]
+    fn interpret_immutable_assignment() {
+        let (result, _) = run("
+            const LIMIT: isize = 3;
+
+            fn main() {
+                let x = 1;
+                x = LIMIT;
+                LIMIT = x;
+            }
+        ");
+
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::ImmutableAssignment(name), .. }] if name == "LIMIT")));
+    }
+
+    #[test]
+    fn interpret_refutable_let() {
+        let (result, _) = run("
+            fn split(pair: (isize, isize)): isize {
+                let (a, 1) = pair;
+                a
+            }
+
+            fn main() {
+                split((1, 2));
+            }
+        ");
+
+        let error = runtime_error(result);
+        assert!(matches!(error.error_type, RuntimeErrorType::RefutablePattern));
+        assert_eq!(error.backtrace().iter().map(|(function, context)| (*function, context.line)).collect::<Vec<_>>(), [("split", 3), ("main", 8)]);
+
+        let (result, _) = run("
+            fn name(n: isize): str = match n {
+                1 => \"one\",
+                2 => \"two\",
+            };
+
+            fn main() {
+                name(3);
+            }
+        ");
+
+        let error = runtime_error(result);
+        assert!(matches!(error.error_type, RuntimeErrorType::NonExhaustiveMatch));
+        assert_eq!(error.backtrace().iter().map(|(function, context)| (*function, context.line)).collect::<Vec<_>>(), [("name", 2), ("main", 8)]);
+    }
+
+    #[test]
+    fn interpret_inline_modules() {
+        let (result, output) = run("
+            mod geometry {
+                pub struct Point { pub x: isize, pub y: isize }
+
+                pub enum Shape { Dot(Point), Empty }
+
+                pub fn origin(): Point = Point { x: 0, y: 0 };
+
+                pub fn norm(p: Point): isize = p.x * p.x + p.y * p.y;
+
+                pub mod scale {
+                    fn double(p: super::Point): super::Point = super::Point { x: p.x * 2, y: p.y * 2 };
+                    pub fn norm(p: super::Point): isize = super::norm(double(p));
+                }
+            }
+
+            use geometry::Point;
+            use geometry::scale::norm as scaled_norm;
+
+            fn main() {
+                let p = Point { x: 1, y: 2 };
+                println(geometry::norm(p), scaled_norm(p), geometry::origin().x);
+                match geometry::Shape::Dot(p) {
+                    geometry::Shape::Dot(q) => println(q.y),
+                    geometry::Shape::Empty => println(\"empty\"),
+                }
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "5 20 0\n2\n");
+    }
+
+    #[test]
+    fn interpret_module_globs() {
+        let (result, output) = run("
+            mod math {
+                pub const TWO: isize = 2;
+                pub fn square(x: isize): isize = x * x;
+                pub fn quad(x: isize): isize = self::square(square(x));
+            }
+
+            use math::*;
+
+            fn main() {
+                println(square(TWO), quad(TWO));
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "4 16\n");
+    }
+
+    #[test]
+    fn interpret_unresolved_paths() {
+        let (result, _) = run("
+            mod a {
+                pub fn f() {}
+            }
+
+            use a::g;
+
+            fn main() {
+                a::h();
+                super::f();
+            }
+        ");
+
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..], [
+            SyntaxError { error_type: SyntaxErrorType::UnresolvedPath(first), .. },
+            SyntaxError { error_type: SyntaxErrorType::UnresolvedPath(second), .. },
+            SyntaxError { error_type: SyntaxErrorType::UnresolvedPath(third), .. },
+        ] if first == "a::g" && second == "a::h" && third == "super::f")));
+    }
+
+    #[test]
+    fn interpret_private_items() {
+        let (result, output) = run("
+            mod bank {
+                pub struct Account { pub owner: str, balance: isize }
+
+                pub fn open(owner: str): Account = Account { owner, balance: secret() };
+
+                fn secret(): isize = 100;
+
+                mod audit {
+                    pub fn check(): isize = super::secret();
+                }
+
+                pub fn review(): isize = audit::check();
+            }
+
+            fn main() {
+                let account = bank::open(\"ann\");
+                println(account.owner, bank::review());
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "ann 100\n");
+
+        let (result, _) = run("
+            mod bank {
+                pub struct Account { pub owner: str, balance: isize }
+                fn secret(): isize = 100;
+                mod audit {}
+            }
+
+            use bank::secret;
+
+            fn main() {
+                let account = bank::Account { owner: \"ann\", balance: 0 };
+                println(account.balance, bank::audit::check());
+            }
+        ");
+
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..], [
+            SyntaxError { error_type: SyntaxErrorType::PrivateItem { item: first, module: a }, .. },
+            SyntaxError { error_type: SyntaxErrorType::PrivateItem { item: second, module: b }, .. },
+            SyntaxError { error_type: SyntaxErrorType::PrivateItem { item: third, .. }, .. },
+            SyntaxError { error_type: SyntaxErrorType::PrivateItem { item: fourth, .. }, .. },
+        ] if first == "secret" && a == "bank"
+            && second == "Account.balance" && b == "bank"
+            && third == "Account.balance"
+            && fourth == "audit")));
+    }
+
+    #[test]
+    fn interpret_macros() {
+        let (result, output) = run("
+            macro square {
+                ($x:expr) => { $x * $x }
+            }
+
+            macro sum {
+                () => { 0 },
+                ($first:expr $(, $rest:expr)*) => { $first $(+ $rest)* },
+            }
+
+            macro count {
+                () => { 0 },
+                ($x:tt $($rest:tt)*) => { 1 + count!($($rest)*) },
+            }
+
+            macro getter {
+                ($name:ident, $type_:type, $value:expr) => { fn $name(): $type_ = $value; }
+            }
+
+            getter!(answer, [isize; 2], [4, 2]);
+
+            fn main() {
+                println(square!(1 + 2), sum!(), sum!(1, 2, 3), count!(a (b c) d), answer());
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "9 0 6 3 [4, 2]\n");
+    }
+
+    #[test]
+    fn interpret_macro_hygiene() {
+        let (result, output) = run("
+            macro swap {
+                ($a:ident, $b:ident) => { { let tmp = $a; $a = $b; $b = tmp; } }
+            }
+
+            macro twice {
+                ($e:expr) => { { let x = 2; x * $e } }
+            }
+
+            fn main() {
+                let tmp = 1;
+                let other = 2;
+                swap!(tmp, other);
+                let x = 5;
+                println(tmp, other, twice!(x));
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "2 1 10\n");
+
+        // Renamed variables can be passed on to other macros, but not named by
+        // the code around the invocation
+        let (result, output) = run("
+            macro reveal {
+                ($x:tt) => { { let secret = 5; $x } }
+            }
+
+            fn main() {
+                println(reveal!((secret#0)));
+            }
+        ");
+
+        assert!(matches!(result, Err(ProgramError::Syntax(_))));
+        assert_eq!(output, "");
+
+        let (result, output) = run("
+            macro outer {
+                ($e:expr) => { { let y = 3; twice!(y) + $e } }
+            }
+
+            macro twice {
+                ($e:expr) => { $e * 2 }
+            }
+
+            fn main() {
+                println(outer!(1));
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "7\n");
+    }
+
+    #[test]
+    fn interpret_statement_macros() {
+        let (result, output) = run("
+            macro swap_let {
+                ($a:ident, $b:ident) => { let t = $a; $a = $b; $b = t; }
+            }
+
+            macro double {
+                ($x:expr) => { $x + $x }
+            }
+
+            fn main() {
+                let t = 1;
+                let u = 2;
+                swap_let!(t, u);
+                double!(1) * 3;
+                println(t, u, { double!(2) * 3 }, { double!(5) });
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "2 1 12 10\n");
+    }
+
+    #[test]
+    fn interpret_macro_backtrace() {
+        let (result, _) = run("
+            macro first {
+                ($a:expr) => { $a[3] }
+            }
+
+            macro outer {
+                ($a:expr) => { first!($a) }
+            }
+
+            fn main() {
+                println(outer!([1, 2]));
+            }
+        ");
+
+        let RuntimeError { error_type: RuntimeErrorType::IndexOutOfBounds { .. }, context, stack: _ } = runtime_error(result) else {
+            panic!("expected an out of bounds error");
+        };
+        let backtrace = context.backtrace();
+        assert!(matches!(&backtrace[..], [(first, inner), (outer, call)]
+            if first == "first" && outer == "outer" && inner.expansion.is_some() && call.line == 11 && call.column == 25));
+    }
+
+    #[test]
+    fn interpret_labeled_loops() {
+        let (result, output) = run("
+            fn main() {
+                let found = 'rows: loop {
+                    let i = 0;
+                    'columns: while true {
+                        i += 1;
+                        if i == 2 { continue 'columns; }
+                        if i == 4 { break 'rows i * 10; }
+                        println(i);
+                    }
+                };
+
+                'outer: for x in [1, 2, 3] {
+                    for y in [1, 2, 3] {
+                        if y > x { continue 'outer; }
+                        if x == 3 { break 'outer; }
+                        print(x, y, \"\");
+                    }
+                }
+                println(found);
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "1\n3\n1 1 2 1 2 2 40\n");
+    }
+
+    #[test]
+    fn interpret_invalid_labels() {
+        let (result, _) = run("
+            fn main() {
+                'outer: while true {
+                    loop { break 'outer 1; }
+                }
+                loop {
+                    let f = || { break 'outer; };
+                    continue 'inner;
+                }
+            }
+        ");
+
+        let Err(ProgramError::Syntax(collector)) = result else { panic!("expected an error") };
+        assert!(matches!(&collector.errors[..], [
+            SyntaxError { error_type: SyntaxErrorType::InvalidBreakValue, .. },
+            SyntaxError { error_type: SyntaxErrorType::UndefinedLabel(outer), .. },
+            SyntaxError { error_type: SyntaxErrorType::UndefinedLabel(inner), .. },
+        ] if outer == "outer" && inner == "inner"));
+
+        // Unlabeled jumps outside a loop are reported where they are written,
+        // even in functions that are only called from inside a loop
+        let (result, output) = run("
+            fn skip() {
+                continue;
+            }
+
+            fn main() {
+                println(1);
+                loop { skip(); }
+                let f = || { break; };
+            }
+        ");
+
+        assert_eq!(output, "");
+        let Err(ProgramError::Syntax(collector)) = result else { panic!("expected an error") };
+        assert!(matches!(&collector.errors[..], [
+            SyntaxError { error_type: SyntaxErrorType::InvalidControlFlow, context: skip, .. },
+            SyntaxError { error_type: SyntaxErrorType::InvalidControlFlow, context: closure, .. },
+        ] if (skip.line, skip.column) == (3, 17) && (closure.line, closure.column) == (9, 30)));
+    }
+
+    #[test]
+    fn interpret_defer() {
+        let (result, output) = run("
+            fn early(x: isize): isize {
+                defer { print(\"early\"); }
+                if x > 0 { return x; }
+                defer { print(\"late\"); }
+                0
+            }
+
+            fn main() {
+                defer { println(\"main\"); }
+                for i in [1, 2, 3] {
+                    defer { print(i); }
+                    defer { print(\"[\"); }
+                    if i == 1 { continue; }
+                    if i == 2 { break; }
+                }
+                println();
+                println(early(1), early(0));
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "[1[2\nearlylateearly1 0\nmain\n");
+
+        // A deferred block sees the variables declared before it, with the
+        // values they have when it runs
+        let (result, output) = run("
+            fn main() {
+                let x = 1;
+                defer { println(x); }
+                x = 2;
+                let x = 3;
+                println(x);
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "3\n2\n");
+
+        let (result, output) = run("
+            fn leave(flag: bool) {
+                defer { println(late); }
+                if flag { return; }
+                let late = 3;
+            }
+
+            fn main() {
+                leave(true);
+            }
+        ");
+
+        let Err(ProgramError::Syntax(collector)) = result else { panic!("expected an error") };
+        assert!(matches!(&collector.errors[..], [SyntaxError { error_type: SyntaxErrorType::UndefinedVariable(late), .. }] if late == "late"));
+        assert_eq!(output, "");
+    }
+
+    #[test]
+    fn interpret_defer_after_error() {
+        let (result, output) = run("
+            fn main() {
+                defer { println(\"cleanup\"); }
+                let x = [1, 2][5];
+            }
+        ");
+
+        assert!(matches!(runtime_error(result), RuntimeError { error_type: RuntimeErrorType::IndexOutOfBounds { .. }, .. }));
+        assert_eq!(output, "cleanup\n");
+
+        let (result, _) = run("
+            fn main() {
+                'outer: loop {
+                    defer { break 'outer; }
+                }
+            }
+        ");
+
+        let Err(ProgramError::Syntax(collector)) = result else { panic!("expected an error") };
+        assert!(matches!(&collector.errors[..], [SyntaxError { error_type: SyntaxErrorType::UndefinedLabel(_), .. }]));
+    }
+
+    #[test]
+    fn interpret_match_patterns() {
+        let (result, output) = run("
+            enum Shape { Circle(isize), Square(isize), Empty }
+
+            fn describe(s: Shape): str = match s {
+                Shape::Circle(r) | Shape::Square(r) if r > 10 => \"big\",
+                whole @ Shape::Circle(_) => { print(whole); \" circle\" },
+                Shape::Square(1..=3 | 5) => \"small square\",
+                Shape::Square(_) | Shape::Empty => \"other\",
+            };
+
+            fn class(c: char): isize = match c {
+                'a'..='z' => 0,
+                'A'..'Z' => 1,
+                _ => 2,
+            };
+
+            fn main() {
+                println(describe(Shape::Square(12)), describe(Shape::Circle(2)));
+                println(describe(Shape::Square(5)), describe(Shape::Square(4)), describe(Shape::Empty));
+                println(class('q'), class('Q'), class('Z'));
+            }
+        ");
+
+        assert!(result.is_ok());
+        assert_eq!(output, "Shape::Circle(2)big  circle\nsmall square other other\n0 1 2\n");
+    }
+
+    #[test]
+    fn interpret_lint_levels() {
+        let code = "
+            fn f(n: isize): isize = match n { _ => 1, 2 => 2 };
+
+            #[allow(unreachable_patterns)]
+            mod quiet {
+                pub fn g(n: isize): isize = match n { _ => 1, 2 => 2 };
+
+                #[deny(unreachable_patterns)]
+                pub fn h(n: isize): isize = match n { _ => 1, 2 => 2 };
+            }
+
+            fn main() {
+                println(f(2), quiet::g(2));
+            }
+        ";
+
+        let (result, output) = run(code);
+        assert_eq!(output, "");
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if matches!(&collector.errors[..], [
+            SyntaxError { error_type: SyntaxErrorType::DeniedLint(SyntaxWarningType::UnreachablePattern { .. }), context, suggestion: _ },
+        ] if context.line == 9)));
+
+        let mut tree_walker = TreeWalker::new();
+        tree_walker.lints.set("warnings", crate::lints::Level::Deny);
+        let code = code.replace("#[deny(unreachable_patterns)]", "#[warn(unreachable_patterns)]");
+        let result = tree_walker.interpret(Lexer::new("test.px".to_string(), code).parse());
+        assert!(matches!(result, Err(ProgramError::Syntax(collector)) if collector.errors.len() == 2));
+    }
+
+    #[test]
+    fn interpret_runtime_stack() {
+        let (result, _) = run("
+            mod math {
+                pub fn divide(x: isize, y: isize): isize = x / y;
+            }
+
+            fn average(total: isize, count: isize): isize = math::divide(total, count);
+
+            fn main() {
+                let apply = |n: isize| average(n, 0);
+                println(apply(4));
+            }
+        ");
+
+        let error = runtime_error(result);
+        assert!(matches!(error.error_type, RuntimeErrorType::DivisionByZero));
+        let backtrace: Vec<(&str, usize)> = error.backtrace().into_iter().map(|(function, context)| (function, context.line)).collect();
+        assert_eq!(backtrace, [("math::divide", 3), ("average", 6), ("<closure>", 9), ("main", 10)]);
+    }
+
+    #[test]
+    fn interpret_stack_overflow() {
+        let checker = std::thread::Builder::new().stack_size(STACK_SIZE).spawn(|| {
+            let (result, output) = run("
+                fn depth(n: isize): isize = if n == 0 { 0 } else { 1 + depth(n - 1) };
+
+                fn main() {
+                    println(depth(900));
+                    println(depth(5000));
+                }
+            ");
+
+            assert_eq!(output, "900\n");
+            let error = runtime_error(result);
+            assert!(matches!(error.error_type, RuntimeErrorType::StackOverflow));
+            assert_eq!(error.stack.len(), MAX_CALL_DEPTH);
+            assert_eq!(error.stack.last().unwrap().function, "main");
+        });
+        checker.unwrap().join().unwrap();
+    }
+
+    #[test]
+    fn interpret_panic_and_unwrap() {
+        let (result, output) = run("
+            enum Option<T> { Some(T), None }
+
+            fn main() {
+                println(unwrap(Option::Some(1)), Option::Some(2).unwrap());
+                let empty: Option<isize> = Option::None;
+                empty.unwrap();
+            }
+        ");
+
+        assert_eq!(output, "1 2\n");
+        assert!(matches!(runtime_error(result), RuntimeError { error_type: RuntimeErrorType::UnwrapNone, context, .. }
+            if context.line == 7));
+
+        let (result, _) = run("fn main() { panic(\"at\", 3); }");
+        assert!(matches!(runtime_error(result), RuntimeError { error_type: RuntimeErrorType::Panic(message), .. }
+            if message == "at 3"));
+    }
+
+    #[test]
+    fn interpret_suggestions() {
+        let suggestion = |code: &str| match run(code).0 {
+            Err(ProgramError::Syntax(collector)) => collector.errors[0].suggestion.clone().map(|suggestion| (suggestion.original, suggestion.replacement, suggestion.applicable)),
+            _ => None,
+        };
+        let owned = |original: &str, replacement: &str, applicable| Some((original.to_string(), replacement.to_string(), applicable));
+
+        assert_eq!(suggestion("fn main() { let count = 1; println(cuont); }"), owned("cuont", "count", true));
+        assert_eq!(suggestion("fn main() { prnitln(1); }"), owned("prnitln", "println", true));
+        assert_eq!(suggestion("struct P { x: isize, y: isize } fn main() { println(P { x: 1, y: 2 }.z); }"), owned("z", "x", false));
+        assert_eq!(suggestion("mod m { pub fn area() {} } fn main() { m::aera(); }"), owned("m::aera", "m::area", true));
+        assert_eq!(suggestion("strcut P { x: isize } fn main() {}"), owned("strcut", "struct", true));
+        assert_eq!(suggestion("fn main() { println(nothing_like_it); }"), None);
+    }
 }
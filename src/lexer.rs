@@ -1,30 +1,80 @@
-use crate::tokens::{Token, TokenContext, TokenContent};
+use crate::macros::Macro;
+use crate::tokens::{Expansion, Token, TokenContext, TokenContent};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
 
 const MAX_OPERATOR_LENGTH: usize = 3;
 
 pub struct Lexer {
     chars: Vec<char>,
     pub context: TokenContext,
+    /// Cleared while parsing conditions, where a `{` after a name starts the
+    /// body rather than a struct literal
+    pub struct_literals: bool,
+    /// Macros defined so far, which can be used by the code after them
+    pub macros: HashMap<String, Rc<Macro>>,
+    /// The number of macros expanded so far, used to rename the variables
+    /// each expansion introduces
+    pub expansions: usize,
+    /// Set while lexing the arguments of an invocation that a macro produced,
+    /// which may hold variables another expansion renamed
+    pub renamed_names: bool,
+    /// Set when a name in code outside of any expansion is followed by `#1`
+    /// and so on, which is never a renamed variable there
+    pub fake_renamed_name: bool,
 }
 
 #[derive(Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum ProgramContext {
     NormalContext,
     TypeContext,
+    FieldContext,
 }
 
+#[derive(Debug, Clone)]
 pub struct LexerError {
     pub error_type: LexerErrorType,
     pub context: TokenContext,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub enum LexerErrorType {
     UnclosedStringError,
     UnclosedCharError,
     OverlengthyCharError,
     EmptyCharError,
     UnknownTokenStartError,
+    /// An integer literal too large for any integer type
+    IntegerTooLarge(String),
+}
+
+impl LexerErrorType {
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexerErrorType::UnclosedStringError => "PX0001",
+            LexerErrorType::UnclosedCharError => "PX0002",
+            LexerErrorType::OverlengthyCharError => "PX0003",
+            LexerErrorType::EmptyCharError => "PX0004",
+            LexerErrorType::UnknownTokenStartError => "PX0005",
+            LexerErrorType::IntegerTooLarge(_) => "PX0079",
+        }
+    }
+}
+
+impl fmt::Display for LexerErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexerErrorType::UnclosedStringError => write!(f, "the string isn't closed before the end of the line"),
+            LexerErrorType::UnclosedCharError => write!(f, "the character isn't closed before the end of the line"),
+            LexerErrorType::OverlengthyCharError => write!(f, "a character literal can only hold one character"),
+            LexerErrorType::EmptyCharError => write!(f, "the character literal is empty"),
+            LexerErrorType::UnknownTokenStartError => write!(f, "unknown character"),
+            LexerErrorType::IntegerTooLarge(literal) => write!(f, "the literal `{}` doesn't fit in any integer type", literal),
+        }
+    }
 }
 
 impl LexerError {
@@ -40,12 +90,12 @@ impl Lexer {
     pub fn new(filename: String, code: String) -> Lexer {
         Lexer {
             chars: code.chars().collect(),
-            context: TokenContext {
-                filename,
-                index: 0,
-                column: 0,
-                line: 0,
-            }
+            context: TokenContext::new(filename, 0, 1, 1),
+            struct_literals: true,
+            macros: HashMap::new(),
+            expansions: 0,
+            renamed_names: false,
+            fake_renamed_name: false,
         }
     }
 
@@ -71,6 +121,7 @@ impl Lexer {
 
             self.context.column += 1;
             self.context.index += 1;
+            self.leave_expansions();
 
             Some(ch)
         } else {
@@ -85,6 +136,47 @@ impl Lexer {
         self.next_char()
     }
 
+    /// Continues with the position after an invocation once the code its
+    /// expansion produced has been read
+    fn leave_expansions(&mut self) {
+        while let Some(expansion) = self.context.expansion.clone() {
+            if self.context.index < expansion.end.get() {
+                break;
+            }
+            self.context.line = expansion.line;
+            self.context.column = expansion.column;
+            self.context.expansion = expansion.call.expansion.clone();
+        }
+    }
+
+    /// The source code between two character indices
+    pub fn source(&self, start: usize, end: usize) -> String {
+        self.chars[start..end].iter().collect()
+    }
+
+    /// Replaces the invocation of the macro `name` at `call`, which ends at
+    /// the current position, with `code` and continues from its start
+    pub fn splice(&mut self, name: String, call: TokenContext, code: &str) {
+        let code: Vec<char> = code.chars().collect();
+        let end = self.context.index;
+        let mut expansion = call.expansion.clone();
+        while let Some(current) = expansion {
+            current.end.set(current.end.get() - (end - call.index) + code.len());
+            expansion = current.call.expansion.clone();
+        }
+
+        let expansion = Expansion {
+            name,
+            call: call.clone(),
+            end: Cell::new(call.index + code.len()),
+            line: self.context.line,
+            column: self.context.column,
+        };
+        self.chars.splice(call.index..end, code);
+        self.context = TokenContext { expansion: Some(Rc::new(expansion)), ..call };
+        self.leave_expansions();
+    }
+
     fn contextual_token(&self, token: TokenContent) -> LexerTokenResult {
         Ok(Some(Token::new(token, self.context.clone())))
     }
@@ -103,17 +195,26 @@ impl Lexer {
         word
     }
 
-    fn lex_number(&mut self) -> LexerTokenResult {
-        let word = self.next_chars_until(|w, ch, next| {
-            !ch.is_numeric() && ch != '_' && ch != '.'
-                || ch == '.' && w.contains('.')
-                || ch == '.' && !matches!(next, Some(x) if x.is_numeric() || x == '_' || x == '.')
-        });
+    fn lex_number(&mut self, context: ProgramContext) -> LexerTokenResult {
+        // Tuple fields such as `x.0.1` are always integers, so a dot after a
+        // field index never starts a fractional part
+        let word = match context {
+            ProgramContext::FieldContext => self.next_chars_until(|_, ch, _| !ch.is_numeric() && ch != '_'),
+            _ => self.next_chars_until(|w, ch, next| {
+                !ch.is_numeric() && ch != '_' && ch != '.'
+                    || ch == '.' && w.contains('.')
+                    || ch == '.' && !matches!(next, Some(x) if x.is_numeric() || x == '_')
+            }),
+        };
+        let word = word.replace('_', "");
 
         if word.contains('.') {
             self.contextual_token(TokenContent::FloatToken(word.parse::<f64>().unwrap()))
         } else {
-            self.contextual_token(TokenContent::IntToken(word.parse::<isize>().unwrap()))
+            match word.parse::<i128>() {
+                Ok(value) => self.contextual_token(TokenContent::IntToken(value)),
+                Err(_) => Err(LexerErrorType::IntegerTooLarge(word)),
+            }
         }
     }
 
@@ -122,7 +223,7 @@ impl Lexer {
         let word = self.next_chars_until(|_, ch, _| ch == '"' || ch == '\n');
 
         match self.peek_char() {
-            Some(ch) if ch == '"' => {
+            Some('"') => {
                 self.next_char();
                 self.contextual_token(TokenContent::StringToken(word))
             },
@@ -132,10 +233,19 @@ impl Lexer {
 
     fn lex_char(&mut self) -> LexerTokenResult {
         self.next_char();
+
+        // A name that isn't closed right after is a label such as `'outer`
+        let length = (0..).take_while(|&i| matches!(self.peek_chars(i), Some(ch) if ch.is_alphanumeric() || ch == '_')).count();
+        let starts_name = matches!(self.peek_char(), Some(ch) if ch.is_alphabetic() || ch == '_');
+        if starts_name && !matches!(self.peek_chars(length), None | Some('\'') | Some('\n')) {
+            let label = self.next_chars_until(|_, ch, _| !ch.is_alphanumeric() && ch != '_');
+            return self.contextual_token(TokenContent::LabelToken(label));
+        }
+
         let word = self.next_chars_until(|_, ch, _| ch == '\'' || ch == '\n');
 
         match self.peek_char() {
-            Some(ch) if ch == '\'' => {
+            Some('\'') => {
                 self.next_char();
                 match word.chars().collect::<Vec<char>>()[..] {
                     [c] => self.contextual_token(TokenContent::CharToken(c)),
@@ -150,15 +260,18 @@ impl Lexer {
     fn lex_operator(&mut self, context: ProgramContext) -> LexerTokenResult {
         for length in (1..=MAX_OPERATOR_LENGTH).rev() {
             let operator = self.chars.get(self.context.index..self.context.index + length);
-            if let None = operator { continue; }
+            if operator.is_none() { continue; }
 
             let token_content = Token::string_to_token_content(operator.unwrap().iter().collect(), &context);
-            if let None = token_content { continue; }
+            if token_content.is_none() { continue; }
 
             self.next_chars(length - 1);
             return self.contextual_token(token_content.unwrap());
         }
 
+        // Always skip the offending character so that callers recovering from
+        // the error cannot get stuck on a character that isn't punctuation
+        self.next_char();
         while matches!(self.peek_char(), Some(ch) if ch.is_ascii_punctuation()) {
             self.next_char();
         }
@@ -166,7 +279,19 @@ impl Lexer {
     }
 
     fn lex_word(&mut self, context: ProgramContext) -> LexerTokenResult {
-        let word = self.next_chars_until(|_, ch, _| !ch.is_alphanumeric() && ch != '_');
+        let mut word = self.next_chars_until(|_, ch, _| !ch.is_alphanumeric() && ch != '_');
+
+        // Variables introduced by a macro are renamed to `name#1` and so on,
+        // which only code the macro produced can refer to
+        if self.peek_char() == Some('#') && matches!(self.peek_chars(1), Some(ch) if ch.is_ascii_digit()) {
+            if self.renamed_names || self.context.expansion.is_some() {
+                self.next_char();
+                word.push('#');
+                word.push_str(&self.next_chars_until(|_, ch, _| !ch.is_ascii_digit()));
+            } else {
+                self.fake_renamed_name = true;
+            }
+        }
 
         match Token::string_to_token_content(word.clone(), &context) {
             Some(token_content) => self.contextual_token(token_content),
@@ -175,9 +300,10 @@ impl Lexer {
     }
 
     fn wrap_context(context: TokenContext, result: LexerTokenResult) -> LexerResult {
+        // Tokens are located at their first character rather than their last
         match result {
-            Ok(x) => Ok(x.clone()),
-            Err(x) => Err(LexerError::new(x.clone(), context)),
+            Ok(x) => Ok(x.map(|token| Token::new(token.content, context))),
+            Err(x) => Err(LexerError::new(x, context)),
         }
     }
 
@@ -193,14 +319,14 @@ impl Lexer {
         let context = self.context.clone();
 
         let result = match self.peek_char() {
-            Some(x) if x.is_digit(10)    => self.lex_number(),
-            Some(x) if x == '"'          => self.lex_string(),
-            Some(x) if x == '\''         => self.lex_char(),
+            Some(x) if x.is_ascii_digit() => self.lex_number(program_context),
+            Some('"')                     => self.lex_string(),
+            Some('\'')                    => self.lex_char(),
             Some(x) if
                 x.is_alphabetic()
-                || x == '_'              => self.lex_word(program_context),
-            Some(_)                      => self.lex_operator(program_context),
-            None                         => Ok(None),
+                || x == '_'               => self.lex_word(program_context),
+            Some(_)                       => self.lex_operator(program_context),
+            None                          => Ok(None),
         };
 
         Self::wrap_context(context, result)
@@ -226,11 +352,11 @@ mod tests {
 
         assert!(matches!(
             lexer.context,
-            TokenContext { filename, index, column, line }
-                if filename == "test.px".to_string()
+            TokenContext { filename, index, column, line, .. }
+                if filename == "test.px"
                 && index == 0
-                && column == 0
-                && line == 0
+                && column == 1
+                && line == 1
         ));
     }
 
@@ -241,10 +367,19 @@ mod tests {
         assert!(matches!(
             lexer.next(ProgramContext::NormalContext),
             Ok(Some(Token { content: TokenContent::Identifier(x), context: _ }))
-                if x == "main".to_string()
+                if x == "main"
         ));
     }
 
+    #[test]
+    fn lex_keywords() {
+        for keyword in crate::tokens::KEYWORDS {
+            let mut lexer = lexer("test.px", keyword);
+            let token = lexer.next(ProgramContext::NormalContext);
+            assert!(matches!(token, Ok(Some(Token { content, .. })) if !matches!(content, TokenContent::Identifier(_))), "{}", keyword);
+        }
+    }
+
     #[test]
     fn lex_operator_plus() {
         let mut lexer = lexer("test.px", "+");
@@ -301,6 +436,28 @@ mod tests {
     }
 
 
+    #[test]
+    fn lex_number_too_large() {
+        let mut lexer = lexer("test.px", "170_141_183_460_469_231_731_687_303_715_884_105_728");
+
+        assert!(matches!(
+            lexer.next(ProgramContext::NormalContext),
+            Err(LexerError { error_type: LexerErrorType::IntegerTooLarge(literal), context: _ })
+                if literal == "170141183460469231731687303715884105728"
+        ));
+    }
+
+    #[test]
+    fn lex_renamed_names() {
+        let mut written = lexer("test.px", "secret#0");
+        assert!(matches!(written.next(ProgramContext::NormalContext), Ok(Some(Token { content: TokenContent::Identifier(x), .. })) if x == "secret"));
+        assert!(matches!(written.next(ProgramContext::NormalContext), Ok(Some(Token { content: TokenContent::HashOperator, .. }))));
+
+        let mut expanded = lexer("test.px", "secret#0");
+        expanded.renamed_names = true;
+        assert!(matches!(expanded.next(ProgramContext::NormalContext), Ok(Some(Token { content: TokenContent::Identifier(x), .. })) if x == "secret#0"));
+    }
+
     #[test]
     fn lex_number_integer_42() {
         let mut lexer = lexer("test.px", "42");
@@ -339,6 +496,22 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn lex_int_then_range() {
+        let mut lexer = lexer("test.px", "1..=9");
+
+        assert!(matches!(
+            lexer.next(ProgramContext::NormalContext),
+            Ok(Some(Token { content: TokenContent::IntToken(x), context: _ }))
+                if x == 1
+        ));
+
+        assert!(matches!(
+            lexer.next(ProgramContext::NormalContext),
+            Ok(Some(Token { content: TokenContent::DoubleDotEqualOperator, context: _ }))
+        ));
+    }
+
     #[test]
     fn lex_int_then_field() {
         let mut lexer = lexer("test.px", "42.a");
@@ -357,7 +530,7 @@ mod tests {
         assert!(matches!(
             lexer.next(ProgramContext::NormalContext),
             Ok(Some(Token { content: TokenContent::Identifier(x), context: _ }))
-                if x == "a".to_string()
+                if x == "a"
         ));
     }
 
@@ -395,7 +568,7 @@ mod tests {
         assert!(matches!(
             lexer.next(ProgramContext::NormalContext),
             Ok(Some(Token { content: TokenContent::Identifier(x), context: _ }))
-                if x == "a".to_string()
+                if x == "a"
         ));
     }
 
@@ -421,7 +594,7 @@ mod tests {
 
     #[test]
     fn lex_operator_unknown_start() {
-        let mut lexer = lexer("test.px", "$$ let");
+        let mut lexer = lexer("test.px", "`` let");
 
         assert!(matches!(
             lexer.next(ProgramContext::NormalContext),
@@ -444,13 +617,13 @@ mod tests {
         assert!(matches!(
             lexer.next(ProgramContext::NormalContext),
             Ok(Some(Token { content: TokenContent::StringToken(x), context: _ }))
-                if x == "string".to_string()
+                if x == "string"
         ));
 
         assert!(matches!(
             lexer.next(ProgramContext::NormalContext),
             Ok(Some(Token { content: TokenContent::StringToken(x), context: _ }))
-                if x == "string2".to_string()
+                if x == "string2"
         ));
     }
 
@@ -502,6 +675,34 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn lex_label() {
+        let mut lexer = lexer("test.px", "'outer: 'a' 'b;");
+
+        assert!(matches!(
+            lexer.next(ProgramContext::NormalContext),
+            Ok(Some(Token { content: TokenContent::LabelToken(x), context: _ }))
+                if x == "outer"
+        ));
+
+        assert!(matches!(
+            lexer.next(ProgramContext::NormalContext),
+            Ok(Some(Token { content: TokenContent::ColonOperator, context: _ }))
+        ));
+
+        assert!(matches!(
+            lexer.next(ProgramContext::NormalContext),
+            Ok(Some(Token { content: TokenContent::CharToken(x), context: _ }))
+                if x == 'a'
+        ));
+
+        assert!(matches!(
+            lexer.next(ProgramContext::NormalContext),
+            Ok(Some(Token { content: TokenContent::LabelToken(x), context: _ }))
+                if x == "b"
+        ));
+    }
+
     #[test]
     fn lex_char_unclosed_eol() {
         let mut lexer = lexer("test.px", "'c");
@@ -550,4 +751,65 @@ mod tests {
             Ok(Some(Token { content: TokenContent::LetKeyword, context: _ }))
         ));
     }
+
+    #[test]
+    fn lex_field_context_int_then_dot() {
+        let mut lexer = lexer("test.px", "0.1");
+
+        assert!(matches!(
+            lexer.next(ProgramContext::FieldContext),
+            Ok(Some(Token { content: TokenContent::IntToken(x), context: _ }))
+                if x == 0
+        ));
+
+        assert!(matches!(
+            lexer.next(ProgramContext::NormalContext),
+            Ok(Some(Token { content: TokenContent::DotOperator, context: _ }))
+        ));
+
+        assert!(matches!(
+            lexer.next(ProgramContext::FieldContext),
+            Ok(Some(Token { content: TokenContent::IntToken(x), context: _ }))
+                if x == 1
+        ));
+    }
+
+    #[test]
+    fn lex_number_underscores() {
+        let mut lexer = lexer("test.px", "1_000");
+
+        assert!(matches!(
+            lexer.next(ProgramContext::NormalContext),
+            Ok(Some(Token { content: TokenContent::IntToken(x), context: _ }))
+                if x == 1000
+        ));
+    }
+
+    #[test]
+    fn lex_token_context_is_start() {
+        let mut lexer = lexer("test.px", "  let");
+
+        assert!(matches!(
+            lexer.next(ProgramContext::NormalContext),
+            Ok(Some(Token { content: TokenContent::LetKeyword, context }))
+                if context.index == 2
+        ));
+    }
+
+    #[test]
+    fn lex_token_context_lines() {
+        let mut lexer = lexer("test.px", "let\n  x");
+
+        assert!(matches!(
+            lexer.next(ProgramContext::NormalContext),
+            Ok(Some(Token { content: TokenContent::LetKeyword, context }))
+                if context.line == 1 && context.column == 1
+        ));
+
+        assert!(matches!(
+            lexer.next(ProgramContext::NormalContext),
+            Ok(Some(Token { content: TokenContent::Identifier(_), context }))
+                if context.line == 2 && context.column == 3
+        ));
+    }
 }
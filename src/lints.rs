@@ -0,0 +1,154 @@
+use crate::nodes::*;
+use crate::syntax_errors::*;
+use std::collections::HashMap;
+
+/// A named kind of warning whose level can be changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnknownAttributes,
+    UnknownLints,
+    UnreachablePatterns,
+}
+
+pub const LINTS: &[Lint] = &[Lint::UnknownAttributes, Lint::UnknownLints, Lint::UnreachablePatterns];
+
+impl Lint {
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnknownAttributes => "unknown_attributes",
+            Lint::UnknownLints => "unknown_lints",
+            Lint::UnreachablePatterns => "unreachable_patterns",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        LINTS.iter().copied().find(|lint| lint.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    /// The warning isn't shown
+    Allow,
+    Warn,
+    /// The warning is an error
+    Deny,
+}
+
+impl Level {
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// How each lint is reported. Every lint starts at `warn`, and the group
+/// `warnings` changes what happens to the lints that are still at `warn`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintLevels {
+    levels: HashMap<Lint, Level>,
+    warnings: Level,
+}
+
+impl LintLevels {
+    pub fn new() -> LintLevels {
+        LintLevels { levels: HashMap::new(), warnings: Level::Warn }
+    }
+
+    /// Sets the level of the lint or group called `name`, returning whether
+    /// there is one
+    pub fn set(&mut self, name: &str, level: Level) -> bool {
+        match (name, Lint::from_name(name)) {
+            ("warnings", _) => self.warnings = level,
+            (_, Some(lint)) => { self.levels.insert(lint, level); },
+            (_, None) => return false,
+        }
+        true
+    }
+
+    pub fn level(&self, lint: Lint) -> Level {
+        match self.levels.get(&lint).copied().unwrap_or(Level::Warn) {
+            Level::Warn => self.warnings,
+            level => level,
+        }
+    }
+
+    /// The levels inside an item with these attributes, where later
+    /// attributes override earlier ones. Unknown names are left to
+    /// `unknown_lints`
+    pub fn with_attributes(&self, attributes: &[Attribute]) -> LintLevels {
+        let mut levels = self.clone();
+        for (level, name) in lint_attributes(attributes) {
+            levels.set(&name, level);
+        }
+        levels
+    }
+
+    /// Sorts a warning into `warnings` or `errors` by the level of its lint
+    pub fn report(&self, warning: SyntaxWarning, warnings: &mut Vec<SyntaxWarning>, errors: &mut SyntaxErrorCollector) {
+        match self.level(warning.warning_type.lint()) {
+            Level::Allow => {},
+            Level::Warn => warnings.push(warning),
+            Level::Deny => errors.add_errors(SyntaxErrorCollector::from_error(
+                SyntaxErrorType::DeniedLint(warning.warning_type),
+                warning.context,
+            )),
+        }
+    }
+}
+
+/// Every lint named by `allow`, `warn` and `deny` attributes, with the level
+/// it is given
+pub fn lint_attributes(attributes: &[Attribute]) -> Vec<(Level, String)> {
+    let mut lints = Vec::new();
+    for attribute in attributes {
+        if let Meta::List(name, metas) = &attribute.meta {
+            if let Some(level) = Level::from_name(name) {
+                lints.extend(metas.iter().filter_map(Meta::name).map(|lint| (level, lint.to_string())));
+            }
+        }
+    }
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn attributes(code: &str) -> Vec<Attribute> {
+        let items = Lexer::new("test.px".to_string(), code.to_string()).parse().ok().unwrap();
+        items[0].attributes().to_vec()
+    }
+
+    #[test]
+    fn lint_levels() {
+        let mut levels = LintLevels::new();
+        assert_eq!(levels.level(Lint::UnreachablePatterns), Level::Warn);
+        assert!(levels.set("warnings", Level::Deny));
+        assert!(levels.set("unknown_lints", Level::Allow));
+        assert!(!levels.set("unused", Level::Allow));
+
+        assert_eq!(levels.level(Lint::UnreachablePatterns), Level::Deny);
+        assert_eq!(levels.level(Lint::UnknownLints), Level::Allow);
+    }
+
+    #[test]
+    fn attribute_levels() {
+        let levels = LintLevels::new().with_attributes(&attributes("
+            #[allow(warnings)] #[warn(unreachable_patterns)] #[deny(unknown_lints, missing)]
+            fn main() {}
+        "));
+
+        assert_eq!(levels.level(Lint::UnknownAttributes), Level::Allow);
+        assert_eq!(levels.level(Lint::UnreachablePatterns), Level::Allow);
+        assert_eq!(levels.level(Lint::UnknownLints), Level::Deny);
+
+        let levels = levels.with_attributes(&attributes("#[warn(warnings)] fn main() {}"));
+        assert_eq!(levels.level(Lint::UnreachablePatterns), Level::Warn);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::lexer::ProgramContext;
+use crate::syntax_errors::SyntaxErrorType;
+use crate::tokens::Token;
+use std::collections::{HashMap, HashSet};
+
+/// A macro defined with `macro name { (matcher) => { transcriber }, .. }`.
+/// Invoking it tries each rule in order and replaces the invocation with the
+/// transcriber of the first rule whose matcher matches the arguments.
+#[derive(Debug)]
+pub struct Macro {
+    #[allow(dead_code)]
+    pub name: String,
+    pub rules: Vec<MacroRule>,
+}
+
+#[derive(Debug)]
+pub struct MacroRule {
+    pub matcher: Vec<MacroToken>,
+    pub transcriber: Vec<MacroToken>,
+}
+
+#[derive(Debug, Clone)]
+pub enum MacroToken {
+    /// A token that is matched or produced as is, kept as its source code
+    Token(String),
+    /// `$name:kind` in a matcher
+    Fragment(String, FragmentKind),
+    /// `$name` in a transcriber
+    Variable(String),
+    /// `$( .. )` followed by an optional separator and `*`, `+` or `?`
+    Repetition { tokens: Vec<MacroToken>, separator: Option<String>, kind: RepetitionKind },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FragmentKind {
+    Expression,
+    Identifier,
+    Type,
+    Literal,
+    TokenTree,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RepetitionKind {
+    ZeroOrMore,
+    OneOrMore,
+    ZeroOrOne,
+}
+
+/// The code a macro variable matched
+#[derive(Debug, Clone)]
+pub enum Capture {
+    Fragment(String),
+    Repetition(Vec<Captures>),
+}
+
+pub type Captures = HashMap<String, Capture>;
+
+impl FragmentKind {
+    pub fn from_name(name: &str) -> Option<FragmentKind> {
+        match name {
+            "expr" => Some(FragmentKind::Expression),
+            "ident" => Some(FragmentKind::Identifier),
+            "type" => Some(FragmentKind::Type),
+            "literal" => Some(FragmentKind::Literal),
+            "tt" => Some(FragmentKind::TokenTree),
+            _ => None,
+        }
+    }
+}
+
+impl MacroToken {
+    /// The names of the fragments a matcher binds, including those inside
+    /// repetitions
+    pub fn fragments(tokens: &[MacroToken]) -> Vec<String> {
+        tokens.iter().flat_map(|token| match token {
+            MacroToken::Fragment(name, _) => vec![name.clone()],
+            MacroToken::Repetition { tokens, .. } => MacroToken::fragments(tokens),
+            _ => Vec::new(),
+        }).collect()
+    }
+
+    fn variables(tokens: &[MacroToken]) -> Vec<String> {
+        tokens.iter().flat_map(|token| match token {
+            MacroToken::Variable(name) => vec![name.clone()],
+            MacroToken::Repetition { tokens, .. } => MacroToken::variables(tokens),
+            _ => Vec::new(),
+        }).collect()
+    }
+
+    /// Every token of a transcriber in order, with `None` for the code that
+    /// comes from the invocation
+    fn flatten<'a>(tokens: &'a [MacroToken], flat: &mut Vec<Option<&'a str>>) {
+        for token in tokens {
+            match token {
+                MacroToken::Token(text) => flat.push(Some(text)),
+                MacroToken::Repetition { tokens, separator, kind: _ } => {
+                    MacroToken::flatten(tokens, flat);
+                    flat.push(separator.as_deref());
+                },
+                _ => flat.push(None),
+            }
+        }
+    }
+}
+
+impl MacroRule {
+    /// Produces the code for an invocation that matched this rule. Variables
+    /// that the transcriber binds itself with `let`, `const`, `for` or closure
+    /// parameters are renamed to `name#expansion`, so they can neither capture
+    /// nor shadow the variables of the code around the invocation.
+    pub fn transcribe(&self, captures: &Captures, expansion: usize) -> Result<String, SyntaxErrorType> {
+        let mut flat = Vec::new();
+        MacroToken::flatten(&self.transcriber, &mut flat);
+        let renamed: HashMap<String, String> = introduced(&flat).into_iter()
+            .map(|name| (name.clone(), format!("{}#{}", name, expansion)))
+            .collect();
+
+        let mut code = Vec::new();
+        transcribe(&self.transcriber, captures, &renamed, &mut code)?;
+        Ok(code.join(" "))
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(|ch: char| ch.is_alphabetic() || ch == '_')
+        && text != "_"
+        && Token::string_to_token_content(text.to_string(), &ProgramContext::NormalContext).is_none()
+}
+
+/// The names bound by the transcriber itself
+fn introduced(tokens: &[Option<&str>]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let mut end: Option<&[&str]> = None;
+    let mut closure = false;
+
+    for (index, token) in tokens.iter().enumerate() {
+        let previous = if index == 0 { None } else { tokens[index - 1] };
+        let next = tokens.get(index + 1).copied().flatten();
+        let Some(token) = *token else { continue };
+
+        match (end, token) {
+            (Some(ends), token) if ends.contains(&token) => end = None,
+            (Some(_), token) if is_identifier(token) && next != Some("::") && previous != Some("::") && (!closure || next == Some(":")) => {
+                names.insert(token.to_string());
+            },
+            (Some(_), _) => {},
+            (None, "let" | "const") => {
+                end = Some(&["=", ":", ";"]);
+                closure = false;
+            },
+            (None, "for") => {
+                end = Some(&["in"]);
+                closure = false;
+            },
+            (None, "|") if matches!(previous, None | Some("(" | "," | "=" | "{" | ";" | "=>" | "return")) => {
+                end = Some(&["|"]);
+                closure = true;
+            },
+            _ => {},
+        }
+    }
+    names
+}
+
+fn transcribe(tokens: &[MacroToken], captures: &Captures, renamed: &HashMap<String, String>, code: &mut Vec<String>) -> Result<(), SyntaxErrorType> {
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            MacroToken::Token(text) => {
+                let previous = index.checked_sub(1).and_then(|index| tokens.get(index));
+                let field = matches!(previous, Some(MacroToken::Token(previous)) if previous == "." || previous == "::");
+                match renamed.get(text) {
+                    Some(name) if !field => code.push(name.clone()),
+                    _ => code.push(text.clone()),
+                }
+            },
+            MacroToken::Variable(name) => match captures.get(name) {
+                Some(Capture::Fragment(text)) => code.push(text.clone()),
+                Some(Capture::Repetition(_)) => return Err(SyntaxErrorType::MacroRepetitionMismatch(name.clone())),
+                None => return Err(SyntaxErrorType::UndefinedMacroVariable(name.clone())),
+            },
+            MacroToken::Repetition { tokens, separator, kind: _ } => {
+                // Every repeated variable in the repetition must have matched
+                // the same number of times
+                let mut count = None;
+                for name in MacroToken::variables(tokens) {
+                    if let Some(Capture::Repetition(matches)) = captures.get(&name) {
+                        match count {
+                            Some(count) if count != matches.len() => return Err(SyntaxErrorType::MacroRepetitionMismatch(name)),
+                            _ => count = Some(matches.len()),
+                        }
+                    }
+                }
+                let Some(count) = count else {
+                    return Err(SyntaxErrorType::MacroRepetitionMismatch(MacroToken::variables(tokens).join(", ")));
+                };
+
+                for repetition in 0..count {
+                    if repetition > 0 {
+                        code.extend(separator.clone());
+                    }
+                    let mut inner = captures.clone();
+                    for (name, capture) in captures {
+                        if let Capture::Repetition(matches) = capture {
+                            inner.remove(name);
+                            inner.extend(matches[repetition].clone());
+                        }
+                    }
+                    transcribe(tokens, &inner, renamed, code)?;
+                }
+            },
+            MacroToken::Fragment(name, _) => return Err(SyntaxErrorType::UndefinedMacroVariable(name.clone())),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(code: &str) -> Vec<MacroToken> {
+        code.split(' ').map(|token| match token.strip_prefix('$') {
+            Some(name) => MacroToken::Variable(name.to_string()),
+            None => MacroToken::Token(token.to_string()),
+        }).collect()
+    }
+
+    #[test]
+    fn transcribe_renames_introduced_variables() {
+        let rule = MacroRule {
+            matcher: Vec::new(),
+            transcriber: tokens("{ let ( a , b ) = $e ; for i in a { | c : i32 | c + b + i + p . a } }"),
+        };
+        let captures = HashMap::from([("e".to_string(), Capture::Fragment("(a)".to_string()))]);
+
+        assert_eq!(
+            rule.transcribe(&captures, 7).ok().unwrap(),
+            "{ let ( a#7 , b#7 ) = (a) ; for i#7 in a#7 { | c#7 : i32 | c#7 + b#7 + i#7 + p . a } }"
+        );
+    }
+
+    #[test]
+    fn transcribe_repetitions() {
+        let rule = MacroRule {
+            matcher: Vec::new(),
+            transcriber: vec![MacroToken::Repetition {
+                tokens: tokens("f ( $x , $y )"),
+                separator: Some(";".to_string()),
+                kind: RepetitionKind::ZeroOrMore,
+            }],
+        };
+        let repetition = |values: &[(&str, &str)]| Capture::Repetition(values.iter().map(|(x, y)| HashMap::from([
+            ("x".to_string(), Capture::Fragment(x.to_string())),
+            ("y".to_string(), Capture::Fragment(y.to_string())),
+        ])).collect());
+
+        let captures = HashMap::from([
+            ("x".to_string(), repetition(&[("1", "2"), ("3", "4")])),
+            ("y".to_string(), repetition(&[("1", "2"), ("3", "4")])),
+        ]);
+        assert_eq!(rule.transcribe(&captures, 0).ok().unwrap(), "f ( 1 , 2 ) ; f ( 3 , 4 )");
+
+        let captures = HashMap::from([
+            ("x".to_string(), repetition(&[("1", "2")])),
+            ("y".to_string(), repetition(&[])),
+        ]);
+        assert!(matches!(rule.transcribe(&captures, 0), Err(SyntaxErrorType::MacroRepetitionMismatch(_))));
+    }
+}
@@ -1,14 +1,32 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use cfg::Config;
 use lexer::Lexer;
-use interpreter::TreeWalker;
+use interpreter::{TreeWalker, STACK_SIZE};
+use modules::ModuleLoader;
+use diagnostics::{DiagnosticSink, Renderer};
+use lints::{Level, LintLevels};
+use runtime_errors::ProgramError;
+use explanations::Explanation;
+use std::collections::HashMap;
+use std::io::IsTerminal;
 
 mod read_file;
 mod tokens;
 mod lexer;
+mod macros;
 mod nodes;
 mod syntax_errors;
+mod runtime_errors;
+mod diagnostics;
+mod suggestions;
+mod explanations;
 mod parser;
 mod static_analyzer;
+mod const_evaluator;
+mod modules;
+mod attributes;
+mod lints;
+mod cfg;
 mod values;
 mod interpreter;
 
@@ -29,24 +47,146 @@ enum Command {
         /// Name of the target file
         filename: String,
 
-        /// Suppress warnings
+        /// Suppress warnings, like `-A warnings`
         #[arg(long)]
         no_warnings: bool,
+
+        /// Hide the warnings of a lint, or of every lint with `warnings`
+        #[arg(short = 'A', long = "allow", value_name = "LINT")]
+        allow: Vec<String>,
+
+        /// Show the warnings of a lint
+        #[arg(short = 'W', long = "warn", value_name = "LINT")]
+        warn: Vec<String>,
+
+        /// Make the warnings of a lint errors, or every warning with `-D warnings`
+        #[arg(short = 'D', long = "deny", value_name = "LINT")]
+        deny: Vec<String>,
+
+        /// Enable a configuration option such as `debug` or `feature="x"`
+        #[arg(long = "cfg", value_name = "OPTION")]
+        cfg: Vec<String>,
+
+        /// When to color diagnostics
+        #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+        color: ColorChoice,
+
+        /// How to print diagnostics
+        #[arg(long, value_enum, default_value_t = ErrorFormat::Human)]
+        error_format: ErrorFormat,
+    },
+    /// Apply the fixes that diagnostics are sure about to a file
+    Fix {
+        /// Name of the target file
+        filename: String,
+
+        /// Enable a configuration option such as `debug` or `feature="x"`
+        #[arg(long = "cfg", value_name = "OPTION")]
+        cfg: Vec<String>,
+    },
+    /// Describe an error code in detail
+    Explain {
+        /// The code, such as `PX0001`
+        code: String,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ErrorFormat {
+    /// Source snippets with underlines
+    Human,
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ColorChoice {
+    /// Only when writing to a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => std::io::stderr().is_terminal(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
     }
 }
 
 fn main() {
-    let args = Args::parse();
-    
-    match args.command {
-        Some(Command::Run { filename, no_warnings }) => {
-            run(filename, no_warnings)
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+    let lints = matches.subcommand_matches("run").map(lint_flags).unwrap_or_default();
+
+    // Programs run on a thread of their own, since the main thread's stack is
+    // too small for deep recursion
+    let command = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || execute(args.command, lints))
+        .expect("couldn't start the interpreter thread");
+    if command.join().is_err() {
+        std::process::exit(101);
+    }
+}
+
+/// The levels given to lints with `-A`, `-W` and `-D` in the order they
+/// appear on the command line
+fn lint_flags(matches: &ArgMatches) -> Vec<(Level, String)> {
+    let mut flags = Vec::new();
+    for (id, level) in [("allow", Level::Allow), ("warn", Level::Warn), ("deny", Level::Deny)] {
+        let (Some(indices), Some(names)) = (matches.indices_of(id), matches.get_many::<String>(id)) else {
+            continue;
+        };
+        flags.extend(indices.zip(names).map(|(index, name)| (index, level, name.clone())));
+    }
+    flags.sort_by_key(|(index, _, _)| *index);
+    flags.into_iter().map(|(_, level, name)| (level, name)).collect()
+}
+
+fn execute(command: Option<Command>, flags: Vec<(Level, String)>) {
+    match command {
+        Some(Command::Run { filename, no_warnings, cfg, color, error_format, .. }) => {
+            let mut lints = LintLevels::new();
+            if no_warnings {
+                lints.set("warnings", Level::Allow);
+            }
+            // Later flags win, so `-A x -D x` denies `x`
+            for (level, name) in flags {
+                if !lints.set(&name, level) {
+                    eprintln!("error: unknown lint `{}`", name);
+                    std::process::exit(1);
+                }
+            }
+            run(filename, lints, config(&cfg), color.enabled(), error_format)
+        },
+        Some(Command::Fix { filename, cfg }) => fix(filename, config(&cfg)),
+        Some(Command::Explain { code }) => match Explanation::find(&code) {
+            Some(explanation) => print!("{}", explanation.render()),
+            None => {
+                eprintln!("error: `{}` isn't an error code", code);
+                std::process::exit(1);
+            },
         },
         None => {},
     }
 }
 
-fn run(filename: String, no_warnings: bool) {
+/// The configuration set with `--cfg`, exiting if an option is invalid
+fn config(options: &[String]) -> Config {
+    match Config::from_options(options) {
+        Ok(config) => config,
+        Err(option) => {
+            eprintln!("error: invalid `--cfg` option `{}`, which has to be a name or `name=\"value\"`", option);
+            std::process::exit(1);
+        },
+    }
+}
+
+fn run(filename: String, lints: LintLevels, config: Config, color: bool, error_format: ErrorFormat) {
     let code_result = read_file::read_file(filename.clone());
     if let Err(error) = code_result {
         println!("{}: {}", filename, error);
@@ -54,13 +194,118 @@ fn run(filename: String, no_warnings: bool) {
     }
     
     let code: String = code_result.unwrap();
+    let mut lexer = Lexer::new(filename.clone(), code.clone());
+    let tree = ModuleLoader::load(&filename, lexer.parse(), &config);
 
-    let mut lexer = Lexer::new(filename, code);
-    let tree = lexer.parse_expression();
-    
     let mut tree_walker = TreeWalker::new();
-    
+    tree_walker.lints = lints;
     let result = tree_walker.interpret(tree);
 
     println!("{}", tree_walker.output);
-}
\ No newline at end of file
+
+    let mut sink = DiagnosticSink::new();
+    for warning in &tree_walker.warnings {
+        sink.warning(warning);
+    }
+    if let Err(error) = &result {
+        sink.program_error(error);
+    }
+    // The count is only for people reading the output
+    if error_format == ErrorFormat::Human {
+        sink.summarize();
+    }
+
+    let mut renderer = Renderer::new(color);
+    renderer.add_source(&filename, &code);
+    for diagnostic in &sink.diagnostics {
+        match error_format {
+            ErrorFormat::Human => eprint!("{}", renderer.render(diagnostic)),
+            ErrorFormat::Json => eprint!("{}", renderer.render_json(diagnostic)),
+        }
+    }
+
+    if result.is_err() {
+        std::process::exit(1);
+    }
+}
+
+/// The most passes `fix` makes, since fixing one error can reveal another
+const FIX_PASSES: usize = 16;
+
+fn fix(filename: String, config: Config) {
+    let mut fixed = 0;
+    for _ in 0..FIX_PASSES {
+        let code = match read_file::read_file(filename.clone()) {
+            Ok(code) => code,
+            Err(error) => {
+                println!("{}: {}", filename, error);
+                std::process::exit(1);
+            },
+        };
+        let mut lexer = Lexer::new(filename.clone(), code);
+        let tree = ModuleLoader::load(&filename, lexer.parse(), &config);
+        // Only errors in the source have fixes
+        let Err(ProgramError::Syntax(collector)) = TreeWalker::new().interpret(tree) else {
+            break;
+        };
+
+        // Errors in modules are fixed in the files they came from, and errors
+        // inside macro expansions aren't fixed at all
+        let mut suggestions: HashMap<&str, Vec<&syntax_errors::Suggestion>> = HashMap::new();
+        for suggestion in collector.errors.iter().filter_map(|error| error.suggestion.as_ref()) {
+            if suggestion.context.expansion.is_some() {
+                continue;
+            }
+            suggestions.entry(&suggestion.context.filename).or_default().push(suggestion);
+        }
+
+        let mut applied = 0;
+        for (file, suggestions) in suggestions {
+            let Ok(code) = read_file::read_file(file.to_string()) else {
+                continue;
+            };
+            let (code, count) = suggestions::apply(&code, &suggestions);
+            if count > 0 && std::fs::write(file, code).is_ok() {
+                applied += count;
+            }
+        }
+        if applied == 0 {
+            break;
+        }
+        fixed += applied;
+    }
+
+    match fixed {
+        1 => println!("{}: applied 1 fix", filename),
+        _ => println!("{}: applied {} fixes", filename, fixed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(args: &[&str]) -> Vec<(Level, String)> {
+        let matches = Args::command().get_matches_from(["polarix", "run", "main.px"].iter().chain(args));
+        lint_flags(matches.subcommand_matches("run").unwrap())
+    }
+
+    #[test]
+    fn lint_flags_in_order() {
+        assert_eq!(flags(&["-D", "warnings", "-A", "unknown_lints", "--warn", "warnings"]), vec![
+            (Level::Deny, String::from("warnings")),
+            (Level::Allow, String::from("unknown_lints")),
+            (Level::Warn, String::from("warnings")),
+        ]);
+        assert_eq!(flags(&[]), vec![]);
+    }
+
+    #[test]
+    fn later_lint_flags_win() {
+        let mut lints = LintLevels::new();
+        for (level, name) in flags(&["-D", "unreachable_patterns", "-A", "unreachable_patterns"]) {
+            lints.set(&name, level);
+        }
+        assert_eq!(lints.level(lints::Lint::UnreachablePatterns), Level::Allow);
+    }
+}
@@ -0,0 +1,770 @@
+use crate::cfg::Config;
+use crate::lexer::Lexer;
+use crate::lints::Level;
+use crate::nodes::*;
+use crate::read_file;
+use crate::suggestions;
+use crate::syntax_errors::*;
+use crate::tokens::TokenContext;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Fills in the items of every `mod name;` by parsing `name.px` or
+/// `name/mod.px`. Both are looked up in the directory of the module that
+/// declares them, which is the directory of the root file for the root module
+/// and a directory named after the module for every other module.
+///
+/// Items disabled by `#[cfg(...)]` are removed from every file as it's loaded,
+/// so a disabled `mod name;` never needs its file.
+pub struct ModuleLoader<'a> {
+    config: &'a Config,
+    files: Vec<PathBuf>,
+    errors: SyntaxErrorCollector,
+}
+
+impl ModuleLoader<'_> {
+    pub fn load(filename: &str, tree: Result<Vec<Item>, SyntaxErrorCollector>, config: &Config) -> Result<Vec<Item>, SyntaxErrorCollector> {
+        let path = Path::new(filename);
+        let mut loader = ModuleLoader {
+            config,
+            files: vec![fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())],
+            errors: SyntaxErrorCollector::new(),
+        };
+
+        let mut items = config.strip(tree?)?;
+        let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        loader.load_items(&mut items, &directory);
+        if loader.errors.is_empty() { Ok(items) } else { Err(loader.errors) }
+    }
+
+    fn load_items(&mut self, items: &mut [Item], directory: &Path) {
+        for item in items {
+            if let Item::Module { name, items, context, public: _, attributes: _ } = item {
+                let directory = directory.join(name.as_str());
+                match items {
+                    Some(items) => self.load_items(items, &directory),
+                    None => *items = Some(self.load_file(name, &directory, context).unwrap_or_default()),
+                }
+            }
+        }
+    }
+
+    fn load_file(&mut self, name: &str, directory: &Path, context: &TokenContext) -> Option<Vec<Item>> {
+        let candidates = [directory.with_extension("px"), directory.join("mod.px")];
+        let path = match &candidates.iter().filter(|path| path.is_file()).collect::<Vec<&PathBuf>>()[..] {
+            [path] => (*path).clone(),
+            [] => return self.error(SyntaxErrorType::ModuleNotFound(name.to_string()), context),
+            _ => return self.error(SyntaxErrorType::AmbiguousModule(name.to_string()), context),
+        };
+
+        // A file that is already being loaded would include itself forever
+        let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if let Some(start) = self.files.iter().position(|file| *file == canonical) {
+            let mut cycle: Vec<String> = self.files[start..].iter().map(|file| file.display().to_string()).collect();
+            cycle.push(canonical.display().to_string());
+            return self.error(SyntaxErrorType::ModuleCycle(cycle), context);
+        }
+
+        let filename = path.display().to_string();
+        let code = match read_file::read_file(filename.clone()) {
+            Ok(code) => code,
+            Err(_) => return self.error(SyntaxErrorType::ModuleNotFound(name.to_string()), context),
+        };
+        let mut items = match Lexer::new(filename, code).parse().and_then(|items| self.config.strip(items)) {
+            Ok(items) => items,
+            Err(errors) => {
+                self.errors.add_errors(errors);
+                return None;
+            },
+        };
+
+        self.files.push(canonical);
+        self.load_items(&mut items, directory);
+        self.files.pop();
+        Some(items)
+    }
+
+    fn error<T>(&mut self, error_type: SyntaxErrorType, context: &TokenContext) -> Option<T> {
+        self.errors.add_errors(SyntaxErrorCollector::from_error(error_type, context.clone()));
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ItemKind {
+    Function,
+    Value,
+    Struct,
+    Enum,
+    Trait,
+}
+
+/// What a name or path refers to
+#[derive(Debug, Clone)]
+enum Binding {
+    Item(String, ItemKind),
+    Module(usize),
+    Variant(String, String),
+}
+
+struct Module {
+    path: Vec<String>,
+    parent: Option<usize>,
+    declared: HashMap<String, Binding>,
+    imports: HashMap<String, Binding>,
+    globs: HashMap<String, Binding>,
+    public: HashSet<String>,
+}
+
+/// Flattens the module tree into a single list of items. Items are renamed to
+/// their full path, such as `shapes::area`, except for those of the root
+/// module, and every name and path that refers to an item is rewritten to
+/// that full path.
+///
+/// A name is looked up in the items of its module, then in the names brought
+/// in by `use` and then in the root module. Paths may start with `self` or
+/// `super` to start from the current module or its parent.
+///
+/// Items are private unless marked `pub`, and a private item can only be
+/// named from the module that declares it and the modules inside that one.
+pub struct ModuleResolver {
+    modules: Vec<Module>,
+    structs: HashMap<String, (usize, Vec<StructField>)>,
+    uses: Vec<(usize, Item)>,
+    items: Vec<(usize, Item)>,
+    errors: SyntaxErrorCollector,
+}
+
+impl ModuleResolver {
+    pub fn new() -> ModuleResolver {
+        ModuleResolver {
+            modules: Vec::new(),
+            structs: HashMap::new(),
+            uses: Vec::new(),
+            items: Vec::new(),
+            errors: SyntaxErrorCollector::new(),
+        }
+    }
+
+    pub fn resolve(mut self, items: Vec<Item>) -> Result<Vec<Item>, SyntaxErrorCollector> {
+        self.collect(items, None, Vec::new());
+
+        for (module, item) in std::mem::take(&mut self.uses) {
+            if let Item::Use { path, alias, glob, context, attributes: _ } = item {
+                self.import(module, &path, alias, glob, &context);
+            }
+        }
+
+        let mut resolved = Vec::new();
+        for (module, mut item) in std::mem::take(&mut self.items) {
+            self.resolve_item(&mut item, module);
+            resolved.push(item);
+        }
+
+        if self.errors.is_empty() { Ok(resolved) } else { Err(self.errors) }
+    }
+
+    fn full_name(&self, module: usize, name: &str) -> String {
+        let mut path = self.modules[module].path.clone();
+        path.push(name.to_string());
+        path.join("::")
+    }
+
+    fn collect(&mut self, items: Vec<Item>, parent: Option<usize>, path: Vec<String>) -> usize {
+        let module = self.modules.len();
+        self.modules.push(Module {
+            path,
+            parent,
+            declared: HashMap::new(),
+            imports: HashMap::new(),
+            globs: HashMap::new(),
+            public: HashSet::new(),
+        });
+
+        for item in items {
+            let declared = match &item {
+                Item::Function { header, body: _ } => Some((header.name.clone(), ItemKind::Function, header.public)),
+                Item::ConstItem { name, public, .. } | Item::StaticItem { name, public, .. } => Some((name.clone(), ItemKind::Value, *public)),
+                Item::Struct { name, public, .. } => Some((name.clone(), ItemKind::Struct, *public)),
+                Item::Enum { name, public, .. } => Some((name.clone(), ItemKind::Enum, *public)),
+                Item::Trait { name, public, .. } => Some((name.clone(), ItemKind::Trait, *public)),
+                _ => None,
+            };
+            if let Some((name, kind, public)) = declared {
+                let full_name = self.full_name(module, &name);
+                if let Item::Struct { fields, .. } = &item {
+                    self.structs.insert(full_name.clone(), (module, fields.clone()));
+                }
+                self.declare(module, name, Binding::Item(full_name, kind), public);
+            }
+
+            match item {
+                Item::Module { name, items, context: _, public, attributes } => {
+                    let mut path = self.modules[module].path.clone();
+                    path.push(name.clone());
+                    // Resolved items no longer sit in their module, so they
+                    // take over the lint levels it sets
+                    let lints: Vec<Attribute> = attributes.into_iter()
+                        .filter(|attribute| attribute.meta.name().and_then(Level::from_name).is_some())
+                        .collect();
+                    let items = items.unwrap_or_default().into_iter().map(|mut item| {
+                        item.attributes_mut().splice(0..0, lints.iter().cloned());
+                        item
+                    }).collect();
+                    let child = self.collect(items, Some(module), path);
+                    self.declare(module, name, Binding::Module(child), public);
+                },
+                item @ Item::Use { .. } => self.uses.push((module, item)),
+                item => self.items.push((module, item)),
+            }
+        }
+        module
+    }
+
+    fn declare(&mut self, module: usize, name: String, binding: Binding, public: bool) {
+        if public {
+            self.modules[module].public.insert(name.clone());
+        }
+        self.modules[module].declared.insert(name, binding);
+    }
+
+    /// Whether `module` is `owner` or one of the modules inside it, which are
+    /// the modules that can see the private items of `owner`
+    fn within(&self, module: usize, owner: usize) -> bool {
+        module == owner || self.modules[module].parent.is_some_and(|parent| self.within(parent, owner))
+    }
+
+    fn import(&mut self, module: usize, path: &[PathSegment], alias: Option<String>, glob: bool, context: &TokenContext) {
+        let binding = match self.resolve_path(module, path) {
+            Ok(binding) => binding,
+            Err(error_type @ SyntaxErrorType::PrivateItem { .. }) => return self.error(error_type, context),
+            Err(_) => return self.unresolved(module, path, context),
+        };
+
+        match (binding, glob) {
+            (Binding::Module(target), true) => {
+                let visible: Vec<(String, Binding)> = self.modules[target].declared.iter()
+                    .filter(|(name, _)| self.modules[target].public.contains(*name) || self.within(module, target))
+                    .map(|(name, binding)| (name.clone(), binding.clone()))
+                    .collect();
+                self.modules[module].globs.extend(visible);
+            },
+            (binding @ (Binding::Item(..) | Binding::Module(_)), false) => {
+                let name = match (alias, path.last()) {
+                    (Some(alias), _) => alias,
+                    (None, Some(PathSegment::PathIdentifier { id })) => id.clone(),
+                    _ => return self.error(SyntaxErrorType::UnresolvedPath(PathSegment::path_as_string(path)), context),
+                };
+                self.modules[module].imports.insert(name, binding);
+            },
+            _ => self.unresolved(module, path, context),
+        }
+    }
+
+    /// Reports that `path` doesn't lead to an item, suggesting the path it is
+    /// most likely a typo of. Only the first segment that can't be found is
+    /// corrected
+    fn unresolved(&mut self, module: usize, path: &[PathSegment], context: &TokenContext) {
+        let written = PathSegment::path_as_string(path);
+        self.error(SyntaxErrorType::UnresolvedPath(written.clone()), context);
+
+        let Some(index) = (1..=path.len()).find(|length| self.resolve_path(module, &path[..*length]).is_err()).map(|length| length - 1) else {
+            return;
+        };
+        let PathSegment::PathIdentifier { id } = &path[index] else {
+            return;
+        };
+        let names: Vec<&String> = match index {
+            0 => [module, 0].iter()
+                .map(|scope| &self.modules[*scope])
+                .flat_map(|scope| scope.declared.keys().chain(scope.imports.keys()).chain(scope.globs.keys()))
+                .collect(),
+            _ => match self.resolve_path(module, &path[..index]) {
+                Ok(Binding::Module(owner)) => self.modules[owner].declared.keys()
+                    .filter(|name| self.modules[owner].public.contains(*name) || self.within(module, owner))
+                    .collect(),
+                _ => return,
+            },
+        };
+
+        let correct = |name: &str| {
+            let mut corrected = path.to_vec();
+            corrected[index] = PathSegment::PathIdentifier { id: name.to_string() };
+            corrected
+        };
+        let candidates = names.iter()
+            .map(|name| name.as_str())
+            .filter(|name| self.resolve_path(module, &correct(name)).is_ok());
+        if let Some((closest, unique)) = suggestions::closest(id, candidates) {
+            let replacement = PathSegment::path_as_string(&correct(closest));
+            self.errors.suggest(written, replacement, unique);
+        }
+    }
+
+    fn lookup(&self, module: usize, name: &str) -> Option<Binding> {
+        let scope = &self.modules[module];
+        let binding = scope.declared.get(name)
+            .or_else(|| scope.imports.get(name))
+            .or_else(|| scope.globs.get(name));
+        match binding {
+            Some(binding) => Some(binding.clone()),
+            None if module != 0 => self.lookup(0, name),
+            None => None,
+        }
+    }
+
+    fn resolve_path(&self, module: usize, path: &[PathSegment]) -> Result<Binding, SyntaxErrorType> {
+        let unresolved = || SyntaxErrorType::UnresolvedPath(PathSegment::path_as_string(path));
+        let (first, rest) = path.split_first().ok_or_else(unresolved)?;
+        let mut binding = match first {
+            PathSegment::SelfPath => Binding::Module(module),
+            PathSegment::SuperPath => Binding::Module(self.modules[module].parent.ok_or_else(unresolved)?),
+            PathSegment::PathIdentifier { id } => self.lookup(module, id).ok_or_else(unresolved)?,
+        };
+
+        for segment in rest {
+            binding = match (binding, segment) {
+                (Binding::Module(owner), PathSegment::PathIdentifier { id }) => {
+                    let binding = self.modules[owner].declared.get(id).ok_or_else(unresolved)?;
+                    if !self.modules[owner].public.contains(id) && !self.within(module, owner) {
+                        return Err(SyntaxErrorType::PrivateItem { item: id.clone(), module: self.modules[owner].path.join("::") });
+                    }
+                    binding.clone()
+                },
+                (Binding::Module(owner), PathSegment::SuperPath) => Binding::Module(self.modules[owner].parent.ok_or_else(unresolved)?),
+                (Binding::Item(enum_, ItemKind::Enum), PathSegment::PathIdentifier { id }) => Binding::Variant(enum_, id.clone()),
+                _ => return Err(unresolved()),
+            };
+        }
+        Ok(binding)
+    }
+
+    /// Checks that `field` of the struct `struct_` can be named from `module`
+    fn check_field(&mut self, struct_: &str, field: &str, module: usize, context: &TokenContext) {
+        if let Some((owner, fields)) = self.structs.get(struct_) {
+            let private = fields.iter().any(|candidate| candidate.name == field && !candidate.public);
+            if private && !self.within(module, *owner) {
+                let item = format!("{}.{}", struct_.rsplit("::").next().unwrap_or(struct_), field);
+                let module = self.modules[*owner].path.join("::");
+                self.error(SyntaxErrorType::PrivateItem { item, module }, context);
+            }
+        }
+    }
+
+    /// Checks a field access whose struct isn't known before the program runs.
+    /// The access is only rejected if every struct with such a field keeps it
+    /// private from `module`.
+    fn check_field_access(&mut self, field: &str, module: usize, context: &TokenContext) {
+        let mut owners: Vec<(&String, usize, bool)> = self.structs.iter()
+            .filter_map(|(struct_, (owner, fields))| {
+                let candidate = fields.iter().find(|candidate| candidate.name == field)?;
+                Some((struct_, *owner, candidate.public))
+            })
+            .collect();
+        owners.sort();
+
+        if let (false, Some((struct_, _, _))) = (owners.iter().any(|(_, owner, public)| *public || self.within(module, *owner)), owners.first()) {
+            let struct_ = struct_.to_string();
+            self.check_field(&struct_, field, module, context);
+        }
+    }
+
+    /// Resolves the name of a struct, enum or trait. Single names that aren't
+    /// items are left alone since they may be builtin types such as `str`.
+    fn resolve_type_name(&mut self, name: &mut String, module: usize, context: &TokenContext) {
+        let path = PathSegment::from_name(name);
+        match self.resolve_path(module, &path) {
+            Ok(Binding::Item(full_name, ItemKind::Struct | ItemKind::Enum | ItemKind::Trait)) => *name = full_name,
+            _ if path.len() == 1 => {},
+            Err(error_type @ SyntaxErrorType::PrivateItem { .. }) => self.error(error_type, context),
+            _ => self.error(SyntaxErrorType::UnresolvedPath(name.clone()), context),
+        }
+    }
+
+    fn resolve_type(&mut self, type_: &mut Type, module: usize, type_parameters: &[String], context: &TokenContext) {
+        match type_ {
+            Type::Type { name } if type_parameters.contains(name) || name == "Self" => {},
+            Type::Type { name } => self.resolve_type_name(name, module, context),
+            Type::GenericType { name, types } => {
+                self.resolve_type_name(name, module, context);
+                for type_ in types {
+                    self.resolve_type(type_, module, type_parameters, context);
+                }
+            },
+            Type::Tuple { types } => {
+                for type_ in types {
+                    self.resolve_type(type_, module, type_parameters, context);
+                }
+            },
+            Type::Function { parameters, return_type } => {
+                for type_ in parameters {
+                    self.resolve_type(type_, module, type_parameters, context);
+                }
+                self.resolve_type(return_type, module, type_parameters, context);
+            },
+            Type::Array { type_, length: _ } | Type::Pointer { pointed: type_ } => {
+                self.resolve_type(type_, module, type_parameters, context);
+            },
+            _ => {},
+        }
+    }
+
+    fn resolve_trait(&mut self, trait_: &mut Trait, module: usize, type_parameters: &[String], context: &TokenContext) {
+        match trait_ {
+            Trait::Trait { name } => self.resolve_type_name(name, module, context),
+            Trait::GenericTrait { name, types } => {
+                self.resolve_type_name(name, module, context);
+                for type_ in types {
+                    self.resolve_type(type_, module, type_parameters, context);
+                }
+            },
+        }
+    }
+
+    fn resolve_item(&mut self, item: &mut Item, module: usize) {
+        match item {
+            Item::Function { header, body } => {
+                self.resolve_header(header, module, &[]);
+                let mut locals = header.parameters.clone().unwrap_or_default();
+                let type_parameters: Vec<String> = header.type_parameters.iter().map(|parameter| parameter.name.clone()).collect();
+                let context = header.context.clone();
+                self.resolve_block(body, module, &type_parameters, &mut locals, &context);
+            },
+            Item::FunctionDeclaration { header } => self.resolve_header(header, module, &[]),
+            Item::Struct { name, type_parameters, fields, context, public: _, attributes: _ } => {
+                *name = self.full_name(module, name);
+                for field in fields {
+                    self.resolve_type(&mut field.type_, module, type_parameters, context);
+                }
+            },
+            Item::Enum { name, type_parameters, fields, context, public: _, attributes: _ } => {
+                *name = self.full_name(module, name);
+                for field in fields {
+                    for type_ in &mut field.types {
+                        self.resolve_type(type_, module, type_parameters, context);
+                    }
+                }
+            },
+            Item::Trait { name, type_parameters: _, items, context: _, public: _, attributes: _ } => {
+                *name = self.full_name(module, name);
+                // Methods keep their names since they're found through the
+                // type of their first argument rather than by path
+                for item in items {
+                    self.resolve_method(item, module);
+                }
+            },
+            Item::Instance { trait_, type_, items, context, attributes: _ } => {
+                self.resolve_trait(trait_, module, &[], context);
+                self.resolve_type(type_, module, &[], context);
+                for item in items {
+                    self.resolve_method(item, module);
+                }
+            },
+            Item::ConstItem { name, type_, value, context, public: _, attributes: _ } | Item::StaticItem { name, type_, value, context, public: _, attributes: _ } => {
+                *name = self.full_name(module, name);
+                self.resolve_type(type_, module, &[], context);
+                self.resolve_expression(value, module, &[], &mut Vec::new(), &context.clone());
+            },
+            _ => {},
+        }
+    }
+
+    fn resolve_method(&mut self, item: &mut Item, module: usize) {
+        let name = match item {
+            Item::Function { header, body: _ } | Item::FunctionDeclaration { header } => header.name.clone(),
+            _ => return,
+        };
+        self.resolve_item(item, module);
+        if let Item::Function { header, body: _ } | Item::FunctionDeclaration { header } = item {
+            header.name = name;
+        }
+    }
+
+    fn resolve_header(&mut self, header: &mut FunctionHeader, module: usize, type_parameters: &[String]) {
+        header.name = self.full_name(module, &header.name);
+        let mut type_parameters = type_parameters.to_vec();
+        type_parameters.extend(header.type_parameters.iter().map(|parameter| parameter.name.clone()));
+
+        let context = header.context.clone();
+        for parameter in &mut header.type_parameters {
+            for bound in &mut parameter.bounds {
+                self.resolve_trait(bound, module, &type_parameters, &context);
+            }
+        }
+        for type_ in &mut header.types {
+            self.resolve_type(type_, module, &type_parameters, &context);
+        }
+        self.resolve_type(&mut header.return_type, module, &type_parameters, &context);
+    }
+
+    fn resolve_block(&mut self, block: &mut Block, module: usize, type_parameters: &[String], locals: &mut Vec<String>, context: &TokenContext) {
+        let scope = locals.len();
+        for statement in &mut block.statements {
+            match statement {
+                Statement::LetStatement { pattern, type_, type_context, expression, context }
+                | Statement::ConstStatement { pattern, type_, type_context, expression, context } => {
+                    self.resolve_expression(expression, module, type_parameters, locals, context);
+                    if let (Some(type_), Some(type_context)) = (type_, type_context) {
+                        self.resolve_type(type_, module, type_parameters, type_context);
+                    }
+                    self.resolve_pattern(pattern, module);
+                    locals.extend(pattern.bindings());
+                },
+                Statement::ExpressionStatement { expression } => {
+                    self.resolve_expression(expression, module, type_parameters, locals, context);
+                },
+                Statement::DeferStatement { body, context } => {
+                    self.resolve_block(body, module, type_parameters, locals, context);
+                },
+            }
+        }
+        self.resolve_expression(&mut block.expression, module, type_parameters, locals, context);
+        locals.truncate(scope);
+    }
+
+    fn resolve_pattern(&mut self, pattern: &mut Pattern, module: usize) {
+        match pattern {
+            Pattern::EnumPattern { path, patterns, context } => {
+                match self.resolve_path(module, path) {
+                    Ok(Binding::Variant(enum_, variant)) => {
+                        *path = vec![PathSegment::PathIdentifier { id: enum_ }, PathSegment::PathIdentifier { id: variant }];
+                    },
+                    Err(error_type @ SyntaxErrorType::PrivateItem { .. }) => self.error(error_type, context),
+                    _ => self.unresolved(module, path, context),
+                }
+                for pattern in patterns {
+                    self.resolve_pattern(pattern, module);
+                }
+            },
+            Pattern::TuplePattern { patterns } | Pattern::OrPattern { patterns, context: _ } => {
+                for pattern in patterns {
+                    self.resolve_pattern(pattern, module);
+                }
+            },
+            Pattern::BindingPattern { name: _, pattern, context: _ } => self.resolve_pattern(pattern, module),
+            Pattern::IdentifierPattern { .. } | Pattern::LiteralPattern { .. } | Pattern::RangePattern { .. } | Pattern::BlankPattern => {},
+        }
+    }
+
+    fn resolve_expression(&mut self, expression: &mut Expression, module: usize, type_parameters: &[String], locals: &mut Vec<String>, context: &TokenContext) {
+        match expression {
+            Expression::Variable { name, context: _ } => {
+                if let (false, Some(Binding::Item(full_name, ItemKind::Function | ItemKind::Value))) = (locals.contains(name), self.lookup(module, name)) {
+                    *name = full_name;
+                }
+            },
+            Expression::PathExpression { segments, context } => match self.resolve_path(module, segments) {
+                Ok(Binding::Item(name, ItemKind::Function | ItemKind::Value)) => {
+                    *expression = Expression::Variable { name, context: context.clone() };
+                },
+                Ok(Binding::Variant(enum_, variant)) => {
+                    *segments = vec![PathSegment::PathIdentifier { id: enum_ }, PathSegment::PathIdentifier { id: variant }];
+                },
+                Err(error_type @ SyntaxErrorType::PrivateItem { .. }) => self.error(error_type, context),
+                _ => self.unresolved(module, segments, context),
+            },
+            Expression::CallExpression { callee, arguments, context } => {
+                match callee.as_mut() {
+                    // `x.f()` may call a function of this module by its short name
+                    Expression::FieldExpression { left, right, context: _ } if right.parse::<usize>().is_err() => {
+                        self.resolve_expression(left, module, type_parameters, locals, context);
+                        if let (false, Some(Binding::Item(full_name, ItemKind::Function))) = (locals.contains(right), self.lookup(module, right)) {
+                            *right = full_name;
+                        }
+                    },
+                    callee => self.resolve_expression(callee, module, type_parameters, locals, context),
+                }
+                for argument in arguments {
+                    self.resolve_expression(argument, module, type_parameters, locals, context);
+                }
+            },
+            Expression::StructExpression { struct_, fields, base, context } => {
+                self.resolve_type_name(struct_, module, context);
+                for field in fields {
+                    self.check_field(struct_, &field.name, module, context);
+                    self.resolve_expression(&mut field.expression, module, type_parameters, locals, context);
+                }
+                if let Some(base) = base {
+                    self.resolve_expression(base, module, type_parameters, locals, context);
+                }
+            },
+            Expression::ClosureExpression { parameters, contexts: _, types, return_type, body, context: _ } => {
+                for type_ in types.iter_mut().chain(return_type) {
+                    self.resolve_type(type_, module, type_parameters, context);
+                }
+                let scope = locals.len();
+                locals.extend(parameters.iter().cloned());
+                self.resolve_expression(body, module, type_parameters, locals, context);
+                locals.truncate(scope);
+            },
+            Expression::MatchExpression { discriminant, branches, context } => {
+                self.resolve_expression(discriminant, module, type_parameters, locals, context);
+                for branch in branches {
+                    self.resolve_pattern(&mut branch.pattern, module);
+                    let scope = locals.len();
+                    locals.extend(branch.pattern.bindings());
+                    if let Some(guard) = &mut branch.guard {
+                        self.resolve_expression(guard, module, type_parameters, locals, context);
+                    }
+                    self.resolve_expression(&mut branch.consequent, module, type_parameters, locals, context);
+                    locals.truncate(scope);
+                }
+            },
+            Expression::ForExpression { label: _, pattern, iterator, body, context: _ } => {
+                self.resolve_expression(iterator, module, type_parameters, locals, context);
+                self.resolve_pattern(pattern, module);
+                let scope = locals.len();
+                locals.extend(pattern.bindings());
+                self.resolve_block(body, module, type_parameters, locals, context);
+                locals.truncate(scope);
+            },
+            Expression::IfExpression { condition, body, alternate, context } => {
+                self.resolve_expression(condition, module, type_parameters, locals, context);
+                self.resolve_block(body, module, type_parameters, locals, context);
+                self.resolve_block(alternate, module, type_parameters, locals, context);
+            },
+            Expression::WhileExpression { label: _, condition, body, context } => {
+                self.resolve_expression(condition, module, type_parameters, locals, context);
+                self.resolve_block(body, module, type_parameters, locals, context);
+            },
+            Expression::LoopExpression { label: _, body } | Expression::BlockExpression { body } => {
+                self.resolve_block(body, module, type_parameters, locals, context);
+            },
+            Expression::ArrayExpression { type_, elements, context } => {
+                self.resolve_type(type_, module, type_parameters, context);
+                for element in elements {
+                    self.resolve_expression(element, module, type_parameters, locals, context);
+                }
+            },
+            Expression::TupleExpression { elements } => {
+                for element in elements {
+                    self.resolve_expression(element, module, type_parameters, locals, context);
+                }
+            },
+            Expression::TypeCastExpression { value, type_ } => {
+                self.resolve_expression(value, module, type_parameters, locals, context);
+                self.resolve_type(type_, module, type_parameters, context);
+            },
+            Expression::ArrayRepeatExpression { value: left, length: right, context: _ }
+            | Expression::IndexExpression { indexed: left, argument: right, context: _ }
+            | Expression::AssignExpression { target: left, op: _, value: right, context: _ }
+            | Expression::BinaryOp { op: _, left, right, context: _ }
+            | Expression::CatchExpression { expression: left, result: right } => {
+                self.resolve_expression(left, module, type_parameters, locals, context);
+                self.resolve_expression(right, module, type_parameters, locals, context);
+            },
+            Expression::FieldExpression { left, right, context } => {
+                self.resolve_expression(left, module, type_parameters, locals, context);
+                if right.parse::<usize>().is_err() {
+                    self.check_field_access(right, module, context);
+                }
+            },
+            Expression::TryExpression { expression: child }
+            | Expression::UnaryOp { op: _, child, context: _ } => {
+                self.resolve_expression(child, module, type_parameters, locals, context);
+            },
+            Expression::ReturnExpression { returned } | Expression::BreakExpression { label: _, returned, context: _ } => {
+                if let Some(returned) = returned {
+                    self.resolve_expression(returned, module, type_parameters, locals, context);
+                }
+            },
+            Expression::ContinueExpression { .. }
+            | Expression::IntLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::CharLiteral { .. }
+            | Expression::BooleanLiteral { .. } => {},
+        }
+    }
+
+    fn error(&mut self, error_type: SyntaxErrorType, context: &TokenContext) {
+        self.errors.add_errors(SyntaxErrorCollector::from_error(error_type, context.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temporary directory of files, removed when it is dropped
+    struct Project {
+        root: PathBuf,
+    }
+
+    impl Drop for Project {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn project(name: &str, files: &[(&str, &str)]) -> Project {
+        let root = std::env::temp_dir().join(format!("polarix-modules-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (file, code) in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, code).unwrap();
+        }
+        Project { root }
+    }
+
+    fn load(project: &Project) -> Result<Vec<Item>, SyntaxErrorCollector> {
+        let filename = project.root.join("main.px").display().to_string();
+        let code = fs::read_to_string(&filename).unwrap();
+        ModuleLoader::load(&filename.clone(), Lexer::new(filename, code).parse(), &Config::new())
+    }
+
+    #[test]
+    fn load_file_modules() {
+        let project = project("files", &[
+            ("main.px", "mod shapes; fn main() {}"),
+            ("shapes/mod.px", "mod circle; fn f() {}"),
+            ("shapes/circle.px", "fn area() {}"),
+        ]);
+
+        assert!(matches!(
+            &load(&project).ok().unwrap()[..],
+            [Item::Module { name: _, items: Some(shapes), context: _, public: _, attributes: _ }, Item::Function { .. }]
+                if matches!(&shapes[..], [Item::Module { name: _, items: Some(circle), context: _, public: _, attributes: _ }, Item::Function { .. }]
+                    if circle.len() == 1)
+        ));
+    }
+
+    #[test]
+    fn load_missing_and_ambiguous_modules() {
+        let project = project("missing", &[
+            ("main.px", "mod a; mod b;"),
+            ("b.px", ""),
+            ("b/mod.px", ""),
+        ]);
+
+        assert!(matches!(load(&project), Err(collector) if matches!(&collector.errors[..], [
+            SyntaxError { error_type: SyntaxErrorType::ModuleNotFound(a), .. },
+            SyntaxError { error_type: SyntaxErrorType::AmbiguousModule(b), .. },
+        ] if a == "a" && b == "b")));
+    }
+
+    #[test]
+    fn load_module_cycle() {
+        let project = project("cycle", &[
+            ("main.px", "mod main;"),
+        ]);
+
+        assert!(matches!(load(&project), Err(collector) if matches!(&collector.errors[..],
+            [SyntaxError { error_type: SyntaxErrorType::ModuleCycle(files), .. }] if files.len() == 2)));
+    }
+
+    #[test]
+    fn load_skips_disabled_modules() {
+        let project = project("cfg", &[
+            ("main.px", "#[cfg(windows)] mod windows; #[cfg(not(windows))] mod unix;"),
+            ("unix.px", "#[cfg(windows)] mod registry; fn f() {}"),
+        ]);
+
+        assert!(matches!(
+            &load(&project).ok().unwrap()[..],
+            [Item::Module { items: Some(items), .. }] if matches!(&items[..], [Item::Function { .. }])
+        ));
+    }
+}
@@ -1,92 +1,192 @@
+use crate::tokens::TokenContext;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Item {
+    // No syntax produces imports yet
+    #[allow(dead_code)]
     Import {
         imported: String,
+        attributes: Vec<Attribute>,
     },
     Use {
-        used: String,
+        path: Vec<PathSegment>,
+        alias: Option<String>,
+        glob: bool,
+        context: TokenContext,
+        attributes: Vec<Attribute>,
+    },
+    Module {
+        name: String,
+        items: Option<Vec<Item>>,
+        context: TokenContext,
+        public: bool,
+        attributes: Vec<Attribute>,
+    },
+    #[allow(dead_code)]
+    Macro {
+        name: String,
+        context: TokenContext,
+        attributes: Vec<Attribute>,
     },
     Function {
         header: FunctionHeader,
         body: Block,
     },
+    FunctionDeclaration {
+        header: FunctionHeader,
+    },
     Struct {
         name: String,
         type_parameters: Vec<String>,
         fields: Vec<StructField>,
+        context: TokenContext,
+        public: bool,
+        attributes: Vec<Attribute>,
     },
     Enum {
         name: String,
         type_parameters: Vec<String>,
         fields: Vec<EnumField>,
+        context: TokenContext,
+        public: bool,
+        attributes: Vec<Attribute>,
     },
     Trait {
         name: String,
         type_parameters: Vec<String>,
         items: Vec<Item>,
+        context: TokenContext,
+        public: bool,
+        attributes: Vec<Attribute>,
     },
     Instance {
         trait_: Trait,
         type_: Type,
         items: Vec<Item>,
+        context: TokenContext,
+        attributes: Vec<Attribute>,
     },
+    // No syntax produces type aliases yet
+    #[allow(dead_code)]
     TypeAlias {
         newtype: String,
         oldtype: Type,
+        attributes: Vec<Attribute>,
     },
     ConstItem {
         name: String,
         type_: Type,
         value: Expression,
+        context: TokenContext,
+        public: bool,
+        attributes: Vec<Attribute>,
+    },
+    StaticItem {
+        name: String,
+        type_: Type,
+        value: Expression,
+        context: TokenContext,
+        public: bool,
+        attributes: Vec<Attribute>,
     },
 }
 
+#[derive(Debug, Clone)]
 pub struct FunctionHeader {
-    name: String,
-    parameters: Option<Vec<String>>,
-    types: Vec<Type>,
-    return_type: Type,
+    pub name: String,
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Option<Vec<String>>,
+    /// Where each parameter is named
+    pub contexts: Vec<TokenContext>,
+    pub types: Vec<Type>,
+    pub return_type: Type,
+    pub public: bool,
+    pub attributes: Vec<Attribute>,
+    pub context: TokenContext,
 }
 
+#[derive(Debug, Clone)]
+pub struct TypeParameter {
+    pub name: String,
+    pub bounds: Vec<Trait>,
+}
+
+#[derive(Debug, Clone)]
 pub struct StructField {
-    name: String,
-    type_: Type,
+    pub name: String,
+    pub type_: Type,
+    pub public: bool,
+    pub attributes: Vec<Attribute>,
 }
 
+#[derive(Debug, Clone)]
 pub struct EnumField {
-    name: String,
-    types: Vec<Type>,
+    pub name: String,
+    pub types: Vec<Type>,
+    pub attributes: Vec<Attribute>,
+}
+
+/// An attribute such as `#[inline]` or `#[deprecated("use bar")]`
+#[derive(Debug, Clone)]
+pub struct Attribute {
+    pub meta: Meta,
+    pub context: TokenContext,
+}
+
+/// The contents of an attribute, which may nest as in `#[cfg(not(debug))]`
+#[derive(Debug, Clone)]
+pub enum Meta {
+    Word(String),
+    #[allow(dead_code)]
+    Literal(Expression),
+    NameValue(String, Expression),
+    List(String, Vec<Meta>),
 }
 
 // Expression nodes
 
+#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Expression {
     ForExpression {
+        label: Option<String>,
         pattern: Pattern,
         iterator: Box<Expression>,
         body: Block,
+        context: TokenContext,
     },
     IfExpression {
         condition: Box<Expression>,
         body: Block,
         alternate: Block,
+        context: TokenContext,
     },
     WhileExpression {
+        label: Option<String>,
         condition: Box<Expression>,
         body: Block,
+        context: TokenContext,
     },
     LoopExpression {
+        label: Option<String>,
         body: Block,
     },
     MatchExpression {
         discriminant: Box<Expression>,
         branches: Vec<MatchBranch>,
+        context: TokenContext,
     },
     BlockExpression {
         body: Block,
     },
+    // No syntax produces these yet
+    #[allow(dead_code)]
     TryExpression {
         expression: Box<Expression>,
     },
+    #[allow(dead_code)]
     CatchExpression {
         expression: Box<Expression>,
         result: Box<Expression>,
@@ -94,95 +194,195 @@ pub enum Expression {
     ArrayExpression {
         type_: Type,
         elements: Vec<Expression>,
+        context: TokenContext,
+    },
+    ArrayRepeatExpression {
+        value: Box<Expression>,
+        length: Box<Expression>,
+        context: TokenContext,
+    },
+    TupleExpression {
+        elements: Vec<Expression>,
     },
     CallExpression {
         callee: Box<Expression>,
         arguments: Vec<Expression>,
+        context: TokenContext,
     },
     IndexExpression {
         indexed: Box<Expression>,
         argument: Box<Expression>,
+        context: TokenContext,
     },
     FieldExpression {
         left: Box<Expression>,
         right: String,
+        context: TokenContext,
     },
+    // No syntax produces casts yet
+    #[allow(dead_code)]
     TypeCastExpression {
         value: Box<Expression>,
         type_: Type,
     },
+    ClosureExpression {
+        parameters: Vec<String>,
+        /// Where each parameter is named
+        contexts: Vec<TokenContext>,
+        types: Vec<Type>,
+        return_type: Option<Type>,
+        body: Box<Expression>,
+        context: TokenContext,
+    },
     ReturnExpression {
         returned: Option<Box<Expression>>,
     },
     BreakExpression {
+        label: Option<String>,
         returned: Option<Box<Expression>>,
+        context: TokenContext,
+    },
+    ContinueExpression {
+        label: Option<String>,
+        context: TokenContext,
     },
-    ContinueExpression,
     StructExpression {
         struct_: String,
         fields: Vec<StructExpressionField>,
+        base: Option<Box<Expression>>,
+        context: TokenContext,
+    },
+    AssignExpression {
+        target: Box<Expression>,
+        op: Option<Operator>,
+        value: Box<Expression>,
+        context: TokenContext,
     },
     PathExpression {
-        source: PathSegment,
-        member: PathSegment,
+        segments: Vec<PathSegment>,
+        context: TokenContext,
     },
     BinaryOp {
         op: Operator,
         left: Box<Expression>,
         right: Box<Expression>,
+        context: TokenContext,
     },
     UnaryOp {
         op: Operator,
         child: Box<Expression>,
+        context: TokenContext,
     },
     Variable {
         name: String,
+        context: TokenContext,
     },
     IntLiteral {
-        value: isize,
+        value: i128,
+        context: TokenContext,
     },
     FloatLiteral {
         value: f64,
+        context: TokenContext,
     },
     StringLiteral {
         value: String,
+        context: TokenContext,
     },
     CharLiteral {
         value: char,
+        context: TokenContext,
     },
     BooleanLiteral {
         value: bool,
+        context: TokenContext,
     },
 }
 
+#[derive(Debug, Clone)]
 pub struct Block {
-    statements: Vec<Statement>,
-    expression: Box<Expression>,
+    pub statements: Vec<Statement>,
+    pub expression: Box<Expression>,
 }
 
+#[derive(Debug, Clone)]
 pub struct MatchBranch {
-    pattern: Pattern,
-    consequent: Expression,
+    pub pattern: Pattern,
+    pub guard: Option<Expression>,
+    pub consequent: Expression,
+    pub context: TokenContext,
 }
 
+#[derive(Debug, Clone)]
 pub struct StructExpressionField {
-    name: String,
-    expression: Expression,
+    pub name: String,
+    pub expression: Expression,
 }
 
-pub struct Pattern {
-
+#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
+pub enum Pattern {
+    IdentifierPattern {
+        name: String,
+        context: TokenContext,
+    },
+    TuplePattern {
+        patterns: Vec<Pattern>,
+    },
+    LiteralPattern {
+        literal: Box<Expression>,
+    },
+    EnumPattern {
+        path: Vec<PathSegment>,
+        patterns: Vec<Pattern>,
+        context: TokenContext,
+    },
+    OrPattern {
+        patterns: Vec<Pattern>,
+        context: TokenContext,
+    },
+    BindingPattern {
+        name: String,
+        pattern: Box<Pattern>,
+        context: TokenContext,
+    },
+    RangePattern {
+        start: Box<Expression>,
+        end: Box<Expression>,
+        inclusive: bool,
+        context: TokenContext,
+    },
+    BlankPattern,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub enum Operator {
     AddOperator,
     SubtractOperator,
     MultiplyOperator,
     DivideOperator,
     ModuloOperator,
+    ExponentOperator,
     NegateOperator,
+    EqualOperator,
+    NotEqualOperator,
+    LessThanOperator,
+    GreaterThanOperator,
+    LessEqualOperator,
+    GreaterEqualOperator,
+    AndOperator,
+    OrOperator,
+    NotOperator,
+    BitwiseAndOperator,
+    BitwiseOrOperator,
+    BitwiseXorOperator,
+    BitwiseNotOperator,
+    LeftShiftOperator,
+    RightShiftOperator,
 }
 
+#[derive(Debug, Clone)]
 pub enum PathSegment {
     PathIdentifier {
         id: String
@@ -193,20 +393,36 @@ pub enum PathSegment {
 
 // Statement nodes
 
+#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Statement {
     LetStatement {
         pattern: Pattern,
+        type_: Option<Type>,
+        /// Where the type is written, if it is
+        type_context: Option<TokenContext>,
         expression: Expression,
+        context: TokenContext,
     },
     ConstStatement {
         pattern: Pattern,
+        type_: Option<Type>,
+        /// Where the type is written, if it is
+        type_context: Option<TokenContext>,
         expression: Expression,
+        context: TokenContext,
     },
     ExpressionStatement {
         expression: Expression,
     },
+    DeferStatement {
+        body: Block,
+        context: TokenContext,
+    },
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub enum Type {
     Int8,
     Int16,
@@ -224,13 +440,27 @@ pub enum Type {
     Float64,
     Boolean,
     Char,
+    Tuple {
+        types: Vec<Type>,
+    },
     Array {
         type_: Box<Type>,
         length: usize,
     },
+    // No syntax produces pointer types yet
+    #[allow(dead_code)]
     Pointer {
         pointed: Box<Type>,
     },
+    Function {
+        parameters: Vec<Type>,
+        return_type: Box<Type>,
+    },
+    Inferred,
+    /// A type the type checker hasn't worked out yet, which never leaves it
+    Variable {
+        id: usize,
+    },
     Type {
         name: String,
     },
@@ -238,11 +468,14 @@ pub enum Type {
         name: String,
         types: Vec<Type>,
     },
+    // No syntax produces trait types yet
+    #[allow(dead_code)]
     Trait {
         trait_: Trait,
     },
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum Trait {
     Trait {
         name: String,
@@ -251,4 +484,291 @@ pub enum Trait {
         name: String,
         types: Vec<Type>,
     },
-}
\ No newline at end of file
+}
+
+impl Item {
+    pub fn attributes(&self) -> &[Attribute] {
+        match self {
+            Item::Function { header, body: _ } | Item::FunctionDeclaration { header } => &header.attributes,
+            Item::Import { attributes, .. }
+            | Item::Use { attributes, .. }
+            | Item::Module { attributes, .. }
+            | Item::Macro { attributes, .. }
+            | Item::Struct { attributes, .. }
+            | Item::Enum { attributes, .. }
+            | Item::Trait { attributes, .. }
+            | Item::Instance { attributes, .. }
+            | Item::TypeAlias { attributes, .. }
+            | Item::ConstItem { attributes, .. }
+            | Item::StaticItem { attributes, .. } => attributes,
+        }
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut Vec<Attribute> {
+        match self {
+            Item::Function { header, body: _ } | Item::FunctionDeclaration { header } => &mut header.attributes,
+            Item::Import { attributes, .. }
+            | Item::Use { attributes, .. }
+            | Item::Module { attributes, .. }
+            | Item::Macro { attributes, .. }
+            | Item::Struct { attributes, .. }
+            | Item::Enum { attributes, .. }
+            | Item::Trait { attributes, .. }
+            | Item::Instance { attributes, .. }
+            | Item::TypeAlias { attributes, .. }
+            | Item::ConstItem { attributes, .. }
+            | Item::StaticItem { attributes, .. } => attributes,
+        }
+    }
+}
+
+impl Meta {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Meta::Word(name) | Meta::NameValue(name, _) | Meta::List(name, _) => Some(name),
+            Meta::Literal(_) => None,
+        }
+    }
+}
+
+impl Expression {
+    /// The position of the expression, for the kinds of expression that keep
+    /// one
+    pub fn context(&self) -> Option<&TokenContext> {
+        match self {
+            Expression::ForExpression { context, .. }
+            | Expression::IfExpression { context, .. }
+            | Expression::WhileExpression { context, .. }
+            | Expression::MatchExpression { context, .. }
+            | Expression::ArrayExpression { context, .. }
+            | Expression::ArrayRepeatExpression { context, .. }
+            | Expression::CallExpression { context, .. }
+            | Expression::IndexExpression { context, .. }
+            | Expression::FieldExpression { context, .. }
+            | Expression::BreakExpression { context, .. }
+            | Expression::ContinueExpression { context, .. }
+            | Expression::StructExpression { context, .. }
+            | Expression::AssignExpression { context, .. }
+            | Expression::PathExpression { context, .. }
+            | Expression::BinaryOp { context, .. }
+            | Expression::UnaryOp { context, .. }
+            | Expression::ClosureExpression { context, .. }
+            | Expression::Variable { context, .. }
+            | Expression::IntLiteral { context, .. }
+            | Expression::FloatLiteral { context, .. }
+            | Expression::StringLiteral { context, .. }
+            | Expression::CharLiteral { context, .. }
+            | Expression::BooleanLiteral { context, .. } => Some(context),
+            Expression::LoopExpression { .. }
+            | Expression::BlockExpression { .. }
+            | Expression::TryExpression { .. }
+            | Expression::CatchExpression { .. }
+            | Expression::TupleExpression { .. }
+            | Expression::TypeCastExpression { .. }
+            | Expression::ReturnExpression { .. } => None,
+        }
+    }
+}
+
+impl Pattern {
+    /// The names of every variable bound by the pattern
+    pub fn bindings(&self) -> Vec<String> {
+        match self {
+            Pattern::IdentifierPattern { name, context: _ } => vec![name.clone()],
+            Pattern::TuplePattern { patterns } | Pattern::EnumPattern { path: _, patterns, context: _ } => {
+                patterns.iter().flat_map(|pattern| pattern.bindings()).collect()
+            },
+            // Every alternative binds the same names
+            Pattern::OrPattern { patterns, context: _ } => match patterns.first() {
+                Some(pattern) => pattern.bindings(),
+                None => Vec::new(),
+            },
+            Pattern::BindingPattern { name, pattern, context: _ } => {
+                let mut bindings = vec![name.clone()];
+                bindings.extend(pattern.bindings());
+                bindings
+            },
+            Pattern::LiteralPattern { literal: _ } | Pattern::RangePattern { .. } | Pattern::BlankPattern => Vec::new(),
+        }
+    }
+}
+
+impl PathSegment {
+    pub fn as_string(&self) -> String {
+        match self {
+            PathSegment::PathIdentifier { id } => id.clone(),
+            PathSegment::SuperPath => String::from("super"),
+            PathSegment::SelfPath => String::from("self"),
+        }
+    }
+
+    /// Splits a name such as `super::shapes::Point` into its segments
+    pub fn from_name(name: &str) -> Vec<PathSegment> {
+        name.split("::").map(|segment| match segment {
+            "super" => PathSegment::SuperPath,
+            "self" => PathSegment::SelfPath,
+            id => PathSegment::PathIdentifier { id: id.to_string() },
+        }).collect()
+    }
+
+    pub fn path_as_string(path: &[PathSegment]) -> String {
+        path.iter().map(|segment| segment.as_string()).collect::<Vec<String>>().join("::")
+    }
+
+    /// The enum and variant names of a path such as `Shape::Circle`
+    pub fn variant(path: &[PathSegment]) -> Option<(&String, &String)> {
+        match path {
+            [PathSegment::PathIdentifier { id: enum_ }, PathSegment::PathIdentifier { id: variant }] => Some((enum_, variant)),
+            _ => None,
+        }
+    }
+}
+
+impl Type {
+    /// The empty tuple `()`, used for expressions and functions that produce
+    /// no meaningful value
+    pub fn unit() -> Type {
+        Type::Tuple { types: Vec::new() }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(self,
+            Type::Int8 | Type::Int16 | Type::Int32 | Type::Int64 | Type::Int128 | Type::IntSize
+            | Type::UInt8 | Type::UInt16 | Type::UInt32 | Type::UInt64 | Type::UInt128 | Type::UIntSize)
+    }
+
+    /// The smallest and largest values of an integer type
+    pub fn integer_range(&self) -> Option<(i128, i128)> {
+        let range = match self {
+            Type::Int8 => (i8::MIN as i128, i8::MAX as i128),
+            Type::Int16 => (i16::MIN as i128, i16::MAX as i128),
+            Type::Int32 => (i32::MIN as i128, i32::MAX as i128),
+            Type::Int64 => (i64::MIN as i128, i64::MAX as i128),
+            Type::Int128 => (i128::MIN, i128::MAX),
+            Type::IntSize => (isize::MIN as i128, isize::MAX as i128),
+            Type::UInt8 => (0, u8::MAX as i128),
+            Type::UInt16 => (0, u16::MAX as i128),
+            Type::UInt32 => (0, u32::MAX as i128),
+            Type::UInt64 => (0, u64::MAX as i128),
+            // Integers are held in an `i128`, so a `u128` can't go past the
+            // largest `i128`
+            Type::UInt128 => (0, i128::MAX),
+            Type::UIntSize => (0, usize::MAX as i128),
+            _ => return None,
+        };
+        Some(range)
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::Float32 | Type::Float64)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+
+    /// Replaces the named type parameters in this type with the types bound
+    /// to them
+    pub fn substitute(&self, bindings: &HashMap<String, Type>) -> Type {
+        match self {
+            Type::Type { name } => bindings.get(name).cloned().unwrap_or_else(|| self.clone()),
+            Type::Tuple { types } => Type::Tuple {
+                types: types.iter().map(|type_| type_.substitute(bindings)).collect(),
+            },
+            Type::Array { type_, length } => Type::Array {
+                type_: Box::new(type_.substitute(bindings)),
+                length: *length,
+            },
+            Type::Pointer { pointed } => Type::Pointer { pointed: Box::new(pointed.substitute(bindings)) },
+            Type::Function { parameters, return_type } => Type::Function {
+                parameters: parameters.iter().map(|type_| type_.substitute(bindings)).collect(),
+                return_type: Box::new(return_type.substitute(bindings)),
+            },
+            Type::GenericType { name, types } => Type::GenericType {
+                name: name.clone(),
+                types: types.iter().map(|type_| type_.substitute(bindings)).collect(),
+            },
+            _ => self.clone(),
+        }
+    }
+
+    pub fn as_string(&self) -> String {
+        match self {
+            Type::Int8                   => String::from("i8"),
+            Type::Int16                  => String::from("i16"),
+            Type::Int32                  => String::from("i32"),
+            Type::Int64                  => String::from("i64"),
+            Type::Int128                 => String::from("i128"),
+            Type::IntSize                => String::from("isize"),
+            Type::UInt8                  => String::from("u8"),
+            Type::UInt16                 => String::from("u16"),
+            Type::UInt32                 => String::from("u32"),
+            Type::UInt64                 => String::from("u64"),
+            Type::UInt128                => String::from("u128"),
+            Type::UIntSize               => String::from("usize"),
+            Type::Float32                => String::from("f32"),
+            Type::Float64                => String::from("f64"),
+            Type::Boolean                => String::from("bool"),
+            Type::Char                   => String::from("char"),
+            Type::Tuple { types } if types.len() == 1 => format!("({},)", types[0].as_string()),
+            Type::Tuple { types }        => format!("({})", Self::list_as_string(types)),
+            Type::Array { type_, length } => format!("[{}; {}]", type_.as_string(), length),
+            Type::Pointer { pointed }    => format!("{}&", pointed.as_string()),
+            Type::Function { parameters, return_type } if **return_type == Type::unit() => {
+                format!("fn({})", Self::list_as_string(parameters))
+            },
+            Type::Function { parameters, return_type } => {
+                format!("fn({}): {}", Self::list_as_string(parameters), return_type.as_string())
+            },
+            Type::Inferred               => String::from("_"),
+            Type::Variable { id: _ }     => String::from("_"),
+            Type::Type { name }          => name.clone(),
+            Type::GenericType { name, types } => format!("{}<{}>", name, Self::list_as_string(types)),
+            Type::Trait { trait_ }       => trait_.as_string(),
+        }
+    }
+
+    /// Whether a value of this type may be used where `expected` is required.
+    /// Inferred types, such as the return type of an unannotated closure, are
+    /// compatible with anything.
+    pub fn conforms_to(&self, expected: &Type) -> bool {
+        match (self, expected) {
+            (Type::Inferred, _) | (_, Type::Inferred) => true,
+            (Type::Function { parameters: x, return_type: x_return },
+             Type::Function { parameters: y, return_type: y_return }) => {
+                x.len() == y.len()
+                    && x.iter().zip(y).all(|(x, y)| y.conforms_to(x))
+                    && x_return.conforms_to(y_return)
+            },
+            (Type::Tuple { types: x }, Type::Tuple { types: y }) => {
+                x.len() == y.len() && x.iter().zip(y).all(|(x, y)| x.conforms_to(y))
+            },
+            (Type::Array { type_: x, length: x_length }, Type::Array { type_: y, length: y_length }) => {
+                x_length == y_length && x.conforms_to(y)
+            },
+            (Type::GenericType { name: x_name, types: x }, Type::GenericType { name: y_name, types: y }) => {
+                x_name == y_name && x.len() == y.len() && x.iter().zip(y).all(|(x, y)| x.conforms_to(y))
+            },
+            _ => self == expected,
+        }
+    }
+
+    pub fn list_as_string(types: &[Type]) -> String {
+        types.iter().map(|type_| type_.as_string()).collect::<Vec<String>>().join(", ")
+    }
+}
+
+impl Trait {
+    pub fn name(&self) -> &String {
+        match self {
+            Trait::Trait { name } | Trait::GenericTrait { name, types: _ } => name,
+        }
+    }
+
+    pub fn as_string(&self) -> String {
+        match self {
+            Trait::Trait { name } => name.clone(),
+            Trait::GenericTrait { name, types } => format!("{}<{}>", name, Type::list_as_string(types)),
+        }
+    }
+}
@@ -1,48 +1,1548 @@
 use crate::lexer::*;
+use crate::macros::*;
 use crate::nodes::*;
-use crate::tokens::Token;
+use crate::tokens::{Token, TokenContent, TokenContext, ITEM_KEYWORDS, STATEMENT_KEYWORDS};
 use crate::tokens::TokenContent::*;
 use crate::syntax_errors::*;
+use crate::suggestions;
+use std::collections::HashMap;
+use std::mem::discriminant;
+use std::rc::Rc;
+
+type ParseResult<T> = Result<T, SyntaxErrorCollector>;
+
+/// How deeply macro invocations may expand into further invocations
+const MAX_MACRO_DEPTH: usize = 24;
 
 impl Lexer {
-    // pub fn parse(&mut self) -> Result<Vec<Item>, SyntaxError> {
-    //     let items: Vec<Item> = Vec::new();
+    pub fn parse(&mut self) -> ParseResult<Vec<Item>> {
+        let mut items: Vec<Item> = Vec::new();
+        let mut errors = SyntaxErrorCollector::new();
+
+        while !self.at_end() {
+            match self.parse_item() {
+                Ok(item) => items.push(item),
+                Err(error) => {
+                    errors.add_errors(error);
+                    self.synchronize();
+                },
+            }
+        }
+
+        if errors.is_empty() { Ok(items) } else { Err(errors) }
+    }
+
+    // Token helpers
+
+    fn peek(&mut self, program_context: ProgramContext) -> LexerResult {
+        let context = self.context.clone();
+        let result = self.next(program_context);
+        self.context = context;
+        result
+    }
+
+    fn peek_content(&mut self, program_context: ProgramContext) -> Option<TokenContent> {
+        match self.peek(program_context) {
+            Ok(Some(token)) => Some(token.content),
+            _ => None,
+        }
+    }
+
+    fn peek_context(&mut self) -> TokenContext {
+        match self.peek(ProgramContext::NormalContext) {
+            Ok(Some(token)) => token.context,
+            Err(error) => error.context,
+            Ok(None) => self.context.clone(),
+        }
+    }
+
+    fn peek_is(&mut self, content: &TokenContent, program_context: ProgramContext) -> bool {
+        matches!(self.peek_content(program_context), Some(x) if discriminant(&x) == discriminant(content))
+    }
+
+    fn at_end(&mut self) -> bool {
+        matches!(self.peek(ProgramContext::NormalContext), Ok(None))
+    }
+
+    fn eat(&mut self, content: &TokenContent, program_context: ProgramContext) -> bool {
+        let is_next = self.peek_is(content, program_context.clone());
+        if is_next {
+            let _ = self.next(program_context);
+        }
+        is_next
+    }
+
+    fn next_token(&mut self, program_context: ProgramContext, expected: SyntaxErrorType) -> ParseResult<Token> {
+        match self.next(program_context) {
+            Ok(Some(token)) => Ok(token),
+            Ok(None) => Err(SyntaxErrorCollector::from_error(expected, self.context.clone())),
+            Err(lex_error) => Err(SyntaxErrorCollector::from_lexer_error(lex_error)),
+        }
+    }
+
+    fn expect(&mut self, content: TokenContent, program_context: ProgramContext) -> ParseResult<Token> {
+        let context = self.context.clone();
+        let token = self.next_token(program_context.clone(), SyntaxErrorType::TokenExpected(content.clone()))?;
+        if discriminant(&token.content) == discriminant(&content) {
+            Ok(token)
+        } else {
+            // Leave the unexpected token for whoever recovers from the error
+            self.context = context;
+            Err(SyntaxErrorCollector::from_error(SyntaxErrorType::TokenExpected(content), token.context))
+        }
+    }
+
+    fn expect_identifier(&mut self, program_context: ProgramContext) -> ParseResult<(String, TokenContext)> {
+        match self.next_token(program_context, SyntaxErrorType::IdentifierExpected)? {
+            Token { content: Identifier(name), context } => Ok((name, context)),
+            Token { content: _, context } => {
+                Err(SyntaxErrorCollector::from_error(SyntaxErrorType::IdentifierExpected, context))
+            },
+        }
+    }
+
+    fn synchronize(&mut self) {
+        // Skip tokens until something that can start a new item
+        loop {
+            match self.peek(ProgramContext::NormalContext) {
+                Ok(Some(token)) if Self::starts_item(&token.content) => return,
+                Ok(None) => return,
+                _ => { let _ = self.next(ProgramContext::NormalContext); },
+            }
+        }
+    }
+
+    fn starts_item(content: &TokenContent) -> bool {
+        matches!(content, HashOperator | PubKeyword | MacroKeyword | ModKeyword | UseKeyword | FnKeyword | StructKeyword | EnumKeyword | TraitKeyword | InstanceKeyword | ConstKeyword | StaticKeyword)
+    }
+
+    // Items
+
+    fn parse_item(&mut self) -> ParseResult<Item> {
+        let attributes = self.parse_attributes()?;
+        let public = match self.peek(ProgramContext::NormalContext) {
+            Ok(Some(Token { content: PubKeyword, context })) => {
+                let _ = self.next(ProgramContext::NormalContext);
+                Some(context)
+            },
+            _ => None,
+        };
+
+        let mut item = self.parse_item_body()?;
+        if let Some(context) = public {
+            match &mut item {
+                Item::Function { header, body: _ } => header.public = true,
+                Item::Module { public, .. }
+                | Item::Struct { public, .. }
+                | Item::Enum { public, .. }
+                | Item::Trait { public, .. }
+                | Item::ConstItem { public, .. }
+                | Item::StaticItem { public, .. } => *public = true,
+                _ => return Err(SyntaxErrorCollector::from_error(SyntaxErrorType::UnexpectedVisibility, context)),
+            }
+        }
+        *item.attributes_mut() = attributes;
+        Ok(item)
+    }
+
+    /// Parses any number of attributes such as `#[inline]`
+    fn parse_attributes(&mut self) -> ParseResult<Vec<Attribute>> {
+        let mut attributes = Vec::new();
+        while let Ok(Some(Token { content: HashOperator, context })) = self.peek(ProgramContext::NormalContext) {
+            let _ = self.next(ProgramContext::NormalContext);
+            self.expect(LeftSquareBracketOperator, ProgramContext::NormalContext)?;
+            let meta = self.parse_meta()?;
+            self.expect(RightSquareBracketOperator, ProgramContext::NormalContext)?;
+            attributes.push(Attribute { meta, context });
+        }
+        Ok(attributes)
+    }
+
+    fn parse_meta(&mut self) -> ParseResult<Meta> {
+        let token = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::AttributeExpected)?;
+        // Keywords such as `not` are allowed as names inside attributes
+        let word = token.as_string();
+        let name = match token.content {
+            Identifier(name) => name,
+            content => match Self::literal(content, &token.context) {
+                Some(literal) => return Ok(Meta::Literal(literal)),
+                None if word.chars().all(char::is_alphanumeric) => word,
+                None => return Err(SyntaxErrorCollector::from_error(SyntaxErrorType::AttributeExpected, token.context)),
+            },
+        };
+
+        if self.eat(&EqualOperator, ProgramContext::NormalContext) {
+            let token = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::LiteralExpected)?;
+            return match Self::literal(token.content, &token.context) {
+                Some(literal) => Ok(Meta::NameValue(name, literal)),
+                None => Err(SyntaxErrorCollector::from_error(SyntaxErrorType::LiteralExpected, token.context)),
+            };
+        }
+
+        if !self.eat(&LeftParenthesisOperator, ProgramContext::NormalContext) {
+            return Ok(Meta::Word(name));
+        }
+        let mut arguments = Vec::new();
+        while !self.eat(&RightParenthesisOperator, ProgramContext::NormalContext) {
+            arguments.push(self.parse_meta()?);
+
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) {
+                self.expect(RightParenthesisOperator, ProgramContext::NormalContext)?;
+                break;
+            }
+        }
+        Ok(Meta::List(name, arguments))
+    }
+
+    fn literal(content: TokenContent, context: &TokenContext) -> Option<Expression> {
+        match content {
+            IntToken(value) => Some(Expression::IntLiteral { value, context: context.clone() }),
+            FloatToken(value) => Some(Expression::FloatLiteral { value, context: context.clone() }),
+            StringToken(value) => Some(Expression::StringLiteral { value, context: context.clone() }),
+            CharToken(value) => Some(Expression::CharLiteral { value, context: context.clone() }),
+            TrueKeyword => Some(Expression::BooleanLiteral { value: true, context: context.clone() }),
+            FalseKeyword => Some(Expression::BooleanLiteral { value: false, context: context.clone() }),
+            _ => None,
+        }
+    }
+
+    fn parse_item_body(&mut self) -> ParseResult<Item> {
+        match self.peek(ProgramContext::NormalContext) {
+            Ok(Some(Token { content: MacroKeyword, context: _ })) => self.parse_macro(),
+            Ok(Some(Token { content: Identifier(name), context })) if self.peek_macro_call() => {
+                let _ = self.next(ProgramContext::NormalContext);
+                let code = self.parse_macro_call(name.clone(), &context)?;
+                self.eat(&SemicolonOperator, ProgramContext::NormalContext);
+                self.splice(name, context, &code);
+                self.parse_item()
+            },
+            Ok(Some(Token { content: ModKeyword, context: _ })) => self.parse_module(),
+            Ok(Some(Token { content: UseKeyword, context: _ })) => self.parse_use(),
+            Ok(Some(Token { content: FnKeyword, context: _ })) => self.parse_function(false),
+            Ok(Some(Token { content: StructKeyword, context: _ })) => self.parse_struct(),
+            Ok(Some(Token { content: EnumKeyword, context: _ })) => self.parse_enum(),
+            Ok(Some(Token { content: TraitKeyword, context: _ })) => self.parse_trait(),
+            Ok(Some(Token { content: InstanceKeyword, context: _ })) => self.parse_instance(),
+            Ok(Some(Token { content: ConstKeyword, context: _ }))
+            | Ok(Some(Token { content: StaticKeyword, context: _ })) => self.parse_global(),
+            Ok(Some(token)) => {
+                let _ = self.next(ProgramContext::NormalContext);
+                let mut errors = SyntaxErrorCollector::from_error(SyntaxErrorType::ItemExpected, token.context);
+                if let Identifier(name) = &token.content {
+                    errors.suggest_name(name, ITEM_KEYWORDS.iter().copied());
+                }
+                Err(errors)
+            },
+            Ok(None) => Err(SyntaxErrorCollector::from_error(SyntaxErrorType::ItemExpected, self.context.clone())),
+            Err(lex_error) => {
+                let _ = self.next(ProgramContext::NormalContext);
+                Err(SyntaxErrorCollector::from_lexer_error(lex_error))
+            },
+        }
+    }
+
+    /// Parses `mod name { .. }` or `mod name;`, whose items are loaded from
+    /// another file later on
+    fn parse_module(&mut self) -> ParseResult<Item> {
+        self.expect(ModKeyword, ProgramContext::NormalContext)?;
+        let (name, context) = self.expect_identifier(ProgramContext::NormalContext)?;
+        if self.eat(&SemicolonOperator, ProgramContext::NormalContext) {
+            return Ok(Item::Module { name, items: None, context, public: false, attributes: Vec::new() });
+        }
+
+        self.expect(LeftCurlyBracketOperator, ProgramContext::NormalContext)?;
+        let mut items = Vec::new();
+        while !self.eat(&RightCurlyBracketOperator, ProgramContext::NormalContext) {
+            items.push(self.parse_item()?);
+        }
+        Ok(Item::Module { name, items: Some(items), context, public: false, attributes: Vec::new() })
+    }
+
+    fn parse_use(&mut self) -> ParseResult<Item> {
+        let context = self.expect(UseKeyword, ProgramContext::NormalContext)?.context;
+        let first = self.parse_path_segment()?;
+
+        let mut path = vec![first];
+        let mut glob = false;
+        while self.eat(&ScopeResolutionOperator, ProgramContext::NormalContext) {
+            if self.eat(&StarOperator, ProgramContext::NormalContext) {
+                glob = true;
+                break;
+            }
+            path.push(self.parse_path_segment()?);
+        }
+
+        let alias = if !glob && self.eat(&AsKeyword, ProgramContext::NormalContext) {
+            Some(self.expect_identifier(ProgramContext::NormalContext)?.0)
+        } else {
+            None
+        };
+        self.expect(SemicolonOperator, ProgramContext::NormalContext)?;
+        Ok(Item::Use { path, alias, glob, context, attributes: Vec::new() })
+    }
+
+    /// Parses a function, or with `declaration` set, also a bodiless function
+    /// declaration such as those found in traits
+    fn parse_function(&mut self, declaration: bool) -> ParseResult<Item> {
+        self.expect(FnKeyword, ProgramContext::NormalContext)?;
+        let (name, context) = self.expect_identifier(ProgramContext::NormalContext)?;
+
+        let type_parameters = if self.eat(&LeftChevronOperator, ProgramContext::NormalContext) {
+            self.parse_type_parameters()?
+        } else {
+            Vec::new()
+        };
+
+        self.expect(LeftParenthesisOperator, ProgramContext::NormalContext)?;
+        let mut parameters = Vec::new();
+        let mut contexts = Vec::new();
+        let mut types = Vec::new();
+        while !self.eat(&RightParenthesisOperator, ProgramContext::NormalContext) {
+            let (parameter, parameter_context) = self.expect_identifier(ProgramContext::NormalContext)?;
+            self.expect(ColonOperator, ProgramContext::NormalContext)?;
+            parameters.push(parameter);
+            contexts.push(parameter_context);
+            types.push(self.parse_type()?);
+
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) {
+                self.expect(RightParenthesisOperator, ProgramContext::NormalContext)?;
+                break;
+            }
+        }
+
+        // Functions without a return type return the unit type
+        let return_type = if self.eat(&ColonOperator, ProgramContext::NormalContext) {
+            self.parse_type()?
+        } else {
+            Type::unit()
+        };
+
+        let header = FunctionHeader { name, type_parameters, parameters: Some(parameters), contexts, types, return_type, public: false, attributes: Vec::new(), context };
+
+        if declaration && self.eat(&SemicolonOperator, ProgramContext::NormalContext) {
+            return Ok(Item::FunctionDeclaration { header });
+        }
+
+        let body = if self.eat(&EqualOperator, ProgramContext::NormalContext) {
+            let expression = self.parse_expression()?;
+            self.expect(SemicolonOperator, ProgramContext::NormalContext)?;
+            Block { statements: Vec::new(), expression: Box::new(expression) }
+        } else {
+            self.parse_block()?
+        };
+
+        Ok(Item::Function { header, body })
+    }
+
+    fn parse_global(&mut self) -> ParseResult<Item> {
+        let token = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::ItemExpected)?;
+        let (name, context) = self.expect_identifier(ProgramContext::NormalContext)?;
+        self.expect(ColonOperator, ProgramContext::NormalContext)?;
+        let type_ = self.parse_type()?;
+        self.expect(EqualOperator, ProgramContext::NormalContext)?;
+        let value = self.parse_expression()?;
+        self.expect(SemicolonOperator, ProgramContext::NormalContext)?;
+
+        match token.content {
+            StaticKeyword => Ok(Item::StaticItem { name, type_, value, context, public: false, attributes: Vec::new() }),
+            _ => Ok(Item::ConstItem { name, type_, value, context, public: false, attributes: Vec::new() }),
+        }
+    }
+
+    fn parse_type_parameters(&mut self) -> ParseResult<Vec<TypeParameter>> {
+        let mut type_parameters = Vec::new();
+        while !self.eat(&RightChevronOperator, ProgramContext::TypeContext) {
+            let (name, _) = self.expect_identifier(ProgramContext::NormalContext)?;
+            let mut bounds = Vec::new();
+            if self.eat(&ColonOperator, ProgramContext::NormalContext) {
+                bounds.push(self.parse_trait_reference()?);
+                while self.eat(&PlusOperator, ProgramContext::TypeContext) {
+                    bounds.push(self.parse_trait_reference()?);
+                }
+            }
+            type_parameters.push(TypeParameter { name, bounds });
+
+            if !self.eat(&CommaOperator, ProgramContext::TypeContext) {
+                self.expect(RightChevronOperator, ProgramContext::TypeContext)?;
+                break;
+            }
+        }
+        Ok(type_parameters)
+    }
+
+    fn parse_trait_reference(&mut self) -> ParseResult<Trait> {
+        let (name, _) = self.expect_identifier(ProgramContext::TypeContext)?;
+        let name = self.parse_type_path(name)?;
+        if self.eat(&LeftChevronOperator, ProgramContext::TypeContext) {
+            let types = self.parse_type_list(RightChevronOperator)?;
+            Ok(Trait::GenericTrait { name, types })
+        } else {
+            Ok(Trait::Trait { name })
+        }
+    }
+
+    fn parse_struct(&mut self) -> ParseResult<Item> {
+        self.expect(StructKeyword, ProgramContext::NormalContext)?;
+        let (name, context) = self.expect_identifier(ProgramContext::NormalContext)?;
+
+        let type_parameters = self.parse_type_parameter_names()?;
+
+        self.expect(LeftCurlyBracketOperator, ProgramContext::NormalContext)?;
+        let mut fields = Vec::new();
+        while !self.eat(&RightCurlyBracketOperator, ProgramContext::NormalContext) {
+            let attributes = self.parse_attributes()?;
+            let public = self.eat(&PubKeyword, ProgramContext::NormalContext);
+            let (name, _) = self.expect_identifier(ProgramContext::NormalContext)?;
+            self.expect(ColonOperator, ProgramContext::NormalContext)?;
+            fields.push(StructField { name, type_: self.parse_type()?, public, attributes });
+
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) {
+                self.expect(RightCurlyBracketOperator, ProgramContext::NormalContext)?;
+                break;
+            }
+        }
+
+        Ok(Item::Struct { name, type_parameters, fields, context, public: false, attributes: Vec::new() })
+    }
+
+    fn parse_enum(&mut self) -> ParseResult<Item> {
+        self.expect(EnumKeyword, ProgramContext::NormalContext)?;
+        let (name, context) = self.expect_identifier(ProgramContext::NormalContext)?;
+        let type_parameters = self.parse_type_parameter_names()?;
+
+        self.expect(LeftCurlyBracketOperator, ProgramContext::NormalContext)?;
+        let mut fields = Vec::new();
+        while !self.eat(&RightCurlyBracketOperator, ProgramContext::NormalContext) {
+            let attributes = self.parse_attributes()?;
+            let (name, _) = self.expect_identifier(ProgramContext::NormalContext)?;
+            let types = if self.eat(&LeftParenthesisOperator, ProgramContext::TypeContext) {
+                self.parse_type_list(RightParenthesisOperator)?
+            } else {
+                Vec::new()
+            };
+            fields.push(EnumField { name, types, attributes });
+
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) {
+                self.expect(RightCurlyBracketOperator, ProgramContext::NormalContext)?;
+                break;
+            }
+        }
+
+        Ok(Item::Enum { name, type_parameters, fields, context, public: false, attributes: Vec::new() })
+    }
+
+    /// Parses the type parameters of a struct, enum or trait, which unlike
+    /// those of functions have no bounds
+    fn parse_type_parameter_names(&mut self) -> ParseResult<Vec<String>> {
+        let mut type_parameters = Vec::new();
+        if self.eat(&LeftChevronOperator, ProgramContext::TypeContext) {
+            while !self.eat(&RightChevronOperator, ProgramContext::TypeContext) {
+                type_parameters.push(self.expect_identifier(ProgramContext::TypeContext)?.0);
+
+                if !self.eat(&CommaOperator, ProgramContext::TypeContext) {
+                    self.expect(RightChevronOperator, ProgramContext::TypeContext)?;
+                    break;
+                }
+            }
+        }
+        Ok(type_parameters)
+    }
+
+    fn parse_trait(&mut self) -> ParseResult<Item> {
+        self.expect(TraitKeyword, ProgramContext::NormalContext)?;
+        let (name, context) = self.expect_identifier(ProgramContext::NormalContext)?;
+
+        let type_parameters = self.parse_type_parameter_names()?;
+        let items = self.parse_member_functions()?;
+        Ok(Item::Trait { name, type_parameters, items, context, public: false, attributes: Vec::new() })
+    }
+
+    fn parse_instance(&mut self) -> ParseResult<Item> {
+        let context = self.expect(InstanceKeyword, ProgramContext::NormalContext)?.context;
+        let trait_ = self.parse_trait_reference()?;
+        let type_ = self.parse_type()?;
+        let items = self.parse_member_functions()?;
+        Ok(Item::Instance { trait_, type_, items, context, attributes: Vec::new() })
+    }
+
+    fn parse_member_functions(&mut self) -> ParseResult<Vec<Item>> {
+        self.expect(LeftCurlyBracketOperator, ProgramContext::NormalContext)?;
+        let mut items = Vec::new();
+        while !self.eat(&RightCurlyBracketOperator, ProgramContext::NormalContext) {
+            let attributes = self.parse_attributes()?;
+            let mut item = self.parse_function(true)?;
+            *item.attributes_mut() = attributes;
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    // Macros
+
+    fn parse_macro(&mut self) -> ParseResult<Item> {
+        self.expect(MacroKeyword, ProgramContext::NormalContext)?;
+        let (name, context) = self.expect_identifier(ProgramContext::NormalContext)?;
+
+        self.expect(LeftCurlyBracketOperator, ProgramContext::NormalContext)?;
+        let mut rules = Vec::new();
+        while !self.eat(&RightCurlyBracketOperator, ProgramContext::NormalContext) {
+            self.expect(LeftParenthesisOperator, ProgramContext::NormalContext)?;
+            let matcher = self.parse_macro_tokens(RightParenthesisOperator, true)?;
+            self.expect(DoubleArrowOperator, ProgramContext::NormalContext)?;
+            self.expect(LeftCurlyBracketOperator, ProgramContext::NormalContext)?;
+            let transcriber = self.parse_macro_tokens(RightCurlyBracketOperator, false)?;
+            rules.push(MacroRule { matcher, transcriber });
+
+            // Rules may be separated by a comma or semicolon
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) {
+                self.eat(&SemicolonOperator, ProgramContext::NormalContext);
+            }
+        }
+
+        self.macros.insert(name.clone(), Rc::new(Macro { name: name.clone(), rules }));
+        Ok(Item::Macro { name, context, attributes: Vec::new() })
+    }
+
+    /// Parses the tokens of a matcher or transcriber up to `close`, keeping
+    /// delimiters balanced
+    fn parse_macro_tokens(&mut self, close: TokenContent, matcher: bool) -> ParseResult<Vec<MacroToken>> {
+        let mut tokens = Vec::new();
+        loop {
+            let token = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::TokenExpected(close.clone()))?;
+            let text = self.source(token.context.index, self.context.index);
+            let closing = match token.content {
+                ref content if discriminant(content) == discriminant(&close) => return Ok(tokens),
+                DollarOperator => {
+                    tokens.push(self.parse_macro_variable(matcher)?);
+                    continue;
+                },
+                LeftParenthesisOperator => RightParenthesisOperator,
+                LeftSquareBracketOperator => RightSquareBracketOperator,
+                LeftCurlyBracketOperator => RightCurlyBracketOperator,
+                RightParenthesisOperator | RightSquareBracketOperator | RightCurlyBracketOperator => {
+                    return Err(SyntaxErrorCollector::from_error(SyntaxErrorType::TokenExpected(close), token.context));
+                },
+                _ => {
+                    tokens.push(MacroToken::Token(text));
+                    continue;
+                },
+            };
+
+            let closing_text = Token::new(closing.clone(), token.context).as_string();
+            tokens.push(MacroToken::Token(text));
+            tokens.extend(self.parse_macro_tokens(closing, matcher)?);
+            tokens.push(MacroToken::Token(closing_text));
+        }
+    }
+
+    /// Parses what follows a `$`, which is `name:kind` in a matcher, `name`
+    /// in a transcriber or a repetition in either
+    fn parse_macro_variable(&mut self, matcher: bool) -> ParseResult<MacroToken> {
+        let token = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::IdentifierExpected)?;
+        match token.content {
+            Identifier(name) if matcher => {
+                self.expect(ColonOperator, ProgramContext::NormalContext)?;
+                let kind = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::IdentifierExpected)?;
+                match FragmentKind::from_name(&kind.as_string()) {
+                    Some(fragment) => Ok(MacroToken::Fragment(name, fragment)),
+                    None => Err(SyntaxErrorCollector::from_error(SyntaxErrorType::InvalidFragmentKind(kind.as_string()), kind.context)),
+                }
+            },
+            Identifier(name) => Ok(MacroToken::Variable(name)),
+            LeftParenthesisOperator => {
+                let tokens = self.parse_macro_tokens(RightParenthesisOperator, matcher)?;
+                let mut separator = None;
+                loop {
+                    let token = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::RepetitionExpected)?;
+                    let kind = match token.content {
+                        StarOperator => RepetitionKind::ZeroOrMore,
+                        PlusOperator => RepetitionKind::OneOrMore,
+                        QuestionOperator => RepetitionKind::ZeroOrOne,
+                        _ if separator.is_none() => {
+                            separator = Some(self.source(token.context.index, self.context.index));
+                            continue;
+                        },
+                        _ => return Err(SyntaxErrorCollector::from_error(SyntaxErrorType::RepetitionExpected, token.context)),
+                    };
+                    return Ok(MacroToken::Repetition { tokens, separator, kind });
+                }
+            },
+            _ => Err(SyntaxErrorCollector::from_error(SyntaxErrorType::IdentifierExpected, token.context)),
+        }
+    }
+
+    fn peek_macro_call(&mut self) -> bool {
+        let context = self.context.clone();
+        let _ = self.next(ProgramContext::NormalContext);
+        let call = self.peek_is(&BangOperator, ProgramContext::NormalContext);
+        self.context = context;
+        call
+    }
+
+    /// Parses the `!(..)` after the name of a macro and returns the code the
+    /// invocation expands to
+    fn parse_macro_call(&mut self, name: String, context: &TokenContext) -> ParseResult<String> {
+        self.expect(BangOperator, ProgramContext::NormalContext)?;
+        let error = |error_type| Err(SyntaxErrorCollector::from_error(error_type, context.clone()));
+        let Some(macro_) = self.macros.get(&name).cloned() else {
+            return error(SyntaxErrorType::UndefinedMacro(name));
+        };
+        if context.backtrace().len() >= MAX_MACRO_DEPTH {
+            return error(SyntaxErrorType::MacroRecursionLimit(name));
+        }
+
+        let close = match self.next_token(ProgramContext::NormalContext, SyntaxErrorType::TokenExpected(LeftParenthesisOperator))? {
+            Token { content: LeftParenthesisOperator, context: _ } => RightParenthesisOperator,
+            Token { content: LeftSquareBracketOperator, context: _ } => RightSquareBracketOperator,
+            Token { content: LeftCurlyBracketOperator, context: _ } => RightCurlyBracketOperator,
+            Token { content: _, context } => {
+                return Err(SyntaxErrorCollector::from_error(SyntaxErrorType::TokenExpected(LeftParenthesisOperator), context));
+            },
+        };
+        let start = self.context.index;
+        self.parse_macro_tokens(close, false)?;
+        let arguments = self.source(start, self.context.index - 1);
+
+        for rule in &macro_.rules {
+            let mut lexer = Lexer::new(context.filename.clone(), arguments.clone());
+            lexer.macros = self.macros.clone();
+            lexer.expansions = self.expansions;
+            lexer.renamed_names = context.expansion.is_some();
+
+            let mut captures = HashMap::new();
+            if lexer.match_macro(&rule.matcher, &mut captures) && lexer.at_end() {
+                let code = match rule.transcribe(&captures, lexer.expansions) {
+                    Ok(code) => code,
+                    Err(error_type) => return error(error_type),
+                };
+                self.expansions = lexer.expansions + 1;
+                return Ok(code);
+            }
+        }
+        error(SyntaxErrorType::NoMatchingMacroRule(name))
+    }
+
+    /// Matches the arguments of an invocation against a matcher, recording
+    /// the code each fragment matched
+    fn match_macro(&mut self, matcher: &[MacroToken], captures: &mut Captures) -> bool {
+        for token in matcher {
+            match token {
+                MacroToken::Token(text) => {
+                    let start = self.context.clone();
+                    match self.next(ProgramContext::NormalContext) {
+                        Ok(Some(token)) if self.source(token.context.index, self.context.index) == *text => {},
+                        _ => {
+                            self.context = start;
+                            return false;
+                        },
+                    }
+                },
+                MacroToken::Fragment(name, kind) => match self.match_fragment(*kind) {
+                    Some(code) => {
+                        captures.insert(name.clone(), Capture::Fragment(code));
+                    },
+                    None => return false,
+                },
+                MacroToken::Repetition { tokens, separator, kind } => {
+                    let mut matches = Vec::new();
+                    loop {
+                        let start = self.context.clone();
+                        let separated = match separator {
+                            Some(separator) if !matches.is_empty() => {
+                                matches!(self.next(ProgramContext::NormalContext), Ok(Some(token))
+                                    if self.source(token.context.index, self.context.index) == *separator)
+                            },
+                            _ => true,
+                        };
+
+                        let mut inner = HashMap::new();
+                        if !separated || self.at_end() || !self.match_macro(tokens, &mut inner) {
+                            self.context = start;
+                            break;
+                        }
+                        matches.push(inner);
+                        if *kind == RepetitionKind::ZeroOrOne {
+                            break;
+                        }
+                    }
+
+                    if *kind == RepetitionKind::OneOrMore && matches.is_empty() {
+                        return false;
+                    }
+                    for name in MacroToken::fragments(tokens) {
+                        captures.insert(name, Capture::Repetition(matches.clone()));
+                    }
+                },
+                MacroToken::Variable(_) => return false,
+            }
+        }
+        true
+    }
+
+    fn match_fragment(&mut self, kind: FragmentKind) -> Option<String> {
+        let start = self.context.clone();
+        self.fake_renamed_name = false;
+        let matched = match kind {
+            FragmentKind::Expression => self.parse_expression().is_ok(),
+            FragmentKind::Type => self.parse_type().is_ok(),
+            FragmentKind::Identifier => matches!(self.next(ProgramContext::NormalContext), Ok(Some(Token { content: Identifier(_), .. }))),
+            FragmentKind::Literal => {
+                matches!(self.next(ProgramContext::NormalContext), Ok(Some(token)) if Self::literal(token.content.clone(), &token.context).is_some())
+            },
+            FragmentKind::TokenTree => match self.next(ProgramContext::NormalContext) {
+                Ok(Some(Token { content: LeftParenthesisOperator, .. })) => self.parse_macro_tokens(RightParenthesisOperator, false).is_ok(),
+                Ok(Some(Token { content: LeftSquareBracketOperator, .. })) => self.parse_macro_tokens(RightSquareBracketOperator, false).is_ok(),
+                Ok(Some(Token { content: LeftCurlyBracketOperator, .. })) => self.parse_macro_tokens(RightCurlyBracketOperator, false).is_ok(),
+                Ok(Some(Token { content: RightParenthesisOperator | RightSquareBracketOperator | RightCurlyBracketOperator, .. })) => false,
+                result => matches!(result, Ok(Some(_))),
+            },
+        };
+
+        // The code is lexed again inside the expansion, where `name#1` would
+        // become a variable the macro renamed
+        if !matched || self.fake_renamed_name {
+            self.context = start;
+            return None;
+        }
+        let code = self.source(start.index, self.context.index).trim().to_string();
+        match kind {
+            // Expressions stay a single operand wherever they are used
+            FragmentKind::Expression => Some(format!("({})", code)),
+            _ => Some(code),
+        }
+    }
+
+    // Types
+
+    pub fn parse_type(&mut self) -> ParseResult<Type> {
+        let token = self.next_token(ProgramContext::TypeContext, SyntaxErrorType::TypeExpected)?;
+
+        match token.content {
+            I8Keyword    => Ok(Type::Int8),
+            I16Keyword   => Ok(Type::Int16),
+            I32Keyword   => Ok(Type::Int32),
+            I64Keyword   => Ok(Type::Int64),
+            I128Keyword  => Ok(Type::Int128),
+            ISizeKeyword => Ok(Type::IntSize),
+            U8Keyword    => Ok(Type::UInt8),
+            U16Keyword   => Ok(Type::UInt16),
+            U32Keyword   => Ok(Type::UInt32),
+            U64Keyword   => Ok(Type::UInt64),
+            U128Keyword  => Ok(Type::UInt128),
+            USizeKeyword => Ok(Type::UIntSize),
+            F32Keyword   => Ok(Type::Float32),
+            F64Keyword   => Ok(Type::Float64),
+            BoolKeyword  => Ok(Type::Boolean),
+            CharKeyword  => Ok(Type::Char),
+            LeftParenthesisOperator => self.parse_tuple_type(),
+            LeftSquareBracketOperator => {
+                let type_ = self.parse_type()?;
+                self.expect(SemicolonOperator, ProgramContext::TypeContext)?;
+                let token = self.next_token(ProgramContext::TypeContext, SyntaxErrorType::ArrayLengthExpected)?;
+                let length = match token.content {
+                    IntToken(length) if usize::try_from(length).is_ok() => length as usize,
+                    _ => return Err(SyntaxErrorCollector::from_error(SyntaxErrorType::ArrayLengthExpected, token.context)),
+                };
+                self.expect(RightSquareBracketOperator, ProgramContext::TypeContext)?;
+                Ok(Type::Array { type_: Box::new(type_), length })
+            },
+            FnKeyword => {
+                self.expect(LeftParenthesisOperator, ProgramContext::TypeContext)?;
+                let parameters = self.parse_type_list(RightParenthesisOperator)?;
+                let return_type = if self.eat(&ColonOperator, ProgramContext::TypeContext) {
+                    self.parse_type()?
+                } else {
+                    Type::unit()
+                };
+                Ok(Type::Function { parameters, return_type: Box::new(return_type) })
+            },
+            Identifier(name) => {
+                let name = self.parse_type_path(name)?;
+                if self.eat(&LeftChevronOperator, ProgramContext::TypeContext) {
+                    let types = self.parse_type_list(RightChevronOperator)?;
+                    Ok(Type::GenericType { name, types })
+                } else {
+                    Ok(Type::Type { name })
+                }
+            },
+            _ => Err(SyntaxErrorCollector::from_error(SyntaxErrorType::TypeExpected, token.context)),
+        }
+    }
+
+    /// Parses the rest of a type name such as `shapes::Point`, which types
+    /// keep as a single name
+    fn parse_type_path(&mut self, first: String) -> ParseResult<String> {
+        let mut name = first;
+        while self.eat(&ScopeResolutionOperator, ProgramContext::TypeContext) {
+            let (segment, _) = self.expect_identifier(ProgramContext::TypeContext)?;
+            name = format!("{}::{}", name, segment);
+        }
+        Ok(name)
+    }
+
+    fn parse_tuple_type(&mut self) -> ParseResult<Type> {
+        // `()` is the unit type, `(T)` is just `T` and `(T,)` is a 1-tuple
+        let mut types = Vec::new();
+        loop {
+            if self.eat(&RightParenthesisOperator, ProgramContext::TypeContext) {
+                return Ok(Type::Tuple { types });
+            }
+            types.push(self.parse_type()?);
+
+            if !self.eat(&CommaOperator, ProgramContext::TypeContext) {
+                self.expect(RightParenthesisOperator, ProgramContext::TypeContext)?;
+                return Ok(match types.len() {
+                    1 => types.remove(0),
+                    _ => Type::Tuple { types },
+                });
+            }
+        }
+    }
+
+    fn parse_type_list(&mut self, closing: TokenContent) -> ParseResult<Vec<Type>> {
+        let mut types = Vec::new();
+        while !self.eat(&closing, ProgramContext::TypeContext) {
+            types.push(self.parse_type()?);
+
+            if !self.eat(&CommaOperator, ProgramContext::TypeContext) {
+                self.expect(closing, ProgramContext::TypeContext)?;
+                break;
+            }
+        }
+        Ok(types)
+    }
+
+    // Patterns
+
+    fn parse_pattern(&mut self) -> ParseResult<Pattern> {
+        let context = self.peek_context();
+        let mut patterns = vec![self.parse_single_pattern()?];
+        while self.eat(&PipeOperator, ProgramContext::NormalContext) {
+            patterns.push(self.parse_single_pattern()?);
+        }
+
+        match patterns.len() {
+            1 => Ok(patterns.remove(0)),
+            _ => Ok(Pattern::OrPattern { patterns, context }),
+        }
+    }
+
+    fn parse_single_pattern(&mut self) -> ParseResult<Pattern> {
+        let token = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::PatternExpected)?;
+
+        match token.content {
+            Identifier(name) if name == "_" => Ok(Pattern::BlankPattern),
+            SuperKeyword | SelfKeyword => self.parse_enum_pattern(Self::path_segment(token.content), token.context),
+            Identifier(name) if self.peek_is(&ScopeResolutionOperator, ProgramContext::NormalContext) => {
+                self.parse_enum_pattern(PathSegment::PathIdentifier { id: name }, token.context)
+            },
+            Identifier(name) if self.eat(&AtOperator, ProgramContext::NormalContext) => {
+                let pattern = Box::new(self.parse_single_pattern()?);
+                Ok(Pattern::BindingPattern { name, pattern, context: token.context })
+            },
+            Identifier(name) => Ok(Pattern::IdentifierPattern { name, context: token.context }),
+            LeftParenthesisOperator => self.parse_tuple_pattern(),
+            IntToken(value) => {
+                let literal = Expression::IntLiteral { value, context: token.context.clone() };
+                self.parse_range_pattern(literal, token.context)
+            },
+            FloatToken(value) => {
+                Ok(Pattern::LiteralPattern { literal: Box::new(Expression::FloatLiteral { value, context: token.context }) })
+            },
+            StringToken(value) => Ok(Pattern::LiteralPattern { literal: Box::new(Expression::StringLiteral { value, context: token.context }) }),
+            CharToken(value) => self.parse_range_pattern(Expression::CharLiteral { value, context: token.context.clone() }, token.context),
+            TrueKeyword => Ok(Pattern::LiteralPattern { literal: Box::new(Expression::BooleanLiteral { value: true, context: token.context }) }),
+            FalseKeyword => Ok(Pattern::LiteralPattern { literal: Box::new(Expression::BooleanLiteral { value: false, context: token.context }) }),
+            MinusOperator => match self.next_token(ProgramContext::NormalContext, SyntaxErrorType::PatternExpected)? {
+                Token { content: IntToken(value), context } => {
+                    self.parse_range_pattern(Expression::IntLiteral { value: -value, context }, token.context)
+                },
+                Token { content: FloatToken(value), context } => {
+                    Ok(Pattern::LiteralPattern { literal: Box::new(Expression::FloatLiteral { value: -value, context }) })
+                },
+                Token { content: _, context } => {
+                    Err(SyntaxErrorCollector::from_error(SyntaxErrorType::PatternExpected, context))
+                },
+            },
+            _ => Err(SyntaxErrorCollector::from_error(SyntaxErrorType::PatternExpected, token.context)),
+        }
+    }
+
+    /// Parses the rest of a range pattern such as `1..=5` if `start` is
+    /// followed by `..` or `..=`, or else gives the literal pattern `start`
+    fn parse_range_pattern(&mut self, start: Expression, context: TokenContext) -> ParseResult<Pattern> {
+        let inclusive = match self.peek_content(ProgramContext::NormalContext) {
+            Some(DoubleDotEqualOperator) => true,
+            Some(DoubleDotOperator) => false,
+            _ => return Ok(Pattern::LiteralPattern { literal: Box::new(start) }),
+        };
+        self.next_token(ProgramContext::NormalContext, SyntaxErrorType::PatternExpected)?;
+
+        let token = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::PatternExpected)?;
+        let end = match (&start, token.content) {
+            (Expression::IntLiteral { .. }, IntToken(value)) => Expression::IntLiteral { value, context: token.context },
+            (Expression::IntLiteral { .. }, MinusOperator) => match self.next_token(ProgramContext::NormalContext, SyntaxErrorType::PatternExpected)? {
+                Token { content: IntToken(value), context } => Expression::IntLiteral { value: -value, context },
+                Token { content: _, context } => return Err(SyntaxErrorCollector::from_error(SyntaxErrorType::PatternExpected, context)),
+            },
+            (Expression::CharLiteral { .. }, CharToken(value)) => Expression::CharLiteral { value, context: token.context },
+            _ => return Err(SyntaxErrorCollector::from_error(SyntaxErrorType::PatternExpected, token.context)),
+        };
+        Ok(Pattern::RangePattern { start: Box::new(start), end: Box::new(end), inclusive, context })
+    }
+
+    fn parse_enum_pattern(&mut self, first: PathSegment, context: TokenContext) -> ParseResult<Pattern> {
+        let path = self.parse_path(first)?;
+        let patterns = if self.eat(&LeftParenthesisOperator, ProgramContext::NormalContext) {
+            self.parse_pattern_list()?
+        } else {
+            Vec::new()
+        };
+        Ok(Pattern::EnumPattern { path, patterns, context })
+    }
+
+    fn parse_pattern_list(&mut self) -> ParseResult<Vec<Pattern>> {
+        let mut patterns = Vec::new();
+        while !self.eat(&RightParenthesisOperator, ProgramContext::NormalContext) {
+            patterns.push(self.parse_pattern()?);
+
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) {
+                self.expect(RightParenthesisOperator, ProgramContext::NormalContext)?;
+                break;
+            }
+        }
+        Ok(patterns)
+    }
+
+    /// Parses the rest of a path such as `Shape::Circle` after its first
+    /// segment
+    fn parse_path(&mut self, first: PathSegment) -> ParseResult<Vec<PathSegment>> {
+        let mut path = vec![first];
+        while self.eat(&ScopeResolutionOperator, ProgramContext::NormalContext) {
+            path.push(self.parse_path_segment()?);
+        }
+        Ok(path)
+    }
+
+    fn parse_path_segment(&mut self) -> ParseResult<PathSegment> {
+        let token = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::IdentifierExpected)?;
+        match token.content {
+            Identifier(_) | SuperKeyword | SelfKeyword => Ok(Self::path_segment(token.content)),
+            _ => Err(SyntaxErrorCollector::from_error(SyntaxErrorType::IdentifierExpected, token.context)),
+        }
+    }
+
+    fn path_segment(content: TokenContent) -> PathSegment {
+        match content {
+            SuperKeyword => PathSegment::SuperPath,
+            SelfKeyword => PathSegment::SelfPath,
+            Identifier(id) => PathSegment::PathIdentifier { id },
+            _ => unreachable!("only names, `super` and `self` start path segments"),
+        }
+    }
+
+    fn parse_tuple_pattern(&mut self) -> ParseResult<Pattern> {
+        let mut patterns = Vec::new();
+        loop {
+            if self.eat(&RightParenthesisOperator, ProgramContext::NormalContext) {
+                return Ok(Pattern::TuplePattern { patterns });
+            }
+            patterns.push(self.parse_pattern()?);
+
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) {
+                self.expect(RightParenthesisOperator, ProgramContext::NormalContext)?;
+                return Ok(match patterns.len() {
+                    1 => patterns.remove(0),
+                    _ => Pattern::TuplePattern { patterns },
+                });
+            }
+        }
+    }
+
+    // Statements
+
+    fn parse_block(&mut self) -> ParseResult<Block> {
+        self.with_struct_literals(true, Self::parse_block_contents)
+    }
+
+    fn parse_block_contents(&mut self) -> ParseResult<Block> {
+        self.expect(LeftCurlyBracketOperator, ProgramContext::NormalContext)?;
+        let mut statements = Vec::new();
+
+        loop {
+            if self.eat(&RightCurlyBracketOperator, ProgramContext::NormalContext) {
+                let expression = Box::new(Expression::TupleExpression { elements: Vec::new() });
+                return Ok(Block { statements, expression });
+            }
+            if self.eat(&SemicolonOperator, ProgramContext::NormalContext) {
+                continue;
+            }
+
+            match self.peek_content(ProgramContext::NormalContext) {
+                Some(LetKeyword) | Some(ConstKeyword) => {
+                    statements.push(self.parse_let()?);
+                    self.expect(SemicolonOperator, ProgramContext::NormalContext)?;
+                },
+                Some(DeferKeyword) => {
+                    let context = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::AtomExpected)?.context;
+                    statements.push(Statement::DeferStatement { body: self.parse_block()?, context });
+                },
+                // A macro may expand to statements here, so its expansion is
+                // only parenthesized when it is the operand of an expression
+                Some(Identifier(name)) if self.peek_macro_call() => {
+                    let context = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::AtomExpected)?.context;
+                    let code = self.parse_macro_call(name.clone(), &context)?;
+                    let statements = self.peek_is(&SemicolonOperator, ProgramContext::NormalContext)
+                        || self.peek_is(&RightCurlyBracketOperator, ProgramContext::NormalContext);
+                    match statements {
+                        true => self.splice(name, context, &code),
+                        false => self.splice(name, context, &format!("({})", code)),
+                    }
+                },
+                _ => {
+                    let expression = self.parse_expression()?;
+
+                    if self.eat(&SemicolonOperator, ProgramContext::NormalContext) || expression.is_block_like()
+                        && !self.peek_is(&RightCurlyBracketOperator, ProgramContext::NormalContext) {
+                        statements.push(Statement::ExpressionStatement { expression });
+                    } else {
+                        if !self.peek_is(&RightCurlyBracketOperator, ProgramContext::NormalContext) {
+                            if let Some(errors) = Self::misspelled_statement(&expression) {
+                                return Err(errors);
+                            }
+                        }
+                        self.expect(RightCurlyBracketOperator, ProgramContext::NormalContext)?;
+                        return Ok(Block { statements, expression: Box::new(expression) });
+                    }
+                },
+            }
+        }
+    }
+
+    /// An error for a statement that is a single word followed by more code,
+    /// as in `whlie x < 3 { .. }`, when the word is a misspelled keyword
+    fn misspelled_statement(expression: &Expression) -> Option<SyntaxErrorCollector> {
+        let Expression::Variable { name, context } = expression else {
+            return None;
+        };
+        suggestions::closest(name, STATEMENT_KEYWORDS.iter().copied())?;
+        let mut errors = SyntaxErrorCollector::from_error(SyntaxErrorType::StatementExpected, context.clone());
+        errors.suggest_name(name, STATEMENT_KEYWORDS.iter().copied());
+        Some(errors)
+    }
+
+    fn parse_let(&mut self) -> ParseResult<Statement> {
+        let token = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::TokenExpected(LetKeyword))?;
+        let pattern = self.parse_pattern()?;
+        let (type_, type_context) = if self.eat(&ColonOperator, ProgramContext::NormalContext) {
+            let type_context = self.peek_context();
+            (Some(self.parse_type()?), Some(type_context))
+        } else {
+            (None, None)
+        };
+        self.expect(EqualOperator, ProgramContext::NormalContext)?;
+        let expression = self.parse_expression()?;
+        let context = token.context;
+
+        match token.content {
+            ConstKeyword => Ok(Statement::ConstStatement { pattern, type_, type_context, expression, context }),
+            _ => Ok(Statement::LetStatement { pattern, type_, type_context, expression, context }),
+        }
+    }
+
+    // Expressions
+
+    pub fn parse_expression(&mut self) -> ParseResult<Expression> {
+        match self.peek_content(ProgramContext::NormalContext) {
+            Some(LeftCurlyBracketOperator) => Ok(Expression::BlockExpression { body: self.parse_block()? }),
+            Some(IfKeyword) => self.parse_if(),
+            Some(WhileKeyword) | Some(LoopKeyword) | Some(ForKeyword) => self.parse_loop(None),
+            Some(LabelToken(label)) => {
+                self.next_token(ProgramContext::NormalContext, SyntaxErrorType::AtomExpected)?;
+                self.expect(ColonOperator, ProgramContext::NormalContext)?;
+                self.parse_loop(Some(label))
+            },
+            Some(MatchKeyword) => self.parse_match(),
+            Some(PipeOperator) => self.parse_closure(),
+            Some(ReturnKeyword) => {
+                self.next_token(ProgramContext::NormalContext, SyntaxErrorType::AtomExpected)?;
+                Ok(Expression::ReturnExpression { returned: self.parse_optional_expression()? })
+            },
+            Some(BreakKeyword) => {
+                let context = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::AtomExpected)?.context;
+                let label = self.parse_label();
+                Ok(Expression::BreakExpression { label, returned: self.parse_optional_expression()?, context })
+            },
+            Some(ContinueKeyword) => {
+                let context = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::AtomExpected)?.context;
+                Ok(Expression::ContinueExpression { label: self.parse_label(), context })
+            },
+            _ => self.parse_assign(),
+        }
+    }
+
+    /// Parses a `while`, `loop` or `for` loop, which may be given a label
+    fn parse_loop(&mut self, label: Option<String>) -> ParseResult<Expression> {
+        let token = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::LoopExpected)?;
+        let context = token.context;
+        match token.content {
+            WhileKeyword => {
+                let condition = Box::new(self.parse_condition()?);
+                Ok(Expression::WhileExpression { label, condition, body: self.parse_block()?, context })
+            },
+            LoopKeyword => Ok(Expression::LoopExpression { label, body: self.parse_block()? }),
+            ForKeyword => {
+                let pattern = self.parse_pattern()?;
+                self.expect(InKeyword, ProgramContext::NormalContext)?;
+                let iterator = Box::new(self.parse_condition()?);
+                Ok(Expression::ForExpression { label, pattern, iterator, body: self.parse_block()?, context })
+            },
+            _ => Err(SyntaxErrorCollector::from_error(SyntaxErrorType::LoopExpected, context)),
+        }
+    }
+
+    /// Parses the label a `break` or `continue` refers to, if it has one
+    fn parse_label(&mut self) -> Option<String> {
+        match self.peek_content(ProgramContext::NormalContext) {
+            Some(LabelToken(label)) => {
+                let _ = self.next(ProgramContext::NormalContext);
+                Some(label)
+            },
+            _ => None,
+        }
+    }
+
+    /// Runs `parse` with struct literals allowed or not, restoring the previous
+    /// setting afterwards
+    fn with_struct_literals<T>(&mut self, allowed: bool, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        let struct_literals = std::mem::replace(&mut self.struct_literals, allowed);
+        let result = parse(self);
+        self.struct_literals = struct_literals;
+        result
+    }
+
+    /// Parses the expression before the body of an `if`, `while` or `match`,
+    /// where `x { .. }` is `x` followed by the body rather than a struct
+    fn parse_condition(&mut self) -> ParseResult<Expression> {
+        self.with_struct_literals(false, Self::parse_expression)
+    }
+
+    fn parse_assign(&mut self) -> ParseResult<Expression> {
+        let target = self.parse_or()?;
+
+        let operators = [
+            (EqualOperator, None),
+            (PlusEqualOperator, Some(Operator::AddOperator)),
+            (MinusEqualOperator, Some(Operator::SubtractOperator)),
+            (StarEqualOperator, Some(Operator::MultiplyOperator)),
+            (SlashEqualOperator, Some(Operator::DivideOperator)),
+            (PercentEqualOperator, Some(Operator::ModuloOperator)),
+            (DoubleStarEqualOperator, Some(Operator::ExponentOperator)),
+            (AmpersandEqualOperator, Some(Operator::BitwiseAndOperator)),
+            (PipeEqualOperator, Some(Operator::BitwiseOrOperator)),
+            (CaretEqualOperator, Some(Operator::BitwiseXorOperator)),
+            (DoubleLeftChevronEqualOperator, Some(Operator::LeftShiftOperator)),
+            (DoubleRightChevronEqualOperator, Some(Operator::RightShiftOperator)),
+        ];
+
+        for (content, op) in operators {
+            if self.peek_is(&content, ProgramContext::NormalContext) {
+                let context = self.expect(content, ProgramContext::NormalContext)?.context;
+                if !matches!(target,
+                    Expression::Variable { .. } | Expression::FieldExpression { .. } | Expression::IndexExpression { .. }) {
+                    return Err(SyntaxErrorCollector::from_error(SyntaxErrorType::InvalidAssignmentTarget, context));
+                }
+                let value = Box::new(self.parse_expression()?);
+                return Ok(Expression::AssignExpression { target: Box::new(target), op, value, context });
+            }
+        }
+        Ok(target)
+    }
+
+    fn parse_optional_expression(&mut self) -> ParseResult<Option<Box<Expression>>> {
+        match self.peek_content(ProgramContext::NormalContext) {
+            None
+            | Some(SemicolonOperator)
+            | Some(CommaOperator)
+            | Some(RightCurlyBracketOperator)
+            | Some(RightParenthesisOperator) => Ok(None),
+            _ => Ok(Some(Box::new(self.parse_expression()?))),
+        }
+    }
+
+    fn parse_closure(&mut self) -> ParseResult<Expression> {
+        let context = self.expect(PipeOperator, ProgramContext::NormalContext)?.context;
+        let mut parameters = Vec::new();
+        let mut contexts = Vec::new();
+        let mut types = Vec::new();
+        while !self.eat(&PipeOperator, ProgramContext::NormalContext) {
+            let (parameter, parameter_context) = self.expect_identifier(ProgramContext::NormalContext)?;
+            parameters.push(parameter);
+            contexts.push(parameter_context);
+
+            // Parameters without a type have theirs inferred from how the
+            // closure is used
+            if self.eat(&ColonOperator, ProgramContext::NormalContext) {
+                types.push(self.parse_type()?);
+            } else {
+                types.push(Type::Inferred);
+            }
+
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) {
+                self.expect(PipeOperator, ProgramContext::NormalContext)?;
+                break;
+            }
+        }
+
+        let return_type = if self.eat(&ColonOperator, ProgramContext::NormalContext) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+
+        let body = Box::new(self.parse_expression()?);
+        Ok(Expression::ClosureExpression { parameters, contexts, types, return_type, body, context })
+    }
+
+    fn parse_if(&mut self) -> ParseResult<Expression> {
+        let context = self.expect(IfKeyword, ProgramContext::NormalContext)?.context;
+        let condition = Box::new(self.parse_condition()?);
+        let body = self.parse_block()?;
+
+        let alternate = if !self.eat(&ElseKeyword, ProgramContext::NormalContext) {
+            Block { statements: Vec::new(), expression: Box::new(Expression::TupleExpression { elements: Vec::new() }) }
+        } else if self.peek_is(&IfKeyword, ProgramContext::NormalContext) {
+            Block { statements: Vec::new(), expression: Box::new(self.parse_if()?) }
+        } else {
+            self.parse_block()?
+        };
+
+        Ok(Expression::IfExpression { condition, body, alternate, context })
+    }
+
+    fn parse_match(&mut self) -> ParseResult<Expression> {
+        let context = self.expect(MatchKeyword, ProgramContext::NormalContext)?.context;
+        let discriminant = Box::new(self.parse_condition()?);
+        self.expect(LeftCurlyBracketOperator, ProgramContext::NormalContext)?;
+
+        let mut branches = Vec::new();
+        while !self.eat(&RightCurlyBracketOperator, ProgramContext::NormalContext) {
+            let context = self.peek_context();
+            let pattern = self.parse_pattern()?;
+            let guard = match self.eat(&IfKeyword, ProgramContext::NormalContext) {
+                true => Some(self.parse_expression()?),
+                false => None,
+            };
+            self.expect(DoubleArrowOperator, ProgramContext::NormalContext)?;
+            let consequent = self.parse_expression()?;
+            let block_like = consequent.is_block_like();
+            branches.push(MatchBranch { pattern, guard, consequent, context });
+
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) && !block_like {
+                self.expect(RightCurlyBracketOperator, ProgramContext::NormalContext)?;
+                break;
+            }
+        }
+
+        Ok(Expression::MatchExpression { discriminant, branches, context })
+    }
+
+    fn parse_binary(
+        &mut self,
+        operators: &[(TokenContent, Operator)],
+        operand: fn(&mut Self) -> ParseResult<Expression>,
+    ) -> ParseResult<Expression> {
+        let mut left = operand(self)?;
+
+        'outer: loop {
+            for (content, op) in operators {
+                if self.peek_is(content, ProgramContext::NormalContext) {
+                    let context = self.expect(content.clone(), ProgramContext::NormalContext)?.context;
+                    let right = operand(self)?;
+                    left = Expression::BinaryOp { op: *op, left: Box::new(left), right: Box::new(right), context };
+                    continue 'outer;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn parse_or(&mut self) -> ParseResult<Expression> {
+        self.parse_binary(&[(OrKeyword, Operator::OrOperator)], Self::parse_and)
+    }
 
-    //     loop {
-    //         match self.parse_expression() {
-    //             Ok(_x) => {},
-    //             Err(_x) => break,
-    //         }
-    //     }
+    fn parse_and(&mut self) -> ParseResult<Expression> {
+        self.parse_binary(&[(AndKeyword, Operator::AndOperator)], Self::parse_compare)
+    }
+
+    fn parse_compare(&mut self) -> ParseResult<Expression> {
+        self.parse_binary(&[
+            (DoubleEqualOperator, Operator::EqualOperator),
+            (BangEqualOperator, Operator::NotEqualOperator),
+            (RightChevronEqualOperator, Operator::GreaterEqualOperator),
+            (LeftChevronEqualOperator, Operator::LessEqualOperator),
+            (RightChevronOperator, Operator::GreaterThanOperator),
+            (LeftChevronOperator, Operator::LessThanOperator),
+        ], Self::parse_bitwise_or)
+    }
+
+    fn parse_bitwise_or(&mut self) -> ParseResult<Expression> {
+        self.parse_binary(&[(PipeOperator, Operator::BitwiseOrOperator)], Self::parse_bitwise_xor)
+    }
+
+    fn parse_bitwise_xor(&mut self) -> ParseResult<Expression> {
+        self.parse_binary(&[(CaretOperator, Operator::BitwiseXorOperator)], Self::parse_bitwise_and)
+    }
+
+    fn parse_bitwise_and(&mut self) -> ParseResult<Expression> {
+        self.parse_binary(&[(AmpersandOperator, Operator::BitwiseAndOperator)], Self::parse_bitshift)
+    }
+
+    fn parse_bitshift(&mut self) -> ParseResult<Expression> {
+        self.parse_binary(&[
+            (DoubleLeftChevronOperator, Operator::LeftShiftOperator),
+            (DoubleRightChevronOperator, Operator::RightShiftOperator),
+        ], Self::parse_term)
+    }
+
+    fn parse_term(&mut self) -> ParseResult<Expression> {
+        self.parse_binary(&[
+            (PlusOperator, Operator::AddOperator),
+            (MinusOperator, Operator::SubtractOperator),
+        ], Self::parse_factor)
+    }
+
+    fn parse_factor(&mut self) -> ParseResult<Expression> {
+        self.parse_binary(&[
+            (StarOperator, Operator::MultiplyOperator),
+            (SlashOperator, Operator::DivideOperator),
+            (PercentOperator, Operator::ModuloOperator),
+        ], Self::parse_exponent)
+    }
+
+    fn parse_exponent(&mut self) -> ParseResult<Expression> {
+        let left = self.parse_prefix()?;
+
+        // Exponentiation is right-associative
+        if self.peek_is(&DoubleStarOperator, ProgramContext::NormalContext) {
+            let context = self.expect(DoubleStarOperator, ProgramContext::NormalContext)?.context;
+            let right = self.parse_exponent()?;
+            return Ok(Expression::BinaryOp {
+                op: Operator::ExponentOperator,
+                left: Box::new(left),
+                right: Box::new(right),
+                context,
+            });
+        }
+
+        Ok(left)
+    }
+
+    fn parse_prefix(&mut self) -> ParseResult<Expression> {
+        let op = match self.peek_content(ProgramContext::NormalContext) {
+            Some(NotKeyword) => Operator::NotOperator,
+            Some(TildeOperator) => Operator::BitwiseNotOperator,
+            Some(MinusOperator) => Operator::NegateOperator,
+            _ => return self.parse_postfix(),
+        };
+
+        let context = self.next_token(ProgramContext::NormalContext, SyntaxErrorType::AtomExpected)?.context;
+        let child = Box::new(self.parse_prefix()?);
+        Ok(Expression::UnaryOp { op, child, context })
+    }
+
+    fn parse_postfix(&mut self) -> ParseResult<Expression> {
+        let context = self.peek_context();
+        let mut expression = self.parse_atom()?;
+
+        loop {
+            if self.eat(&LeftParenthesisOperator, ProgramContext::NormalContext) {
+                let arguments = self.parse_arguments()?;
+                expression = Expression::CallExpression {
+                    callee: Box::new(expression),
+                    arguments,
+                    context: context.clone(),
+                };
+            } else if self.peek_is(&DotOperator, ProgramContext::NormalContext) {
+                self.expect(DotOperator, ProgramContext::NormalContext)?;
+
+                // Field names are lexed in the field context so that tuple
+                // indices like `.0.1` are not read as a float
+                let token = self.next_token(ProgramContext::FieldContext, SyntaxErrorType::IdentifierExpected)?;
+                let right = match token.content {
+                    Identifier(name) => name,
+                    IntToken(index) => index.to_string(),
+                    _ => return Err(SyntaxErrorCollector::from_error(SyntaxErrorType::IdentifierExpected, token.context)),
+                };
+                expression = Expression::FieldExpression { left: Box::new(expression), right, context: token.context };
+            } else if self.peek_is(&LeftSquareBracketOperator, ProgramContext::NormalContext) {
+                let context = self.peek_context();
+                self.expect(LeftSquareBracketOperator, ProgramContext::NormalContext)?;
+                let argument = self.parse_nested_expression()?;
+                self.expect(RightSquareBracketOperator, ProgramContext::NormalContext)?;
+                expression = Expression::IndexExpression {
+                    indexed: Box::new(expression),
+                    argument: Box::new(argument),
+                    context,
+                };
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    /// Parses an expression inside of brackets, where struct literals are
+    /// always allowed again
+    fn parse_nested_expression(&mut self) -> ParseResult<Expression> {
+        self.with_struct_literals(true, Self::parse_expression)
+    }
+
+    fn parse_arguments(&mut self) -> ParseResult<Vec<Expression>> {
+        let mut arguments = Vec::new();
+        while !self.eat(&RightParenthesisOperator, ProgramContext::NormalContext) {
+            arguments.push(self.parse_nested_expression()?);
+
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) {
+                self.expect(RightParenthesisOperator, ProgramContext::NormalContext)?;
+                break;
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn parse_parenthesized(&mut self) -> ParseResult<Expression> {
+        // `()` is the unit value, `(x)` is just `x` and `(x,)` is a 1-tuple
+        let mut elements = Vec::new();
+        loop {
+            if self.eat(&RightParenthesisOperator, ProgramContext::NormalContext) {
+                return Ok(Expression::TupleExpression { elements });
+            }
+            elements.push(self.parse_nested_expression()?);
+
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) {
+                self.expect(RightParenthesisOperator, ProgramContext::NormalContext)?;
+                return Ok(match elements.len() {
+                    1 => elements.remove(0),
+                    _ => Expression::TupleExpression { elements },
+                });
+            }
+        }
+    }
+
+    fn parse_array(&mut self, context: TokenContext) -> ParseResult<Expression> {
+        // `[x; n]` repeats `x` n times while `[x, y, z]` lists every element
+        let mut elements = Vec::new();
+        loop {
+            if self.eat(&RightSquareBracketOperator, ProgramContext::NormalContext) {
+                break;
+            }
+            elements.push(self.parse_nested_expression()?);
+
+            if elements.len() == 1 && self.eat(&SemicolonOperator, ProgramContext::NormalContext) {
+                let length = self.parse_nested_expression()?;
+                self.expect(RightSquareBracketOperator, ProgramContext::NormalContext)?;
+                return Ok(Expression::ArrayRepeatExpression {
+                    value: Box::new(elements.remove(0)),
+                    length: Box::new(length),
+                    context,
+                });
+            }
 
-    //     Ok(items)
-    // }
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) {
+                self.expect(RightSquareBracketOperator, ProgramContext::NormalContext)?;
+                break;
+            }
+        }
+        Ok(Expression::ArrayExpression { type_: Type::Inferred, elements, context })
+    }
+
+    fn parse_path_expression(&mut self, first: PathSegment, context: TokenContext) -> ParseResult<Expression> {
+        let segments = self.parse_path(first)?;
+        if self.struct_literals && self.peek_is(&LeftCurlyBracketOperator, ProgramContext::NormalContext) {
+            self.parse_struct_expression(PathSegment::path_as_string(&segments), context)
+        } else {
+            Ok(Expression::PathExpression { segments, context })
+        }
+    }
+
+    fn parse_struct_expression(&mut self, struct_: String, context: TokenContext) -> ParseResult<Expression> {
+        self.expect(LeftCurlyBracketOperator, ProgramContext::NormalContext)?;
+        let mut fields = Vec::new();
+        let mut base = None;
+        while !self.eat(&RightCurlyBracketOperator, ProgramContext::NormalContext) {
+            // `..base` takes every field that wasn't listed from `base`
+            if self.eat(&DoubleDotOperator, ProgramContext::NormalContext) {
+                base = Some(Box::new(self.parse_nested_expression()?));
+                self.eat(&CommaOperator, ProgramContext::NormalContext);
+                self.expect(RightCurlyBracketOperator, ProgramContext::NormalContext)?;
+                break;
+            }
 
-    pub fn parse_expression(&mut self) -> Result<Expression, SyntaxErrorCollector> {
-        self.parse_atom()
+            // `Point { x }` is short for `Point { x: x }`
+            let (name, context) = self.expect_identifier(ProgramContext::NormalContext)?;
+            let expression = if self.eat(&ColonOperator, ProgramContext::NormalContext) {
+                self.parse_nested_expression()?
+            } else {
+                Expression::Variable { name: name.clone(), context }
+            };
+            fields.push(StructExpressionField { name, expression });
+
+            if !self.eat(&CommaOperator, ProgramContext::NormalContext) {
+                self.expect(RightCurlyBracketOperator, ProgramContext::NormalContext)?;
+                break;
+            }
+        }
+        Ok(Expression::StructExpression { struct_, fields, base, context })
     }
 
-    fn parse_atom(&mut self) -> Result<Expression, SyntaxErrorCollector> {
+    fn parse_atom(&mut self) -> ParseResult<Expression> {
         match self.next(ProgramContext::NormalContext) {
-            Ok(Some(Token { content: IntToken(int), context: _ })) => {
-                Ok(Expression::IntLiteral { value: int })
+            Ok(Some(Token { content: IntToken(int), context })) => {
+                Ok(Expression::IntLiteral { value: int, context })
             },
-            Ok(Some(Token { content: FloatToken(float), context: _ })) => {
-                Ok(Expression::FloatLiteral { value: float })
+            Ok(Some(Token { content: FloatToken(float), context })) => {
+                Ok(Expression::FloatLiteral { value: float, context })
             },
-            Ok(Some(Token { content: StringToken(float), context: _ })) => {
-                Ok(Expression::StringLiteral { value: float })
+            Ok(Some(Token { content: StringToken(float), context })) => {
+                Ok(Expression::StringLiteral { value: float, context })
             },
-            Ok(Some(Token { content: CharToken(float), context: _ })) => {
-                Ok(Expression::CharLiteral { value: float })
+            Ok(Some(Token { content: CharToken(float), context })) => {
+                Ok(Expression::CharLiteral { value: float, context })
             },
-            Ok(Some(Token { content: TrueKeyword, context: _ })) => {
-                Ok(Expression::BooleanLiteral { value: true })
+            Ok(Some(Token { content: TrueKeyword, context })) => {
+                Ok(Expression::BooleanLiteral { value: true, context })
             },
-            Ok(Some(Token { content: FalseKeyword, context: _ })) => {
-                Ok(Expression::BooleanLiteral { value: false })
+            Ok(Some(Token { content: FalseKeyword, context })) => {
+                Ok(Expression::BooleanLiteral { value: false, context })
             },
-            Ok(_) => {
+            Ok(Some(Token { content: content @ (SuperKeyword | SelfKeyword), context })) => {
+                self.parse_path_expression(Self::path_segment(content), context)
+            },
+            Ok(Some(Token { content: Identifier(name), context })) => {
+                if self.peek_is(&BangOperator, ProgramContext::NormalContext) {
+                    // The expansion is parenthesized so that it stays a
+                    // single operand whatever operators surround it
+                    let code = self.parse_macro_call(name.clone(), &context)?;
+                    self.splice(name, context, &format!("({})", code));
+                    self.parse_atom()
+                } else if self.peek_is(&ScopeResolutionOperator, ProgramContext::NormalContext) {
+                    self.parse_path_expression(PathSegment::PathIdentifier { id: name }, context)
+                } else if self.struct_literals && self.peek_is(&LeftCurlyBracketOperator, ProgramContext::NormalContext) {
+                    self.parse_struct_expression(name, context)
+                } else {
+                    Ok(Expression::Variable { name, context })
+                }
+            },
+            Ok(Some(Token { content: LeftParenthesisOperator, context: _ })) => {
+                self.parse_parenthesized()
+            },
+            Ok(Some(Token { content: LeftSquareBracketOperator, context })) => {
+                self.parse_array(context)
+            },
+            Ok(Some(Token { content: _, context })) => {
+                Err(SyntaxErrorCollector::from_error(SyntaxErrorType::AtomExpected, context))
+            },
+            Ok(None) => {
                 Err(SyntaxErrorCollector::from_error(SyntaxErrorType::AtomExpected, self.context.clone()))
             },
             Err(lex_error) => {
@@ -52,6 +1552,20 @@ impl Lexer {
     }
 }
 
+impl Expression {
+    /// Whether the expression ends in a block and may therefore be used as a
+    /// statement without a trailing semicolon
+    pub fn is_block_like(&self) -> bool {
+        matches!(self,
+            Expression::BlockExpression { .. }
+            | Expression::IfExpression { .. }
+            | Expression::WhileExpression { .. }
+            | Expression::LoopExpression { .. }
+            | Expression::ForExpression { .. }
+            | Expression::MatchExpression { .. })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,7 +1578,7 @@ mod tests {
     fn parse_atom_int() {
         assert!(matches!(
             lexer("test.px", "2").parse_expression(),
-            Ok(Expression::IntLiteral { value: x }) if x == 2
+            Ok(Expression::IntLiteral { value: x, context: _ }) if x == 2
         ));
     }
 
@@ -72,7 +1586,7 @@ mod tests {
     fn parse_atom_float() {
         assert!(matches!(
             lexer("test.px", "2.0").parse_expression(),
-            Ok(Expression::FloatLiteral { value: x }) if x == 2.0
+            Ok(Expression::FloatLiteral { value: x, context: _ }) if x == 2.0
         ));
     }
 
@@ -80,7 +1594,7 @@ mod tests {
     fn parse_atom_string() {
         assert!(matches!(
             lexer("test.px", "\"string\"").parse_expression(),
-            Ok(Expression::StringLiteral { value: x }) if x == "string".to_string()
+            Ok(Expression::StringLiteral { value: x, .. }) if x == "string"
         ));
     }
 
@@ -88,7 +1602,7 @@ mod tests {
     fn parse_atom_char() {
         assert!(matches!(
             lexer("test.px", "'c'").parse_expression(),
-            Ok(Expression::CharLiteral { value: x }) if x == 'c'
+            Ok(Expression::CharLiteral { value: x, .. }) if x == 'c'
         ));
     }
 
@@ -96,7 +1610,7 @@ mod tests {
     fn parse_atom_true() {
         assert!(matches!(
             lexer("test.px", "true").parse_expression(),
-            Ok(Expression::BooleanLiteral { value: x }) if x == true
+            Ok(Expression::BooleanLiteral { value: x, .. }) if x
         ));
     }
 
@@ -104,7 +1618,456 @@ mod tests {
     fn parse_atom_false() {
         assert!(matches!(
             lexer("test.px", "false").parse_expression(),
-            Ok(Expression::BooleanLiteral { value: x }) if x == false
+            Ok(Expression::BooleanLiteral { value: x, .. }) if !x
+        ));
+    }
+
+    #[test]
+    fn parse_binary_precedence() {
+        assert!(matches!(
+            lexer("test.px", "1 + 2 * 3").parse_expression(),
+            Ok(Expression::BinaryOp { op: Operator::AddOperator, left: _, right, context: _ })
+                if matches!(*right, Expression::BinaryOp { op: Operator::MultiplyOperator, .. })
+        ));
+    }
+
+    #[test]
+    fn parse_tuple_expression() {
+        assert!(matches!(
+            lexer("test.px", "(1, true)").parse_expression(),
+            Ok(Expression::TupleExpression { elements }) if elements.len() == 2
+        ));
+    }
+
+    #[test]
+    fn parse_unit_and_single_tuples() {
+        assert!(matches!(
+            lexer("test.px", "()").parse_expression(),
+            Ok(Expression::TupleExpression { elements }) if elements.is_empty()
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "(1,)").parse_expression(),
+            Ok(Expression::TupleExpression { elements }) if elements.len() == 1
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "(1)").parse_expression(),
+            Ok(Expression::IntLiteral { value: 1, context: _ })
+        ));
+    }
+
+    #[test]
+    fn parse_nested_tuple_field() {
+        assert!(matches!(
+            lexer("test.px", "x.0.1").parse_expression(),
+            Ok(Expression::FieldExpression { left, right, context: _ })
+                if right == "1"
+                && matches!(*left, Expression::FieldExpression { ref right, .. } if right == "0")
+        ));
+    }
+
+    #[test]
+    fn parse_tuple_type() {
+        assert!(matches!(
+            lexer("test.px", "(i32, (bool, char))").parse_type(),
+            Ok(Type::Tuple { types }) if types == vec![
+                Type::Int32,
+                Type::Tuple { types: vec![Type::Boolean, Type::Char] },
+            ]
+        ));
+
+        assert!(matches!(lexer("test.px", "()").parse_type(), Ok(t) if t == Type::unit()));
+    }
+
+    #[test]
+    fn parse_function_type() {
+        assert!(matches!(
+            lexer("test.px", "fn(i32, fn(i32)): bool").parse_type(),
+            Ok(Type::Function { parameters, return_type })
+                if parameters == vec![
+                    Type::Int32,
+                    Type::Function { parameters: vec![Type::Int32], return_type: Box::new(Type::unit()) },
+                ]
+                && *return_type == Type::Boolean
+        ));
+    }
+
+    #[test]
+    fn parse_array_type() {
+        assert!(matches!(
+            lexer("test.px", "[[u8; 4]; 16]").parse_type(),
+            Ok(t) if t == Type::Array {
+                type_: Box::new(Type::Array { type_: Box::new(Type::UInt8), length: 4 }),
+                length: 16,
+            }
+        ));
+
+        assert!(lexer("test.px", "[u8]").parse_type().is_err());
+    }
+
+    #[test]
+    fn parse_arrays_and_indexing() {
+        assert!(matches!(
+            lexer("test.px", "[1, 2, 3,]").parse_expression(),
+            Ok(Expression::ArrayExpression { type_: Type::Inferred, elements, context: _ }) if elements.len() == 3
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "[]").parse_expression(),
+            Ok(Expression::ArrayExpression { type_: _, elements, context: _ }) if elements.is_empty()
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "[0; 16]").parse_expression(),
+            Ok(Expression::ArrayRepeatExpression { value, length, context: _ })
+                if matches!(*value, Expression::IntLiteral { value: 0, context: _ })
+                && matches!(*length, Expression::IntLiteral { value: 16, context: _ })
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "grid[1][2]").parse_expression(),
+            Ok(Expression::IndexExpression { indexed, argument, context: _ })
+                if matches!(*indexed, Expression::IndexExpression { .. })
+                && matches!(*argument, Expression::IntLiteral { value: 2, context: _ })
+        ));
+    }
+
+    #[test]
+    fn parse_struct_item() {
+        let items = lexer("test.px", "struct Pair<T> { first: T, second: [T; 2], }").parse();
+
+        assert!(matches!(&items.unwrap()[..],
+            [Item::Struct { name, type_parameters, fields, context: _, public: _, attributes: _ }]
+                if name == "Pair"
+                && *type_parameters == vec![String::from("T")]
+                && fields.len() == 2
+                && fields[1].type_ == Type::Array { type_: Box::new(Type::Type { name: String::from("T") }), length: 2 }
+        ));
+    }
+
+    #[test]
+    fn parse_enum_item() {
+        let items = lexer("test.px", "enum Shape { Circle(f64), Rectangle(f64, f64), Empty }").parse();
+
+        assert!(matches!(&items.unwrap()[..],
+            [Item::Enum { name, type_parameters, fields, context: _, public: _, attributes: _ }]
+                if name == "Shape"
+                && type_parameters.is_empty()
+                && fields.iter().map(|field| field.types.len()).collect::<Vec<usize>>() == vec![1, 2, 0]
+        ));
+    }
+
+    #[test]
+    fn parse_enum_variants() {
+        assert!(matches!(
+            lexer("test.px", "Shape::Circle(1.0)").parse_expression(),
+            Ok(Expression::CallExpression { callee, arguments, context: _ })
+                if matches!(&*callee, Expression::PathExpression { segments, context: _ } if segments.len() == 2)
+                && arguments.len() == 1
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "match s { Shape::Pair(Shape::Circle(r), _) => r, Shape::Empty => 0 }").parse_expression(),
+            Ok(Expression::MatchExpression { discriminant: _, branches, context: _ })
+                if matches!(&branches[0].pattern, Pattern::EnumPattern { path: _, patterns, context: _ }
+                    if matches!(patterns[..], [Pattern::EnumPattern { .. }, Pattern::BlankPattern]))
+                && matches!(&branches[1].pattern, Pattern::EnumPattern { path: _, patterns, context: _ } if patterns.is_empty())
+        ));
+    }
+
+    #[test]
+    fn parse_struct_expression() {
+        assert!(matches!(
+            lexer("test.px", "Point { x: 1, y }").parse_expression(),
+            Ok(Expression::StructExpression { struct_, fields, base: None, context: _ })
+                if struct_ == "Point"
+                && fields.len() == 2
+                && matches!(&fields[1].expression, Expression::Variable { name, context: _ } if name == "y")
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "Point { x: 5, ..p }").parse_expression(),
+            Ok(Expression::StructExpression { struct_: _, fields, base: Some(base), context: _ })
+                if fields.len() == 1 && matches!(*base, Expression::Variable { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_condition_before_block() {
+        assert!(matches!(
+            lexer("test.px", "if x { y } else { z }").parse_expression(),
+            Ok(Expression::IfExpression { condition, body, alternate: _, context: _ })
+                if matches!(*condition, Expression::Variable { .. })
+                && matches!(*body.expression, Expression::Variable { .. })
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "while (Point { x: 1 }) == p { p }").parse_expression(),
+            Ok(Expression::WhileExpression { label: None, condition, body: _, context: _ })
+                if matches!(*condition, Expression::BinaryOp { .. })
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "match p { q => Point { x: 1 } }").parse_expression(),
+            Ok(Expression::MatchExpression { discriminant, branches, context: _ })
+                if matches!(*discriminant, Expression::Variable { .. })
+                && matches!(branches[0].consequent, Expression::StructExpression { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_assignment() {
+        assert!(matches!(
+            lexer("test.px", "p.x[0] += 1").parse_expression(),
+            Ok(Expression::AssignExpression { target, op: Some(Operator::AddOperator), value: _, context: _ })
+                if matches!(*target, Expression::IndexExpression { .. })
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "f() = 1").parse_expression(),
+            Err(collector) if matches!(collector.errors[..],
+                [SyntaxError { error_type: SyntaxErrorType::InvalidAssignmentTarget, .. }])
+        ));
+    }
+
+    #[test]
+    fn parse_closure() {
+        assert!(matches!(
+            lexer("test.px", "|x: i32, y: i32| x + y").parse_expression(),
+            Ok(Expression::ClosureExpression { parameters, contexts: _, types, return_type: None, body, context: _ })
+                if parameters == vec!["x".to_string(), "y".to_string()]
+                && types == vec![Type::Int32, Type::Int32]
+                && matches!(*body, Expression::BinaryOp { op: Operator::AddOperator, .. })
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "|x, y: i32|: i32 x").parse_expression(),
+            Ok(Expression::ClosureExpression { parameters: _, contexts: _, types, return_type: Some(Type::Int32), body: _, context: _ })
+                if types == vec![Type::Inferred, Type::Int32]
+        ));
+    }
+
+    #[test]
+    fn parse_generic_function() {
+        assert!(matches!(
+            &lexer("test.px", "fn max<T: Ord + Eq, U>(a: T, b: T): T = a;").parse().ok().unwrap()[..],
+            [Item::Function { header, body: _ }]
+                if matches!(&header.type_parameters[..], [
+                    TypeParameter { name: t, bounds },
+                    TypeParameter { name: u, bounds: no_bounds },
+                ] if t == "T" && u == "U" && no_bounds.is_empty() && bounds == &vec![
+                    Trait::Trait { name: "Ord".to_string() },
+                    Trait::Trait { name: "Eq".to_string() },
+                ])
+                && header.types == vec![Type::Type { name: "T".to_string() }; 2]
+        ));
+    }
+
+    #[test]
+    fn parse_trait_and_instance() {
+        let items = lexer("test.px", "
+            trait Show { fn show(x: Self): bool; fn twice(x: Self): bool = true; }
+            instance Show i32 { fn show(x: i32): bool = true; }
+        ").parse();
+
+        assert!(matches!(
+            &items.ok().unwrap()[..],
+            [
+                Item::Trait { name, type_parameters: _, items: trait_items, context: _, public: _, attributes: _ },
+                Item::Instance { trait_: Trait::Trait { name: instance_name }, type_: Type::Int32, items: _, context: _, attributes: _ },
+            ] if name == "Show" && instance_name == "Show"
+                && matches!(&trait_items[..], [Item::FunctionDeclaration { .. }, Item::Function { .. }])
+        ));
+    }
+
+    #[test]
+    fn parse_const_and_static() {
+        let items = lexer("test.px", "const A: i32 = 1 + 2; static B: (i32, i32) = (A, A);").parse();
+
+        assert!(matches!(
+            &items.ok().unwrap()[..],
+            [
+                Item::ConstItem { name: a, type_: Type::Int32, value: Expression::BinaryOp { .. }, context: _, public: _, attributes: _ },
+                Item::StaticItem { name: b, type_: Type::Tuple { .. }, value: Expression::TupleExpression { .. }, context: _, public: _, attributes: _ },
+            ] if a == "A" && b == "B"
+        ));
+    }
+
+    #[test]
+    fn parse_bodiless_function_outside_trait() {
+        assert!(lexer("test.px", "fn f(x: i32);").parse().is_err());
+    }
+
+    #[test]
+    fn parse_function_default_unit() {
+        assert!(matches!(
+            &lexer("test.px", "fn main() {}").parse().ok().unwrap()[..],
+            [Item::Function { header, body: _ }] if header.return_type == Type::unit()
+        ));
+    }
+
+    #[test]
+    fn parse_let_tuple_pattern() {
+        let items = lexer("test.px", "fn main() { let (a, (_, c)): (i32, (i32, i32)) = f(); }").parse();
+
+        assert!(matches!(
+            &items.ok().unwrap()[..],
+            [Item::Function { header: _, body }] if matches!(
+                &body.statements[..],
+                [Statement::LetStatement { pattern: Pattern::TuplePattern { patterns }, type_: Some(_), .. }]
+                    if matches!(&patterns[..], [
+                        Pattern::IdentifierPattern { .. },
+                        Pattern::TuplePattern { patterns: inner },
+                    ] if matches!(&inner[..], [Pattern::BlankPattern, Pattern::IdentifierPattern { .. }]))
+            )
+        ));
+    }
+
+    #[test]
+    fn parse_error_recovers_at_next_item() {
+        let result = lexer("test.px", "fn a() { let = 1; } fn b() { 1 + }").parse();
+
+        assert!(matches!(result, Err(collector) if collector.errors.len() == 2));
+    }
+
+    #[test]
+    fn parse_misspelled_keywords() {
+        let result = lexer("test.px", "fnn a() {} fn main() { whlie x < 3 {} }").parse();
+
+        assert!(matches!(result, Err(collector) if matches!(&collector.errors[..], [
+            SyntaxError { error_type: SyntaxErrorType::ItemExpected, suggestion: Some(item), .. },
+            SyntaxError { error_type: SyntaxErrorType::StatementExpected, suggestion: Some(statement), context },
+        ] if item.replacement == "fn" && statement.replacement == "while" && context.column == 24)));
+    }
+
+    #[test]
+    fn parse_modules() {
+        let items = lexer("test.px", "mod a; mod b { fn f() {} } use super::a::*; use self::b::f as g;").parse();
+
+        assert!(matches!(
+            &items.ok().unwrap()[..],
+            [
+                Item::Module { name: _, items: None, context: _, public: _, attributes: _ },
+                Item::Module { name: _, items: Some(items), context: _, public: _, attributes: _ },
+                Item::Use { path: first, alias: None, glob: true, context: _, attributes: _ },
+                Item::Use { path: second, alias: Some(alias), glob: false, context: _, attributes: _ },
+            ] if items.len() == 1
+                && matches!(&first[..], [PathSegment::SuperPath, PathSegment::PathIdentifier { .. }])
+                && matches!(&second[..], [PathSegment::SelfPath, PathSegment::PathIdentifier { .. }, PathSegment::PathIdentifier { .. }])
+                && alias == "g"
+        ));
+    }
+
+    #[test]
+    fn parse_visibility() {
+        let items = lexer("test.px", "pub fn f() {} pub struct P { pub x: i32, y: i32 } const C: i32 = 1;").parse();
+
+        assert!(matches!(
+            &items.ok().unwrap()[..],
+            [
+                Item::Function { header, body: _ },
+                Item::Struct { fields, public: true, .. },
+                Item::ConstItem { public: false, .. },
+            ] if header.public && fields[0].public && !fields[1].public
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "pub use a::b;").parse(),
+            Err(collector) if matches!(collector.errors[..], [SyntaxError { error_type: SyntaxErrorType::UnexpectedVisibility, .. }])
+        ));
+    }
+
+    #[test]
+    fn parse_attributes() {
+        let items = lexer("test.px", "#[inline] #[cfg(not(feature = \"x\"))] pub fn f() {} struct P { #[deprecated(\"old\")] x: i32 }").parse();
+
+        assert!(matches!(
+            &items.ok().unwrap()[..],
+            [Item::Function { header, body: _ }, Item::Struct { fields, .. }]
+                if header.public
+                && matches!(&header.attributes[..], [
+                    Attribute { meta: Meta::Word(inline), .. },
+                    Attribute { meta: Meta::List(cfg, arguments), .. },
+                ] if inline == "inline" && cfg == "cfg" && matches!(&arguments[..],
+                    [Meta::List(not, inner)] if not == "not" && matches!(&inner[..], [Meta::NameValue(_, Expression::StringLiteral { .. })])))
+                && matches!(&fields[0].attributes[..], [Attribute { meta: Meta::List(_, arguments), .. }]
+                    if matches!(&arguments[..], [Meta::Literal(Expression::StringLiteral { .. })]))
+        ));
+    }
+
+    #[test]
+    fn parse_match_patterns() {
+        assert!(matches!(
+            lexer("test.px", "match x { n @ (1 | -3..0) if n != 2 => n, _ => 0 }").parse_expression(),
+            Ok(Expression::MatchExpression { discriminant: _, branches, context: _ })
+                if matches!(&branches[0], MatchBranch { pattern: Pattern::BindingPattern { name, pattern, context: _ }, guard: Some(_), .. }
+                    if name == "n" && matches!(pattern.as_ref(), Pattern::OrPattern { patterns, context: _ }
+                        if matches!(patterns[1], Pattern::RangePattern { inclusive: false, .. })))
+                && branches[1].guard.is_none()
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "match x { 1..'a' => 0 }").parse_expression(),
+            Err(SyntaxErrorCollector { errors }) if matches!(errors[..], [SyntaxError { error_type: SyntaxErrorType::PatternExpected, .. }])
+        ));
+    }
+
+    #[test]
+    fn parse_labeled_loops() {
+        assert!(matches!(
+            lexer("test.px", "'outer: for (x, _) in xs { break 'outer x; }").parse_expression(),
+            Ok(Expression::ForExpression { label: Some(label), pattern: Pattern::TuplePattern { .. }, iterator: _, body, context: _ })
+                if label == "outer"
+                && matches!(&body.statements[..], [Statement::ExpressionStatement {
+                    expression: Expression::BreakExpression { label: Some(target), returned: Some(_), context: _ },
+                }] if target == "outer")
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "loop { continue }").parse_expression(),
+            Ok(Expression::LoopExpression { label: None, body })
+                if matches!(*body.expression, Expression::ContinueExpression { label: None, context: _ })
+        ));
+
+        assert!(matches!(
+            lexer("test.px", "'outer: if x {}").parse_expression(),
+            Err(SyntaxErrorCollector { errors }) if matches!(errors[..], [SyntaxError { error_type: SyntaxErrorType::LoopExpected, .. }])
+        ));
+    }
+
+    #[test]
+    fn parse_macro_errors() {
+        let result = lexer("test.px", "
+            macro forever { () => { forever!() } }
+            macro one { ($x:literal) => { $x } }
+            macro broken { () => { 1 + } }
+            fn a() = missing!();
+            fn b() = one!(x);
+            fn c() = forever!();
+            fn d() = broken!();
+        ").parse();
+
+        let Err(collector) = result else { panic!("expected errors") };
+        assert!(matches!(&collector.errors[..], [
+            SyntaxError { error_type: SyntaxErrorType::UndefinedMacro(_), .. },
+            SyntaxError { error_type: SyntaxErrorType::NoMatchingMacroRule(_), .. },
+            SyntaxError { error_type: SyntaxErrorType::MacroRecursionLimit(_), context: limit, suggestion: _ },
+            SyntaxError { error_type: SyntaxErrorType::AtomExpected, context: broken, suggestion: _ },
+        ] if limit.backtrace().len() == MAX_MACRO_DEPTH
+            && matches!(&broken.backtrace()[..], [(name, call)] if name == "broken" && call.line == 8)));
+    }
+
+    #[test]
+    fn parse_after_macro_call() {
+        let mut lexer = lexer("test.px", "macro id { ($x:expr) => { $x } } fn f() = (id!(1), y);");
+        let items = lexer.parse();
+
+        assert!(matches!(
+            &items.ok().unwrap()[..],
+            [Item::Macro { .. }, Item::Function { header: _, body }] if matches!(&*body.expression,
+                Expression::TupleExpression { elements } if matches!(&elements[1],
+                    Expression::Variable { name, context } if name == "y" && context.column == 52 && context.expansion.is_none()))
         ));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,148 @@
+use crate::nodes::Type;
+use crate::syntax_errors::SyntaxErrorCollector;
+use crate::tokens::TokenContext;
+use std::fmt;
+
+/// Why a program stopped before it finished
+#[derive(Debug)]
+pub enum ProgramError {
+    /// Mistakes in the source, whether they were found before the program ran
+    /// or only once it reached them
+    Syntax(SyntaxErrorCollector),
+    /// A failure of a valid program while it ran
+    Runtime(Box<RuntimeError>),
+}
+
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub error_type: RuntimeErrorType,
+    pub context: TokenContext,
+    /// The calls that were running when the error happened, innermost first
+    pub stack: Vec<Frame>,
+}
+
+/// A call of `function` from `context`
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub function: String,
+    pub context: TokenContext,
+}
+
+#[derive(Debug, Clone)]
+pub enum RuntimeErrorType {
+    DivisionByZero,
+    IntegerOverflow,
+    IndexOutOfBounds { index: i128, length: usize },
+    /// `unwrap` of a `None`
+    UnwrapNone,
+    TypeMismatch { expected: Type, found: Type },
+    /// A call of `panic` with the message it was given
+    Panic(String),
+    /// More calls running at once than `MAX_CALL_DEPTH`
+    StackOverflow,
+    /// A value that didn't match the pattern of a `let` or `for`
+    RefutablePattern,
+    NonExhaustiveMatch,
+    /// A `break` or `continue` that left the function or deferred block it
+    /// was run in
+    InvalidControlFlow,
+}
+
+impl From<SyntaxErrorCollector> for ProgramError {
+    fn from(errors: SyntaxErrorCollector) -> ProgramError {
+        ProgramError::Syntax(errors)
+    }
+}
+
+impl RuntimeError {
+    pub fn new(error_type: RuntimeErrorType, context: TokenContext) -> RuntimeError {
+        RuntimeError { error_type, context, stack: Vec::new() }
+    }
+
+    /// Each running function with where it was when the error happened,
+    /// innermost first. The outermost call has no caller, so the place it was
+    /// called from isn't part of the trace
+    pub fn backtrace(&self) -> Vec<(&str, &TokenContext)> {
+        let places = std::iter::once(&self.context).chain(self.stack.iter().map(|frame| &frame.context));
+        self.stack.iter().map(|frame| frame.function.as_str()).zip(places).collect()
+    }
+}
+
+/// The frames of a backtrace with their index, each followed by how many
+/// times it repeats right after itself, so deep recursion shows as one frame
+pub fn collapse_frames<'a>(frames: impl IntoIterator<Item = (&'a str, &'a TokenContext)>) -> Vec<(usize, &'a str, &'a TokenContext, usize)> {
+    let mut collapsed: Vec<(usize, &str, &TokenContext, usize)> = Vec::new();
+    for (index, (function, context)) in frames.into_iter().enumerate() {
+        match collapsed.last_mut() {
+            Some((_, previous, place, repeated)) if *previous == function
+                && (&place.filename, place.line, place.column) == (&context.filename, context.line, context.column) => *repeated += 1,
+            _ => collapsed.push((index, function, context, 0)),
+        }
+    }
+    collapsed
+}
+
+impl RuntimeErrorType {
+    /// The stable code of the error. Errors that used to be found as syntax
+    /// errors keep the codes they had then
+    pub fn code(&self) -> &'static str {
+        use RuntimeErrorType::*;
+
+        match self {
+            TypeMismatch { .. } => "PX0023",
+            RefutablePattern => "PX0026",
+            NonExhaustiveMatch => "PX0027",
+            DivisionByZero => "PX0028",
+            IntegerOverflow => "PX0029",
+            InvalidControlFlow => "PX0030",
+            IndexOutOfBounds { .. } => "PX0046",
+            UnwrapNone => "PX0067",
+            Panic(_) => "PX0068",
+            StackOverflow => "PX0073",
+        }
+    }
+}
+
+impl fmt::Display for RuntimeErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use RuntimeErrorType::*;
+
+        match self {
+            DivisionByZero => write!(f, "division by zero"),
+            IntegerOverflow => write!(f, "integer overflow"),
+            IndexOutOfBounds { index, length } => write!(f, "index {} is out of bounds for a length of {}", index, length),
+            UnwrapNone => write!(f, "`unwrap` was called on `None`"),
+            TypeMismatch { expected, found } => {
+                write!(f, "expected a value of type `{}`, found `{}`", expected.as_string(), found.as_string())
+            },
+            Panic(message) => write!(f, "the program panicked: {}", message),
+            StackOverflow => write!(f, "the stack overflowed after {} nested calls", crate::interpreter::MAX_CALL_DEPTH),
+            RefutablePattern => write!(f, "the value doesn't match the pattern"),
+            NonExhaustiveMatch => write!(f, "no arm of the match matched the value"),
+            InvalidControlFlow => write!(f, "`break` or `continue` can't leave a function or deferred block"),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let context = &self.context;
+        writeln!(f, "{}:{}:{}: error[{}]: {}", context.filename, context.line, context.column, self.error_type.code(), self.error_type)?;
+        for (index, function, context, repeated) in collapse_frames(self.backtrace()) {
+            writeln!(f, "{:>4}: {} at {}:{}:{}", index, function, context.filename, context.line, context.column)?;
+            if repeated > 0 {
+                writeln!(f, "      [previous frame repeated {} times]", repeated)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProgramError::Syntax(errors) => write!(f, "{}", errors),
+            ProgramError::Runtime(error) => write!(f, "{}", error),
+        }
+    }
+}
@@ -0,0 +1,2956 @@
+use crate::interpreter::{implements, BUILTIN_FUNCTIONS, BUILTIN_TRAITS};
+use crate::lints::LintLevels;
+use crate::nodes::*;
+use crate::suggestions;
+use crate::syntax_errors::*;
+use crate::tokens::TokenContext;
+use std::collections::{HashMap, HashSet};
+
+/// Checks the parts of a program that can be known before it runs, other than
+/// types. For now these are the lengths of repeat arrays, the enum variants
+/// named in expressions and patterns, the loops that `break` and `continue`
+/// refer to and which match arms can be reached.
+pub struct StaticAnalyzer {
+    enums: HashMap<String, Vec<EnumField>>,
+    /// The labels of the loops around the current expression, innermost last,
+    /// and whether each is a `loop` that can break with a value
+    loops: Vec<(Option<String>, bool)>,
+    /// The lint levels of the current item
+    lints: LintLevels,
+    errors: SyntaxErrorCollector,
+    warnings: Vec<SyntaxWarning>,
+}
+
+impl StaticAnalyzer {
+    pub fn new(lints: LintLevels) -> StaticAnalyzer {
+        StaticAnalyzer {
+            enums: HashMap::new(),
+            loops: Vec::new(),
+            lints,
+            errors: SyntaxErrorCollector::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn analyze(mut self, items: &[Item]) -> Result<Vec<SyntaxWarning>, SyntaxErrorCollector> {
+        for item in items {
+            if let Item::Enum { name, type_parameters: _, fields, context: _, public: _, attributes: _ } = item {
+                self.enums.insert(name.clone(), fields.clone());
+            }
+        }
+        for item in items {
+            self.check_item(item);
+        }
+        if self.errors.is_empty() { Ok(self.warnings) } else { Err(self.errors) }
+    }
+
+    fn check_item(&mut self, item: &Item) {
+        let lints = self.lints.with_attributes(item.attributes());
+        let outer = std::mem::replace(&mut self.lints, lints);
+        match item {
+            Item::Function { header: _, body } => self.check_block(body),
+            Item::Trait { name: _, type_parameters: _, items, context: _, public: _, attributes: _ } | Item::Instance { trait_: _, type_: _, items, context: _, attributes: _ } => {
+                for item in items {
+                    self.check_item(item);
+                }
+            },
+            Item::ConstItem { name: _, type_: _, value, context: _, public: _, attributes: _ } | Item::StaticItem { name: _, type_: _, value, context: _, public: _, attributes: _ } => {
+                self.check_expression(value);
+            },
+            _ => {},
+        }
+        self.lints = outer;
+    }
+
+    fn check_block(&mut self, block: &Block) {
+        for statement in &block.statements {
+            match statement {
+                Statement::LetStatement { pattern, type_: _, type_context: _, expression, context: _ }
+                | Statement::ConstStatement { pattern, type_: _, type_context: _, expression, context: _ } => {
+                    self.check_pattern(pattern);
+                    self.check_expression(expression);
+                },
+                Statement::ExpressionStatement { expression } => self.check_expression(expression),
+                Statement::DeferStatement { body, context: _ } => {
+                    // A deferred block can't leave the loops it is run from
+                    let loops = std::mem::take(&mut self.loops);
+                    self.check_block(body);
+                    self.loops = loops;
+                },
+            }
+        }
+        self.check_expression(&block.expression);
+    }
+
+    fn check_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::ArrayExpression { type_: _, elements, context: _ } => {
+                for element in elements {
+                    self.check_expression(element);
+                }
+            },
+            Expression::ArrayRepeatExpression { value, length, context } => {
+                self.check_expression(value);
+                self.check_expression(length);
+                if let Expression::IntLiteral { value, context: _ } = length.as_ref() {
+                    if usize::try_from(*value).is_err() {
+                        self.error(SyntaxErrorType::InvalidArrayLength(*value), context);
+                    }
+                }
+            },
+            Expression::IndexExpression { indexed: left, argument: right, context: _ }
+            | Expression::BinaryOp { op: _, left, right, context: _ }
+            | Expression::CatchExpression { expression: left, result: right } => {
+                self.check_expression(left);
+                self.check_expression(right);
+            },
+            Expression::TupleExpression { elements } => {
+                for element in elements {
+                    self.check_expression(element);
+                }
+            },
+            Expression::CallExpression { callee, arguments, context } => {
+                match callee.as_ref() {
+                    Expression::PathExpression { segments, context: _ } => self.check_variant(segments, arguments.len(), context),
+                    _ => self.check_expression(callee),
+                }
+                for argument in arguments {
+                    self.check_expression(argument);
+                }
+            },
+            Expression::BlockExpression { body } => self.check_block(body),
+            Expression::LoopExpression { label, body } => {
+                self.loops.push((label.clone(), true));
+                self.check_block(body);
+                self.loops.pop();
+            },
+            Expression::IfExpression { condition, body, alternate, context: _ } => {
+                self.check_expression(condition);
+                self.check_block(body);
+                self.check_block(alternate);
+            },
+            Expression::WhileExpression { label, condition, body, context: _ } => {
+                self.check_expression(condition);
+                self.loops.push((label.clone(), false));
+                self.check_block(body);
+                self.loops.pop();
+            },
+            Expression::ForExpression { label, pattern, iterator, body, context: _ } => {
+                self.check_expression(iterator);
+                self.check_pattern(pattern);
+                self.loops.push((label.clone(), false));
+                self.check_block(body);
+                self.loops.pop();
+            },
+            Expression::MatchExpression { discriminant, branches, context: _ } => {
+                self.check_expression(discriminant);
+                for (index, branch) in branches.iter().enumerate() {
+                    self.check_pattern(&branch.pattern);
+                    if let Some(guard) = &branch.guard {
+                        self.check_expression(guard);
+                    }
+                    self.check_expression(&branch.consequent);
+
+                    // Arms with a guard may not match, so they never make the
+                    // arms after them unreachable
+                    let covering = branches[..index].iter()
+                        .find(|earlier| earlier.guard.is_none() && Self::covers(&earlier.pattern, &branch.pattern));
+                    if let Some(earlier) = covering {
+                        let warning = SyntaxWarning {
+                            warning_type: SyntaxWarningType::UnreachablePattern { covered_by: earlier.context.clone() },
+                            context: branch.context.clone(),
+                        };
+                        self.lints.report(warning, &mut self.warnings, &mut self.errors);
+                    }
+                }
+            },
+            Expression::ReturnExpression { returned } => {
+                if let Some(returned) = returned {
+                    self.check_expression(returned);
+                }
+            },
+            Expression::BreakExpression { label, returned, context } => {
+                if let Some(returned) = returned {
+                    self.check_expression(returned);
+                }
+                let target = self.check_label(label, context);
+                if returned.is_some() && matches!(target, Some((_, false))) {
+                    self.error(SyntaxErrorType::InvalidBreakValue, context);
+                }
+            },
+            Expression::ContinueExpression { label, context } => {
+                self.check_label(label, context);
+            },
+            Expression::StructExpression { struct_: _, fields, base, context: _ } => {
+                for field in fields {
+                    self.check_expression(&field.expression);
+                }
+                if let Some(base) = base {
+                    self.check_expression(base);
+                }
+            },
+            Expression::AssignExpression { target, op: _, value, context: _ } => {
+                self.check_expression(target);
+                self.check_expression(value);
+            },
+            Expression::ClosureExpression { parameters: _, contexts: _, types: _, return_type: _, body, context: _ } => {
+                // Loops outside a closure can't be broken out of from inside it
+                let loops = std::mem::take(&mut self.loops);
+                self.check_expression(body);
+                self.loops = loops;
+            },
+            Expression::TryExpression { expression: body }
+            | Expression::FieldExpression { left: body, right: _, context: _ }
+            | Expression::TypeCastExpression { value: body, type_: _ }
+            | Expression::UnaryOp { op: _, child: body, context: _ } => self.check_expression(body),
+            Expression::PathExpression { segments, context } => self.check_variant(segments, 0, context),
+            Expression::Variable { .. }
+            | Expression::IntLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::CharLiteral { .. }
+            | Expression::BooleanLiteral { .. } => {},
+        }
+    }
+
+    /// Finds the loop that a `break` or `continue` with `label` leaves, which
+    /// is the innermost one if there is no label
+    fn check_label(&mut self, label: &Option<String>, context: &TokenContext) -> Option<(Option<String>, bool)> {
+        let target = match label {
+            Some(label) => self.loops.iter().rev().find(|(name, _)| name.as_ref() == Some(label)),
+            None => self.loops.last(),
+        }.cloned();
+        match (label, &target) {
+            (Some(label), None) => self.error(SyntaxErrorType::UndefinedLabel(label.clone()), context),
+            (None, None) => self.error(SyntaxErrorType::InvalidControlFlow, context),
+            _ => {},
+        }
+        target
+    }
+
+    fn check_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::EnumPattern { path, patterns, context } => {
+                self.check_variant(path, patterns.len(), context);
+                for pattern in patterns {
+                    self.check_pattern(pattern);
+                }
+            },
+            Pattern::TuplePattern { patterns } => {
+                for pattern in patterns {
+                    self.check_pattern(pattern);
+                }
+            },
+            Pattern::OrPattern { patterns, context } => {
+                for pattern in patterns {
+                    self.check_pattern(pattern);
+                }
+                let mut expected = patterns[0].bindings();
+                expected.sort();
+                for pattern in &patterns[1..] {
+                    let mut bindings = pattern.bindings();
+                    bindings.sort();
+                    if let Some(name) = expected.iter().chain(&bindings).find(|name| !expected.contains(name) || !bindings.contains(name)) {
+                        self.error(SyntaxErrorType::InconsistentBindings(name.clone()), context);
+                    }
+                }
+            },
+            Pattern::BindingPattern { name: _, pattern, context: _ } => self.check_pattern(pattern),
+            Pattern::RangePattern { context, .. } => {
+                if Self::bounds(pattern).is_none() {
+                    self.error(SyntaxErrorType::EmptyRangePattern, context);
+                }
+            },
+            Pattern::IdentifierPattern { .. } | Pattern::LiteralPattern { .. } | Pattern::BlankPattern => {},
+        }
+    }
+
+    /// Whether a pattern matches every value of the type it is matched with
+    fn irrefutable(pattern: &Pattern) -> bool {
+        match pattern {
+            Pattern::IdentifierPattern { .. } | Pattern::BlankPattern => true,
+            Pattern::TuplePattern { patterns } => patterns.iter().all(Self::irrefutable),
+            Pattern::OrPattern { patterns, context: _ } => patterns.iter().any(Self::irrefutable),
+            Pattern::BindingPattern { name: _, pattern, context: _ } => Self::irrefutable(pattern),
+            Pattern::LiteralPattern { .. } | Pattern::RangePattern { .. } | Pattern::EnumPattern { .. } => false,
+        }
+    }
+
+    /// Whether `pattern` matches every value that `other` matches, in which
+    /// case an arm with `other` after an unguarded arm with `pattern` can
+    /// never be reached
+    fn covers(pattern: &Pattern, other: &Pattern) -> bool {
+        if Self::irrefutable(pattern) {
+            return true;
+        }
+        match (pattern, other) {
+            (_, Pattern::OrPattern { patterns, context: _ }) => patterns.iter().all(|other| Self::covers(pattern, other)),
+            (Pattern::OrPattern { patterns, context: _ }, _) => patterns.iter().any(|pattern| Self::covers(pattern, other)),
+            (Pattern::BindingPattern { name: _, pattern, context: _ }, _) => Self::covers(pattern, other),
+            (_, Pattern::BindingPattern { name: _, pattern: other, context: _ }) => Self::covers(pattern, other),
+            (Pattern::TuplePattern { patterns }, Pattern::TuplePattern { patterns: others }) => {
+                patterns.len() == others.len() && patterns.iter().zip(others).all(|(pattern, other)| Self::covers(pattern, other))
+            },
+            (
+                Pattern::EnumPattern { path, patterns, context: _ },
+                Pattern::EnumPattern { path: other_path, patterns: others, context: _ },
+            ) => {
+                PathSegment::variant(path).is_some() && PathSegment::variant(path) == PathSegment::variant(other_path)
+                    && patterns.len() == others.len()
+                    && patterns.iter().zip(others).all(|(pattern, other)| Self::covers(pattern, other))
+            },
+            (Pattern::LiteralPattern { literal }, Pattern::LiteralPattern { literal: other_literal }) if Self::bounds(pattern).is_none() => {
+                match (literal.as_ref(), other_literal.as_ref()) {
+                    (Expression::StringLiteral { value: x, .. }, Expression::StringLiteral { value: y, .. }) => x == y,
+                    (Expression::FloatLiteral { value: x, context: _ }, Expression::FloatLiteral { value: y, context: _ }) => x == y,
+                    (Expression::BooleanLiteral { value: x, .. }, Expression::BooleanLiteral { value: y, .. }) => x == y,
+                    _ => false,
+                }
+            },
+            _ => match (Self::bounds(pattern), Self::bounds(other)) {
+                (Some((kind, low, high)), Some((other_kind, other_low, other_high))) => {
+                    kind == other_kind && low <= other_low && other_high <= high
+                },
+                _ => false,
+            },
+        }
+    }
+
+    /// The smallest and largest integer or character a literal or range
+    /// pattern matches, with characters as their code points, or `None` if it
+    /// matches no such values
+    fn bounds(pattern: &Pattern) -> Option<(Type, i128, i128)> {
+        let value = |expression: &Expression| match expression {
+            Expression::IntLiteral { value, context: _ } => Some((Type::IntSize, *value)),
+            Expression::CharLiteral { value, context: _ } => Some((Type::Char, *value as i128)),
+            _ => None,
+        };
+        match pattern {
+            Pattern::LiteralPattern { literal } => value(literal).map(|(kind, value)| (kind, value, value)),
+            Pattern::RangePattern { start, end, inclusive, context: _ } => {
+                let ((kind, low), (_, high)) = (value(start)?, value(end)?);
+                let high = if *inclusive { high } else { high - 1 };
+                if low <= high { Some((kind, low, high)) } else { None }
+            },
+            _ => None,
+        }
+    }
+
+    /// Checks that a path names an enum variant with `arity` payload values
+    fn check_variant(&mut self, path: &[PathSegment], arity: usize, context: &TokenContext) {
+        let variant = PathSegment::variant(path)
+            .and_then(|(enum_, variant)| Some((enum_, variant, self.enums.get(enum_)?)));
+        let (enum_, variant, fields) = match variant {
+            Some(variant) => variant,
+            None => {
+                self.error(SyntaxErrorType::UndefinedVariable(PathSegment::path_as_string(path)), context);
+                return;
+            },
+        };
+
+        match fields.iter().find(|field| field.name == *variant) {
+            Some(field) if field.types.len() != arity => self.error(SyntaxErrorType::VariantArityMismatch {
+                variant: PathSegment::path_as_string(path),
+                expected: field.types.len(),
+                found: arity,
+            }, context),
+            Some(_) => {},
+            None => {
+                let written = PathSegment::path_as_string(path);
+                let prefix = &written[..written.len() - variant.len()];
+                let candidates: Vec<String> = fields.iter().map(|field| format!("{}{}", prefix, field.name)).collect();
+                self.error(SyntaxErrorType::UndefinedVariant { enum_: enum_.clone(), variant: variant.clone() }, context);
+                self.errors.suggest_name(&written, candidates.iter().map(String::as_str));
+            },
+        }
+    }
+
+    fn error(&mut self, error_type: SyntaxErrorType, context: &TokenContext) {
+        self.errors.errors.push(SyntaxError { error_type, context: context.clone(), suggestion: None });
+    }
+}
+
+/// The types that are part of the interpreter rather than the program
+const BUILTIN_TYPES: [&str; 1] = ["str"];
+
+/// What a declaration declares
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeclarationKind {
+    Function,
+    /// A function of a trait or an instance, which calls find through the
+    /// type of their first argument
+    Method,
+    Const,
+    Static,
+    /// A variable bound by a pattern
+    Variable,
+    Parameter,
+    Struct,
+    Enum,
+    Variant,
+    Trait,
+    TypeAlias,
+    TypeParameter,
+    /// A function, type or trait that is part of the interpreter
+    Builtin,
+}
+
+#[derive(Debug, Clone)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+    /// Where the name is declared, which is empty for builtins
+    pub context: TokenContext,
+}
+
+/// A region of the program names are declared in, such as a function or a
+/// block. Types and traits are declared apart from values, so a struct and a
+/// function may share a name
+#[derive(Debug)]
+pub struct Scope {
+    pub parent: Option<usize>,
+    values: HashMap<String, usize>,
+    types: HashMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Namespace {
+    Value,
+    Type,
+}
+
+/// The scopes of a program and the declaration every name in it refers to,
+/// so that names can be followed to their declaration without looking them
+/// up by their string
+#[derive(Debug, Default)]
+pub struct NameTable {
+    pub scopes: Vec<Scope>,
+    pub declarations: Vec<Declaration>,
+    /// The declarations of variables, paths and struct literals, by the file
+    /// and index of their token
+    values: HashMap<(String, usize), usize>,
+}
+
+/// The types of the number literals of a program, by the file and index of
+/// their token, as the type checker solved them
+#[derive(Debug, Clone, Default)]
+pub struct LiteralTypes {
+    types: HashMap<(String, usize), Type>,
+}
+
+impl LiteralTypes {
+    /// The type of the number literal at `context`
+    pub fn type_(&self, context: &TokenContext) -> Option<&Type> {
+        self.types.get(&(context.filename.clone(), context.index))
+    }
+}
+
+impl NameTable {
+    /// The declaration of the variable, path or struct literal at `context`
+    pub fn value(&self, context: &TokenContext) -> Option<&Declaration> {
+        let declaration = self.values.get(&(context.filename.clone(), context.index))?;
+        Some(&self.declarations[*declaration])
+    }
+}
+
+impl Scope {
+    fn names(&mut self, namespace: Namespace) -> &mut HashMap<String, usize> {
+        match namespace {
+            Namespace::Value => &mut self.values,
+            Namespace::Type => &mut self.types,
+        }
+    }
+}
+
+/// Binds the variables, paths, struct literals, types and traits of a program
+/// to their declarations and reports the names that are declared twice or
+/// not at all. Modules are resolved first, so items have their full names.
+/// Paths that don't lead to a variant are left to `StaticAnalyzer`, which
+/// checks them along with their payloads.
+pub struct NameResolver {
+    table: NameTable,
+    /// The scope names are currently declared in and looked up from
+    scope: usize,
+    /// The first declaration of each method name
+    methods: HashMap<String, usize>,
+    /// The variants of every enum, by their paths such as `Shape::Circle`
+    variants: HashMap<String, usize>,
+    /// Where the types being resolved are written, which is the item or
+    /// statement they are in unless they have a position of their own
+    context: TokenContext,
+    errors: SyntaxErrorCollector,
+}
+
+impl NameResolver {
+    pub fn new() -> NameResolver {
+        NameResolver {
+            table: NameTable::default(),
+            scope: 0,
+            methods: HashMap::new(),
+            variants: HashMap::new(),
+            context: TokenContext::new(String::new(), 0, 0, 0),
+            errors: SyntaxErrorCollector::new(),
+        }
+    }
+
+    pub fn resolve(mut self, items: &[Item]) -> Result<NameTable, SyntaxErrorCollector> {
+        // Builtins sit in a scope around the program, so the program may
+        // declare names that hide them
+        self.scope_in();
+        let builtin = TokenContext::new(String::new(), 0, 0, 0);
+        for name in BUILTIN_FUNCTIONS {
+            self.declare(Namespace::Value, name, DeclarationKind::Builtin, &builtin);
+        }
+        for name in BUILTIN_TRAITS.into_iter().chain(BUILTIN_TYPES) {
+            self.declare(Namespace::Type, name, DeclarationKind::Builtin, &builtin);
+        }
+
+        self.scope_in();
+        for item in items {
+            self.declare_item(item);
+        }
+        for item in items {
+            self.resolve_item(item);
+        }
+        if self.errors.is_empty() { Ok(self.table) } else { Err(self.errors) }
+    }
+
+    fn scope_in(&mut self) {
+        let parent = if self.table.scopes.is_empty() { None } else { Some(self.scope) };
+        self.table.scopes.push(Scope { parent, values: HashMap::new(), types: HashMap::new() });
+        self.scope = self.table.scopes.len() - 1;
+    }
+
+    fn scope_out(&mut self) {
+        self.scope = self.table.scopes[self.scope].parent.unwrap_or(0);
+    }
+
+    fn add_declaration(&mut self, name: &str, kind: DeclarationKind, context: &TokenContext) -> usize {
+        self.table.declarations.push(Declaration { name: name.to_string(), kind, context: context.clone() });
+        self.table.declarations.len() - 1
+    }
+
+    /// Declares `name` in the current scope, which mustn't declare it already
+    fn declare(&mut self, namespace: Namespace, name: &str, kind: DeclarationKind, context: &TokenContext) -> usize {
+        let declaration = self.add_declaration(name, kind, context);
+        if let Some(previous) = self.table.scopes[self.scope].names(namespace).insert(name.to_string(), declaration) {
+            self.duplicate(name, previous, context);
+        }
+        declaration
+    }
+
+    fn duplicate(&mut self, name: &str, previous: usize, context: &TokenContext) {
+        let previous = self.table.declarations[previous].context.clone();
+        self.error(SyntaxErrorType::DuplicateDefinition { name: name.to_string(), previous }, context);
+    }
+
+    fn lookup(&self, namespace: Namespace, name: &str) -> Option<usize> {
+        let mut scope = Some(self.scope);
+        while let Some(index) = scope {
+            let names = match namespace {
+                Namespace::Value => &self.table.scopes[index].values,
+                Namespace::Type => &self.table.scopes[index].types,
+            };
+            if let Some(declaration) = names.get(name) {
+                return Some(*declaration);
+            }
+            scope = self.table.scopes[index].parent;
+        }
+        None
+    }
+
+    /// The names visible from the current scope that `accept` holds for
+    fn visible(&self, namespace: Namespace, accept: impl Fn(&Declaration) -> bool) -> Vec<String> {
+        let mut visible = Vec::new();
+        let mut scope = Some(self.scope);
+        while let Some(index) = scope {
+            let names = match namespace {
+                Namespace::Value => &self.table.scopes[index].values,
+                Namespace::Type => &self.table.scopes[index].types,
+            };
+            visible.extend(names.iter()
+                .filter(|(_, declaration)| accept(&self.table.declarations[**declaration]))
+                .map(|(name, _)| name.clone()));
+            scope = self.table.scopes[index].parent;
+        }
+        visible
+    }
+
+    fn declare_item(&mut self, item: &Item) {
+        match item {
+            Item::Function { header, body: _ } => {
+                self.declare(Namespace::Value, &header.name, DeclarationKind::Function, &header.context);
+            },
+            Item::ConstItem { name, context, .. } => {
+                self.declare(Namespace::Value, name, DeclarationKind::Const, context);
+            },
+            Item::StaticItem { name, context, .. } => {
+                self.declare(Namespace::Value, name, DeclarationKind::Static, context);
+            },
+            Item::Struct { name, context, .. } => {
+                self.declare(Namespace::Type, name, DeclarationKind::Struct, context);
+            },
+            Item::Enum { name, type_parameters: _, fields, context, public: _, attributes: _ } => {
+                self.declare(Namespace::Type, name, DeclarationKind::Enum, context);
+                for field in fields {
+                    let path = format!("{}::{}", name, field.name);
+                    let declaration = self.add_declaration(&path, DeclarationKind::Variant, context);
+                    if let Some(previous) = self.variants.insert(path.clone(), declaration) {
+                        self.duplicate(&path, previous, context);
+                    }
+                }
+            },
+            Item::Trait { name, type_parameters: _, items, context, public: _, attributes: _ } => {
+                self.declare(Namespace::Type, name, DeclarationKind::Trait, context);
+                self.declare_methods(items);
+            },
+            Item::Instance { trait_: _, type_: _, items, context: _, attributes: _ } => self.declare_methods(items),
+            Item::TypeAlias { newtype, oldtype: _, attributes: _ } => {
+                let context = self.context.clone();
+                self.declare(Namespace::Type, newtype, DeclarationKind::TypeAlias, &context);
+            },
+            _ => {},
+        }
+    }
+
+    /// Declares the methods of a trait or an instance. Every instance
+    /// declares the methods of its trait again, so a method name may only
+    /// appear once in each of them
+    fn declare_methods(&mut self, items: &[Item]) {
+        let mut declared: HashMap<&str, usize> = HashMap::new();
+        for item in items {
+            if let Item::Function { header, body: _ } | Item::FunctionDeclaration { header } = item {
+                let declaration = self.add_declaration(&header.name, DeclarationKind::Method, &header.context);
+                match declared.insert(&header.name, declaration) {
+                    Some(previous) => self.duplicate(&header.name, previous, &header.context),
+                    None => { self.methods.entry(header.name.clone()).or_insert(declaration); },
+                }
+            }
+        }
+    }
+
+    fn resolve_item(&mut self, item: &Item) {
+        match item {
+            Item::Function { header, body } => {
+                self.scope_in();
+                self.resolve_header(header);
+                self.resolve_block_contents(body);
+                self.scope_out();
+            },
+            Item::FunctionDeclaration { header } => {
+                self.scope_in();
+                self.resolve_header(header);
+                self.scope_out();
+            },
+            Item::Struct { name: _, type_parameters, fields, context, public: _, attributes: _ } => {
+                self.context = context.clone();
+                self.scope_in();
+                self.declare_type_parameters(type_parameters, context);
+                for field in fields {
+                    self.resolve_type(&field.type_);
+                }
+                self.scope_out();
+            },
+            Item::Enum { name: _, type_parameters, fields, context, public: _, attributes: _ } => {
+                self.context = context.clone();
+                self.scope_in();
+                self.declare_type_parameters(type_parameters, context);
+                for type_ in fields.iter().flat_map(|field| &field.types) {
+                    self.resolve_type(type_);
+                }
+                self.scope_out();
+            },
+            Item::Trait { name: _, type_parameters, items, context, public: _, attributes: _ } => {
+                self.scope_in();
+                self.declare_type_parameters(type_parameters, context);
+                self.declare(Namespace::Type, "Self", DeclarationKind::TypeParameter, context);
+                for item in items {
+                    self.resolve_item(item);
+                }
+                self.scope_out();
+            },
+            Item::Instance { trait_, type_, items, context, attributes: _ } => {
+                self.context = context.clone();
+                self.resolve_trait(trait_);
+                self.resolve_type(type_);
+                self.scope_in();
+                self.declare(Namespace::Type, "Self", DeclarationKind::TypeParameter, context);
+                for item in items {
+                    self.resolve_item(item);
+                }
+                self.scope_out();
+            },
+            Item::ConstItem { name: _, type_, value, context, public: _, attributes: _ }
+            | Item::StaticItem { name: _, type_, value, context, public: _, attributes: _ } => {
+                self.context = context.clone();
+                self.resolve_type(type_);
+                self.resolve_expression(value);
+            },
+            Item::TypeAlias { newtype: _, oldtype, attributes: _ } => self.resolve_type(oldtype),
+            _ => {},
+        }
+    }
+
+    fn declare_type_parameters(&mut self, type_parameters: &[String], context: &TokenContext) {
+        for name in type_parameters {
+            self.declare(Namespace::Type, name, DeclarationKind::TypeParameter, context);
+        }
+    }
+
+    /// Declares the type parameters and parameters of a function in the
+    /// current scope, which its body is resolved in too
+    fn resolve_header(&mut self, header: &FunctionHeader) {
+        self.context = header.context.clone();
+        let type_parameters: Vec<String> = header.type_parameters.iter().map(|parameter| parameter.name.clone()).collect();
+        self.declare_type_parameters(&type_parameters, &header.context);
+        for bound in header.type_parameters.iter().flat_map(|parameter| &parameter.bounds) {
+            self.resolve_trait(bound);
+        }
+        // The types of parameters are reported at the parameter they belong to
+        for (type_, context) in header.types.iter().zip(&header.contexts) {
+            self.context = context.clone();
+            self.resolve_type(type_);
+        }
+        self.context = header.context.clone();
+        self.resolve_type(&header.return_type);
+        for (parameter, context) in header.parameters.iter().flatten().zip(&header.contexts) {
+            self.declare(Namespace::Value, parameter, DeclarationKind::Parameter, context);
+        }
+    }
+
+    fn resolve_block(&mut self, block: &Block) {
+        self.scope_in();
+        self.resolve_block_contents(block);
+        self.scope_out();
+    }
+
+    fn resolve_block_contents(&mut self, block: &Block) {
+        for statement in &block.statements {
+            match statement {
+                Statement::LetStatement { pattern, type_, type_context, expression, context }
+                | Statement::ConstStatement { pattern, type_, type_context, expression, context } => {
+                    // The value is computed before its variables exist, so
+                    // `let x = x + 1` reads the `x` from before
+                    self.context = context.clone();
+                    self.resolve_expression(expression);
+                    if let (Some(type_), Some(type_context)) = (type_, type_context) {
+                        self.context = type_context.clone();
+                        self.resolve_type(type_);
+                    }
+                    self.declare_pattern(pattern, &mut HashMap::new(), true);
+                },
+                Statement::ExpressionStatement { expression } => self.resolve_expression(expression),
+                // A deferred block runs as the block is left, but only sees
+                // the variables declared before it
+                Statement::DeferStatement { body, context: _ } => self.resolve_block(body),
+            }
+        }
+        self.resolve_expression(&block.expression);
+    }
+
+    /// Declares the variables a pattern binds, each of which it may only bind
+    /// once. Every alternative of an or-pattern binds the same names, which
+    /// are declared by the first of them
+    fn declare_pattern(&mut self, pattern: &Pattern, bound: &mut HashMap<String, usize>, declare: bool) {
+        match pattern {
+            Pattern::IdentifierPattern { name, context } => self.declare_binding(name, context, bound, declare),
+            Pattern::BindingPattern { name, pattern, context } => {
+                self.declare_binding(name, context, bound, declare);
+                self.declare_pattern(pattern, bound, declare);
+            },
+            Pattern::TuplePattern { patterns } => {
+                for pattern in patterns {
+                    self.declare_pattern(pattern, bound, declare);
+                }
+            },
+            Pattern::EnumPattern { path, patterns, context } => {
+                self.resolve_variant(path, context);
+                for pattern in patterns {
+                    self.declare_pattern(pattern, bound, declare);
+                }
+            },
+            Pattern::OrPattern { patterns, context: _ } => {
+                let outer = bound.clone();
+                for (index, pattern) in patterns.iter().enumerate() {
+                    let mut alternative = outer.clone();
+                    self.declare_pattern(pattern, &mut alternative, declare && index == 0);
+                    if index == 0 {
+                        *bound = alternative;
+                    }
+                }
+            },
+            Pattern::LiteralPattern { .. } | Pattern::RangePattern { .. } | Pattern::BlankPattern => {},
+        }
+    }
+
+    fn declare_binding(&mut self, name: &str, context: &TokenContext, bound: &mut HashMap<String, usize>, declare: bool) {
+        let declaration = self.add_declaration(name, DeclarationKind::Variable, context);
+        if let Some(previous) = bound.insert(name.to_string(), declaration) {
+            self.duplicate(name, previous, context);
+        }
+        // Variables may hide earlier ones of the same scope
+        if declare {
+            self.table.scopes[self.scope].values.insert(name.to_string(), declaration);
+        }
+    }
+
+    fn resolve_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Variable { name, context } => self.resolve_variable(name, context),
+            Expression::PathExpression { segments, context } => self.resolve_variant(segments, context),
+            Expression::StructExpression { struct_, fields, base, context } => {
+                self.resolve_struct(struct_, context);
+                for field in fields {
+                    self.resolve_expression(&field.expression);
+                }
+                if let Some(base) = base {
+                    self.resolve_expression(base);
+                }
+            },
+            Expression::ClosureExpression { parameters, contexts, types, return_type, body, context: _ } => {
+                for type_ in types.iter().chain(return_type) {
+                    self.resolve_type(type_);
+                }
+                self.scope_in();
+                for (parameter, context) in parameters.iter().zip(contexts) {
+                    self.declare(Namespace::Value, parameter, DeclarationKind::Parameter, context);
+                }
+                self.resolve_expression(body);
+                self.scope_out();
+            },
+            Expression::ForExpression { label: _, pattern, iterator, body, context: _ } => {
+                self.resolve_expression(iterator);
+                self.scope_in();
+                self.declare_pattern(pattern, &mut HashMap::new(), true);
+                self.resolve_block(body);
+                self.scope_out();
+            },
+            Expression::MatchExpression { discriminant, branches, context: _ } => {
+                self.resolve_expression(discriminant);
+                for branch in branches {
+                    self.scope_in();
+                    self.declare_pattern(&branch.pattern, &mut HashMap::new(), true);
+                    if let Some(guard) = &branch.guard {
+                        self.resolve_expression(guard);
+                    }
+                    self.resolve_expression(&branch.consequent);
+                    self.scope_out();
+                }
+            },
+            Expression::IfExpression { condition, body, alternate, context: _ } => {
+                self.resolve_expression(condition);
+                self.resolve_block(body);
+                self.resolve_block(alternate);
+            },
+            Expression::WhileExpression { label: _, condition, body, context: _ } => {
+                self.resolve_expression(condition);
+                self.resolve_block(body);
+            },
+            Expression::LoopExpression { label: _, body } | Expression::BlockExpression { body } => self.resolve_block(body),
+            Expression::CallExpression { callee, arguments, context: _ } => {
+                // The method of `x.f()` is found through the type of `x`, so
+                // only `x` is resolved
+                self.resolve_expression(callee);
+                for argument in arguments {
+                    self.resolve_expression(argument);
+                }
+            },
+            Expression::ArrayExpression { type_: _, elements, context: _ } | Expression::TupleExpression { elements } => {
+                for element in elements {
+                    self.resolve_expression(element);
+                }
+            },
+            Expression::ArrayRepeatExpression { value: left, length: right, context: _ }
+            | Expression::IndexExpression { indexed: left, argument: right, context: _ }
+            | Expression::AssignExpression { target: left, op: _, value: right, context: _ }
+            | Expression::BinaryOp { op: _, left, right, context: _ }
+            | Expression::CatchExpression { expression: left, result: right } => {
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            },
+            Expression::TypeCastExpression { value, type_ } => {
+                self.resolve_expression(value);
+                self.resolve_type(type_);
+            },
+            Expression::ReturnExpression { returned } | Expression::BreakExpression { label: _, returned, context: _ } => {
+                if let Some(returned) = returned {
+                    self.resolve_expression(returned);
+                }
+            },
+            Expression::TryExpression { expression: child }
+            | Expression::FieldExpression { left: child, right: _, context: _ }
+            | Expression::UnaryOp { op: _, child, context: _ } => self.resolve_expression(child),
+            Expression::ContinueExpression { .. }
+            | Expression::IntLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::CharLiteral { .. }
+            | Expression::BooleanLiteral { .. } => {},
+        }
+    }
+
+    /// Binds a variable, which may also name a method that is called like a
+    /// function, as in `describe(x)`
+    fn resolve_variable(&mut self, name: &str, context: &TokenContext) {
+        let declaration = self.lookup(Namespace::Value, name).or_else(|| self.methods.get(name).copied());
+        if let Some(declaration) = declaration {
+            self.table.values.insert((context.filename.clone(), context.index), declaration);
+            return;
+        }
+
+        let mut names = self.visible(Namespace::Value, |_| true);
+        names.extend(self.methods.keys().cloned());
+        self.error(SyntaxErrorType::UndefinedVariable(name.to_string()), context);
+        self.errors.suggest_name(name, names.iter().map(String::as_str));
+    }
+
+    fn resolve_variant(&mut self, path: &[PathSegment], context: &TokenContext) {
+        let path = PathSegment::path_as_string(path);
+        if let Some(declaration) = self.variants.get(&path) {
+            self.table.values.insert((context.filename.clone(), context.index), *declaration);
+        }
+    }
+
+    fn resolve_struct(&mut self, name: &str, context: &TokenContext) {
+        match self.lookup(Namespace::Type, name) {
+            Some(declaration) if self.table.declarations[declaration].kind == DeclarationKind::Struct => {
+                self.table.values.insert((context.filename.clone(), context.index), declaration);
+            },
+            _ => {
+                let structs = self.visible(Namespace::Type, |declaration| declaration.kind == DeclarationKind::Struct);
+                self.error(SyntaxErrorType::UndefinedStruct(name.to_string()), context);
+                self.errors.suggest_name(name, structs.iter().map(String::as_str));
+            },
+        }
+    }
+
+    fn resolve_type(&mut self, type_: &Type) {
+        match type_ {
+            Type::Type { name } => self.resolve_type_name(name),
+            Type::GenericType { name, types } => {
+                self.resolve_type_name(name);
+                for type_ in types {
+                    self.resolve_type(type_);
+                }
+            },
+            Type::Tuple { types } => {
+                for type_ in types {
+                    self.resolve_type(type_);
+                }
+            },
+            Type::Function { parameters, return_type } => {
+                for type_ in parameters {
+                    self.resolve_type(type_);
+                }
+                self.resolve_type(return_type);
+            },
+            Type::Array { type_, length: _ } | Type::Pointer { pointed: type_ } => self.resolve_type(type_),
+            Type::Trait { trait_ } => self.resolve_trait(trait_),
+            _ => {},
+        }
+    }
+
+    fn resolve_type_name(&mut self, name: &str) {
+        if self.lookup(Namespace::Type, name).is_none() {
+            let types = self.visible(Namespace::Type, |declaration| declaration.kind != DeclarationKind::Trait);
+            let context = self.context.clone();
+            self.error(SyntaxErrorType::UndefinedType(name.to_string()), &context);
+            self.suggest_elsewhere(name, types.iter().map(String::as_str));
+        }
+    }
+
+    fn resolve_trait(&mut self, trait_: &Trait) {
+        let is_trait = |declaration: &Declaration| match declaration.kind {
+            DeclarationKind::Trait => true,
+            DeclarationKind::Builtin => BUILTIN_TRAITS.contains(&declaration.name.as_str()),
+            _ => false,
+        };
+        let name = trait_.name();
+        if !self.lookup(Namespace::Type, name).is_some_and(|declaration| is_trait(&self.table.declarations[declaration])) {
+            let traits = self.visible(Namespace::Type, is_trait);
+            let context = self.context.clone();
+            self.error(SyntaxErrorType::UndefinedTrait(name.clone()), &context);
+            self.suggest_elsewhere(name, traits.iter().map(String::as_str));
+        }
+        if let Trait::GenericTrait { name: _, types } = trait_ {
+            for type_ in types {
+                self.resolve_type(type_);
+            }
+        }
+    }
+
+    /// Suggests a name for the last error, which is shown at the item or
+    /// statement the name is in rather than at the name itself, so the
+    /// suggestion can't be made for the user
+    fn suggest_elsewhere<'a>(&mut self, name: &str, candidates: impl IntoIterator<Item = &'a str>) {
+        if let Some((closest, _)) = suggestions::closest(name, candidates) {
+            self.errors.suggest(name.to_string(), closest.to_string(), false);
+        }
+    }
+
+    fn error(&mut self, error_type: SyntaxErrorType, context: &TokenContext) {
+        self.errors.errors.push(SyntaxError { error_type, context: context.clone(), suggestion: None });
+    }
+}
+
+/// Checks the types of a program before it runs: function bodies against
+/// their return types, calls against the parameters of what they call,
+/// the operands of operators, the fields of struct literals and the branches
+/// of `if` and `match` against each other. Types are passed down to the
+/// expressions that have to produce them where they are known, so a number
+/// literal takes on the integer or float type it is expected to have. Types
+/// that aren't written down, like those of `let` bindings, closure parameters
+/// and type arguments, start out as type variables that are solved by how
+/// their values are used. Types that can't be told at all are
+/// `Type::Inferred`, which agrees with everything. Runs after `NameResolver`,
+/// whose table tells it what each variable refers to.
+pub struct TypeChecker<'a> {
+    names: &'a NameTable,
+    functions: HashMap<String, FunctionHeader>,
+    /// The type parameters and fields of every struct
+    structs: HashMap<String, (Vec<String>, Vec<StructField>)>,
+    /// The type parameters and variants of every enum
+    enums: HashMap<String, (Vec<String>, Vec<EnumField>)>,
+    /// The methods of every name with the type of the instance they belong
+    /// to. The methods of a trait belong to `Self`, which stands for any type
+    methods: HashMap<String, Vec<(Type, FunctionHeader)>>,
+    /// The types of statics and consts
+    globals: HashMap<String, Type>,
+    /// The types of the variables in scope, innermost last
+    scopes: Vec<HashMap<String, Type>>,
+    /// The type parameters of the item being checked, which stand for any
+    /// type and so only agree with themselves
+    type_parameters: Vec<String>,
+    /// What the function or closure being checked returns
+    return_type: Type,
+    /// The labels of the loops around the current expression, innermost last,
+    /// and the type of the first value each was broken out of with
+    loops: Vec<(Option<String>, Option<Type>)>,
+    /// Whether the expression being checked always leaves through `return`,
+    /// `break`, `continue` or `panic`, so that what follows it never runs
+    diverged: bool,
+    /// The innermost expression, statement or item being checked that has a
+    /// position, where errors of expressions without one are shown
+    context: TokenContext,
+    /// What each type variable of the item being checked has been solved as
+    variables: Vec<Option<Type>>,
+    /// The type each type variable of a number literal is solved as if
+    /// nothing else decides it
+    defaults: Vec<Option<Type>>,
+    /// The variables declared in the item, whose types have to be solved by
+    /// the end of it
+    bindings: Vec<(String, Type, TokenContext)>,
+    /// The type arguments of the generic calls in the item, which have to be
+    /// solved by the end of it too
+    instantiations: Vec<(TokenContext, Vec<(String, Type)>)>,
+    /// The trait bounds the type arguments of those calls have to satisfy
+    bounds: Vec<(TokenContext, Type, Trait)>,
+    /// The traits each type implements through an instance
+    instances: Vec<(String, Type)>,
+    /// Operators whose operands weren't known yet when they were checked
+    deferred: Vec<(Operator, Type, Type, TokenContext)>,
+    /// The number literals of the item as written, with their types
+    literals: Vec<(TokenContext, String, Type)>,
+    literal_types: LiteralTypes,
+    errors: SyntaxErrorCollector,
+}
+
+impl<'a> TypeChecker<'a> {
+    pub fn new(names: &'a NameTable) -> TypeChecker<'a> {
+        TypeChecker {
+            names,
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            methods: HashMap::new(),
+            globals: HashMap::new(),
+            scopes: Vec::new(),
+            type_parameters: Vec::new(),
+            return_type: Type::unit(),
+            loops: Vec::new(),
+            diverged: false,
+            context: TokenContext::new(String::new(), 0, 0, 0),
+            variables: Vec::new(),
+            defaults: Vec::new(),
+            bindings: Vec::new(),
+            instantiations: Vec::new(),
+            bounds: Vec::new(),
+            instances: Vec::new(),
+            deferred: Vec::new(),
+            literals: Vec::new(),
+            literal_types: LiteralTypes::default(),
+            errors: SyntaxErrorCollector::new(),
+        }
+    }
+
+    /// Checks the program, returning the types its number literals were given
+    pub fn check(mut self, items: &[Item]) -> Result<LiteralTypes, SyntaxErrorCollector> {
+        for item in items {
+            self.declare_item(item);
+        }
+        for item in items {
+            self.check_item(item);
+        }
+        if self.errors.is_empty() { Ok(self.literal_types) } else { Err(self.errors) }
+    }
+
+    fn declare_item(&mut self, item: &Item) {
+        match item {
+            Item::Function { header, body: _ } | Item::FunctionDeclaration { header } => {
+                self.functions.insert(header.name.clone(), header.clone());
+            },
+            Item::Struct { name, type_parameters, fields, context: _, public: _, attributes: _ } => {
+                self.structs.insert(name.clone(), (type_parameters.clone(), fields.clone()));
+            },
+            Item::Enum { name, type_parameters, fields, context: _, public: _, attributes: _ } => {
+                self.enums.insert(name.clone(), (type_parameters.clone(), fields.clone()));
+            },
+            Item::Trait { name: _, type_parameters: _, items, context: _, public: _, attributes: _ } => {
+                for item in items {
+                    if let Item::Function { header, body: _ } | Item::FunctionDeclaration { header } = item {
+                        let methods = self.methods.entry(header.name.clone()).or_default();
+                        methods.push((Self::self_type(), header.clone()));
+                    }
+                }
+            },
+            Item::Instance { trait_, type_, items, context: _, attributes: _ } => {
+                for item in items {
+                    if let Item::Function { header, body: _ } = item {
+                        let methods = self.methods.entry(header.name.clone()).or_default();
+                        methods.push((type_.clone(), Self::instance_header(header, type_)));
+                    }
+                }
+                self.instances.push((trait_.name().clone(), type_.clone()));
+            },
+            Item::ConstItem { name, type_, .. } | Item::StaticItem { name, type_, .. } => {
+                self.globals.insert(name.clone(), type_.clone());
+            },
+            _ => {},
+        }
+    }
+
+    fn check_item(&mut self, item: &Item) {
+        match item {
+            Item::Function { header, body } => self.check_function(header, body),
+            Item::Trait { name: _, type_parameters, items, context: _, public: _, attributes: _ } => {
+                self.type_parameters = type_parameters.clone();
+                self.type_parameters.push(String::from("Self"));
+                for item in items {
+                    if let Item::Function { header, body } = item {
+                        self.check_function(header, body);
+                    }
+                }
+                self.type_parameters.clear();
+            },
+            Item::Instance { trait_: _, type_, items, context: _, attributes: _ } => {
+                for item in items {
+                    if let Item::Function { header, body } = item {
+                        self.check_function(&Self::instance_header(header, type_), body);
+                    }
+                }
+            },
+            Item::ConstItem { name: _, type_, value, context, public: _, attributes: _ }
+            | Item::StaticItem { name: _, type_, value, context, public: _, attributes: _ } => {
+                let first = self.errors.errors.len();
+                self.context = context.clone();
+                self.scopes = vec![HashMap::new()];
+                self.check_expression(value, type_);
+                self.solve(first);
+            },
+            _ => {},
+        }
+    }
+
+    /// Checks the body of a function against its return type, with the type
+    /// parameters of the function added to those of the item it is in
+    fn check_function(&mut self, header: &FunctionHeader, body: &Block) {
+        let first = self.errors.errors.len();
+        let outer = self.type_parameters.len();
+        self.type_parameters.extend(header.type_parameters.iter().map(|parameter| parameter.name.clone()));
+        self.context = header.context.clone();
+        self.return_type = header.return_type.clone();
+        let parameters = header.parameters.iter().flatten().cloned().zip(header.types.iter().cloned());
+        self.scopes = vec![parameters.collect()];
+        self.diverged = false;
+        self.check_block(body, &header.return_type);
+        self.solve(first);
+        self.type_parameters.truncate(outer);
+    }
+
+    /// Reports what the item just checked left unsolved: variables whose
+    /// types nothing decided, operators on them and the type arguments of
+    /// calls. Number literals that nothing decided the type of are an `isize`
+    /// or an `f64` first. What a variable is reported for is taken to be
+    /// `Type::Inferred` from then on, so that one unknown type is only
+    /// reported once. The errors of the item from `first` on are then put in
+    /// the order of the source
+    fn solve(&mut self, first: usize) {
+        for id in 0..self.variables.len() {
+            if self.variables[id].is_none() {
+                self.variables[id] = self.defaults[id].clone();
+            }
+        }
+        for (name, type_, context) in std::mem::take(&mut self.bindings) {
+            let type_ = self.resolve(&type_);
+            let unknown = match &type_ {
+                Type::Variable { .. } => true,
+                Type::Array { type_, length: _ } => matches!(**type_, Type::Variable { .. }),
+                _ => false,
+            };
+            if unknown {
+                self.forget(&type_);
+                self.error_at(SyntaxErrorType::TypeAnnotationsNeeded(name), context);
+            }
+        }
+        for (op, left, right, context) in std::mem::take(&mut self.deferred) {
+            let (left, right) = (self.resolve(&left), self.resolve(&right));
+            if !self.is_unknown(&left) && !self.is_unknown(&right) && !self.valid_operands(op, &left, &right) {
+                self.error_at(Self::invalid_operands(op, left, right), context);
+            }
+        }
+        for (context, arguments) in std::mem::take(&mut self.instantiations) {
+            let unsolved = arguments.into_iter().find(|(_, type_)| matches!(self.resolve(type_), Type::Variable { .. }));
+            if let Some((name, _)) = unsolved {
+                self.error_at(SyntaxErrorType::UninferredTypeParameter(name), context);
+            }
+        }
+        for (context, type_, trait_) in std::mem::take(&mut self.bounds) {
+            let type_ = self.resolve(&type_);
+            if self.is_concrete(&type_) && !implements(&self.instances, &type_, &trait_) {
+                self.error_at(SyntaxErrorType::UnsatisfiedTraitBound { type_, trait_ }, context);
+            }
+        }
+        for (context, literal, type_) in std::mem::take(&mut self.literals) {
+            let type_ = self.resolve(&type_);
+            if !Self::fits(&literal, &type_) {
+                self.error_at(SyntaxErrorType::LiteralOutOfRange { literal, type_: type_.clone() }, context.clone());
+            }
+            if type_.is_numeric() {
+                self.literal_types.types.insert((context.filename, context.index), type_);
+            }
+        }
+        self.variables.clear();
+        self.defaults.clear();
+        self.errors.errors[first..].sort_by_key(|error| (error.context.line, error.context.column));
+    }
+
+    /// Whether the number literal `literal` can be a value of type `type_`
+    fn fits(literal: &str, type_: &Type) -> bool {
+        match type_.integer_range() {
+            Some((min, max)) => literal.parse::<i128>().is_ok_and(|value| min <= value && value <= max),
+            None if *type_ == Type::Float32 => literal.parse::<f64>().is_ok_and(|value| (value as f32).is_finite()),
+            None => true,
+        }
+    }
+
+    /// The type of a block, whose value is its last expression unless it
+    /// always leaves early, in which case it can be used as any type
+    fn check_block(&mut self, block: &Block, expected: &Type) -> Type {
+        let outer = std::mem::replace(&mut self.diverged, false);
+        let context = self.context.clone();
+        self.scopes.push(HashMap::new());
+        for statement in &block.statements {
+            match statement {
+                Statement::LetStatement { pattern, type_, type_context: _, expression, context: statement_context }
+                | Statement::ConstStatement { pattern, type_, type_context: _, expression, context: statement_context } => {
+                    self.context = statement_context.clone();
+                    let type_ = match type_ {
+                        Some(type_) => {
+                            self.check_expression(expression, type_);
+                            type_.clone()
+                        },
+                        None => self.check_expression(expression, &Type::Inferred),
+                    };
+                    self.bind_pattern(pattern, &type_);
+                    self.context = context.clone();
+                },
+                Statement::ExpressionStatement { expression } => {
+                    self.check_expression(expression, &Type::Inferred);
+                },
+                // A deferred block sees the variables declared before it,
+                // can't leave the loops it is run from, and its value is
+                // thrown away
+                Statement::DeferStatement { body, context: _ } => {
+                    let (loops, diverged) = (std::mem::take(&mut self.loops), self.diverged);
+                    self.check_block(body, &Type::Inferred);
+                    (self.loops, self.diverged) = (loops, diverged);
+                },
+            }
+        }
+
+        let type_ = match block.expression.as_ref() {
+            // The `()` a block without a final expression ends with
+            Expression::TupleExpression { elements } if elements.is_empty() && self.diverged => Type::Inferred,
+            expression => self.check_expression(expression, expected),
+        };
+        self.scopes.pop();
+        self.diverged = outer || self.diverged;
+        type_
+    }
+
+    /// The type of `expression`, reporting it if it doesn't fit `expected`
+    fn check_expression(&mut self, expression: &Expression, expected: &Type) -> Type {
+        let outer = expression.context().map(|context| std::mem::replace(&mut self.context, context.clone()));
+        let type_ = self.expression_type(expression, expected);
+        if let Some(outer) = outer {
+            self.context = outer;
+        }
+        self.resolve(&type_)
+    }
+
+    fn synthesize(&mut self, expression: &Expression) -> Type {
+        self.check_expression(expression, &Type::Inferred)
+    }
+
+    fn expression_type(&mut self, expression: &Expression, expected: &Type) -> Type {
+        let expected = &self.resolve(expected);
+        let found = match expression {
+            // Number literals take on the type they are expected to have, or
+            // the type of what they end up being used as if nothing is
+            // expected of them yet. Integer literals can be floats too
+            Expression::IntLiteral { value, context } => {
+                let type_ = match expected {
+                    expected if expected.is_numeric() => expected.clone(),
+                    Type::Inferred | Type::Variable { .. } => self.fresh_number(Type::IntSize),
+                    _ => Type::IntSize,
+                };
+                self.literals.push((context.clone(), value.to_string(), type_.clone()));
+                type_
+            },
+            Expression::FloatLiteral { value, context } => {
+                let type_ = match expected {
+                    expected if expected.is_float() => expected.clone(),
+                    Type::Inferred | Type::Variable { .. } => self.fresh_number(Type::Float64),
+                    _ => Type::Float64,
+                };
+                self.literals.push((context.clone(), value.to_string(), type_.clone()));
+                type_
+            },
+            Expression::StringLiteral { .. } => Type::Type { name: String::from("str") },
+            Expression::CharLiteral { .. } => Type::Char,
+            Expression::BooleanLiteral { .. } => Type::Boolean,
+            Expression::TupleExpression { elements } => match expected {
+                Type::Tuple { types } if types.len() == elements.len() => {
+                    let types = elements.iter().zip(types).map(|(element, type_)| self.check_expression(element, type_)).collect();
+                    return Type::Tuple { types };
+                },
+                _ => Type::Tuple { types: elements.iter().map(|element| self.synthesize(element)).collect() },
+            },
+            Expression::ArrayExpression { type_: _, elements, context: _ } => {
+                // Every element has to have the type of the first one
+                let mut element_type = match expected {
+                    Type::Array { type_, length: _ } => (**type_).clone(),
+                    _ => Type::Inferred,
+                };
+                for element in elements {
+                    let found = self.check_expression(element, &element_type);
+                    if element_type == Type::Inferred {
+                        element_type = found;
+                    }
+                }
+                // The elements of an empty array have whatever type it is
+                // used as having
+                if elements.is_empty() && element_type == Type::Inferred {
+                    element_type = self.fresh();
+                }
+                Type::Array { type_: Box::new(element_type), length: elements.len() }
+            },
+            Expression::ArrayRepeatExpression { value, length, context: _ } => {
+                let element_type = match expected {
+                    Type::Array { type_, length: _ } => (**type_).clone(),
+                    _ => Type::Inferred,
+                };
+                let element_type = self.check_expression(value, &element_type);
+                let length_type = self.synthesize(length);
+                self.check_index(length_type);
+                match length.as_ref() {
+                    Expression::IntLiteral { value, context: _ } if usize::try_from(*value).is_ok() => {
+                        Type::Array { type_: Box::new(element_type), length: *value as usize }
+                    },
+                    // The length is only known once the program runs
+                    _ => Type::Inferred,
+                }
+            },
+            Expression::IndexExpression { indexed, argument, context: _ } => {
+                let indexed = self.synthesize(indexed);
+                let index = self.synthesize(argument);
+                self.check_index(index);
+                match self.describe(&indexed) {
+                    Type::Array { type_, length: _ } => *type_,
+                    Type::Inferred | Type::Variable { .. } => Type::Inferred,
+                    indexed => {
+                        self.error(SyntaxErrorType::NotIndexable(indexed));
+                        Type::Inferred
+                    },
+                }
+            },
+            Expression::PathExpression { segments, context: _ } => self.check_variant(segments, &[], expected),
+            Expression::CallExpression { callee, arguments, context: _ } => self.check_call(callee, arguments, expected),
+            Expression::StructExpression { struct_, fields, base, context: _ } => {
+                self.check_struct(struct_, fields, base, expected)
+            },
+            Expression::AssignExpression { target, op, value, context: _ } => {
+                let target = self.synthesize(target);
+                match op {
+                    Some(op) => {
+                        let value = self.check_operand(value, &target);
+                        let result = self.operator_type(*op, target.clone(), value);
+                        self.expect(&result, &target);
+                    },
+                    None => {
+                        self.check_expression(value, &target);
+                    },
+                }
+                Type::unit()
+            },
+            Expression::Variable { name, context } => self.variable_type(name, context),
+            Expression::ClosureExpression { parameters, contexts: _, types, return_type, body, context } => {
+                // The types left out are solved from what the closure is
+                // expected to be and from its body
+                let types: Vec<Type> = types.iter()
+                    .map(|type_| if *type_ == Type::Inferred { self.fresh() } else { type_.clone() })
+                    .collect();
+                let declared = return_type.clone().unwrap_or_else(|| self.fresh());
+                let closure = Type::Function { parameters: types.clone(), return_type: Box::new(declared.clone()) };
+                self.unify(&closure, expected);
+
+                let return_type = std::mem::replace(&mut self.return_type, declared.clone());
+                let loops = std::mem::take(&mut self.loops);
+                let diverged = self.diverged;
+                self.scopes.push(HashMap::new());
+                for (parameter, type_) in parameters.iter().zip(&types) {
+                    self.declare(parameter, type_, context);
+                }
+                self.check_expression(body, &declared);
+                self.scopes.pop();
+                self.diverged = diverged;
+                self.loops = loops;
+                self.return_type = return_type;
+                closure
+            },
+            Expression::BlockExpression { body } => return self.check_block(body, expected),
+            Expression::IfExpression { condition, body, alternate, context: _ } => {
+                return self.check_if(condition, body, alternate, expected);
+            },
+            Expression::MatchExpression { discriminant, branches, context: _ } => {
+                return self.check_match(discriminant, branches, expected);
+            },
+            Expression::WhileExpression { label, condition, body, context: _ } => {
+                self.check_expression(condition, &Type::Boolean);
+                let diverged = self.diverged;
+                self.check_loop_body(label, body);
+                self.diverged = diverged;
+                Type::unit()
+            },
+            Expression::ForExpression { label, pattern, iterator, body, context: _ } => {
+                let iterator = self.synthesize(iterator);
+                let element_type = match self.describe(&iterator) {
+                    Type::Array { type_, length: _ } => *type_,
+                    Type::Inferred | Type::Variable { .. } => Type::Inferred,
+                    iterator => {
+                        self.error(SyntaxErrorType::NotIterable(iterator));
+                        Type::Inferred
+                    },
+                };
+                let diverged = self.diverged;
+                self.scopes.push(HashMap::new());
+                self.bind_pattern(pattern, &element_type);
+                self.check_loop_body(label, body);
+                self.scopes.pop();
+                self.diverged = diverged;
+                Type::unit()
+            },
+            // A `loop` that is never broken out of never produces a value
+            Expression::LoopExpression { label, body } => {
+                let diverged = self.diverged;
+                match self.check_loop_body(label, body) {
+                    Some(type_) => {
+                        self.diverged = diverged;
+                        type_
+                    },
+                    None => {
+                        self.diverged = true;
+                        Type::Inferred
+                    },
+                }
+            },
+            Expression::ReturnExpression { returned } => {
+                let return_type = self.return_type.clone();
+                match returned {
+                    Some(returned) => {
+                        self.check_expression(returned, &return_type);
+                    },
+                    None => self.expect(&Type::unit(), &return_type),
+                }
+                self.diverged = true;
+                Type::Inferred
+            },
+            Expression::BreakExpression { label, returned, context: _ } => {
+                let target = self.loops.iter().rposition(|(name, _)| label.is_none() || name == label);
+                let broken = target.and_then(|target| self.loops[target].1.clone()).unwrap_or(Type::Inferred);
+                let found = match returned {
+                    Some(returned) => self.check_expression(returned, &broken),
+                    None => {
+                        self.expect(&Type::unit(), &broken);
+                        Type::unit()
+                    },
+                };
+                if let Some(target) = target {
+                    if broken == Type::Inferred {
+                        self.loops[target].1 = Some(found);
+                    }
+                }
+                self.diverged = true;
+                Type::Inferred
+            },
+            Expression::ContinueExpression { .. } => {
+                self.diverged = true;
+                Type::Inferred
+            },
+            Expression::FieldExpression { left, right, context: _ } => {
+                let left = self.synthesize(left);
+                self.field_type(left, right)
+            },
+            // The right operand of `&&` and `||` may not run at all
+            Expression::BinaryOp { op: Operator::AndOperator | Operator::OrOperator, left, right, context: _ } => {
+                self.check_expression(left, &Type::Boolean);
+                let diverged = self.diverged;
+                self.check_expression(right, &Type::Boolean);
+                self.diverged = diverged;
+                Type::Boolean
+            },
+            Expression::BinaryOp { op, left, right, context: _ } => {
+                let shift = matches!(op, Operator::LeftShiftOperator | Operator::RightShiftOperator);
+                let comparison = Self::is_comparison(*op);
+
+                // A number literal takes the type of the other operand, so
+                // that one is checked first. The result of arithmetic has the
+                // type of its operands, which a literal can take from the
+                // expected type too
+                let (left, right) = if shift {
+                    let left = self.check_operand(left, expected);
+                    (left, self.synthesize(right))
+                } else if Self::is_number(left) && !Self::is_number(right) {
+                    let right = self.synthesize(right);
+                    (self.check_operand(left, &right), right)
+                } else {
+                    let left = self.check_operand(left, if comparison { &Type::Inferred } else { expected });
+                    (left.clone(), self.check_operand(right, &left))
+                };
+                self.operator_type(*op, left, right)
+            },
+            Expression::UnaryOp { op, child, context: _ } => {
+                let child_type = match op {
+                    Operator::NegateOperator => self.check_operand(child, expected),
+                    _ => self.synthesize(child),
+                };
+                // A negative literal has to fit with its sign
+                if let (Operator::NegateOperator, Expression::IntLiteral { .. } | Expression::FloatLiteral { .. }) = (op, child.as_ref()) {
+                    if let Some((_, literal, _)) = self.literals.last_mut() {
+                        literal.insert(0, '-');
+                    }
+                }
+                self.check_operands(*op, child_type.clone(), child_type.clone());
+                child_type
+            },
+            // These have no syntax yet, so no program contains them
+            Expression::TryExpression { expression: child } | Expression::TypeCastExpression { value: child, type_: _ } => {
+                self.synthesize(child);
+                Type::Inferred
+            },
+            Expression::CatchExpression { expression, result } => {
+                self.synthesize(expression);
+                self.synthesize(result);
+                Type::Inferred
+            },
+        };
+        self.expect(&found, expected);
+        found
+    }
+
+    fn check_if(&mut self, condition: &Expression, body: &Block, alternate: &Block, expected: &Type) -> Type {
+        self.check_expression(condition, &Type::Boolean);
+        let diverged = self.diverged;
+        let body_type = self.check_block(body, expected);
+        let body_diverged = std::mem::replace(&mut self.diverged, diverged);
+
+        // Without an expected type, the branches have to agree with each other
+        let expected = if *expected == Type::Inferred { body_type.clone() } else { expected.clone() };
+        let alternate_type = self.check_block(alternate, &expected);
+        self.diverged = self.diverged && body_diverged || diverged;
+        if body_type == Type::Inferred { alternate_type } else { body_type }
+    }
+
+    fn check_match(&mut self, discriminant: &Expression, branches: &[MatchBranch], expected: &Type) -> Type {
+        let discriminant = self.synthesize(discriminant);
+        let diverged = self.diverged;
+        let mut always_diverges = !branches.is_empty();
+
+        // Without an expected type, every arm has to agree with the first one
+        // that produces a value
+        let mut expected = expected.clone();
+        for branch in branches {
+            let outer = std::mem::replace(&mut self.context, branch.context.clone());
+            self.scopes.push(HashMap::new());
+            self.bind_pattern(&branch.pattern, &discriminant);
+            if let Some(guard) = &branch.guard {
+                self.check_expression(guard, &Type::Boolean);
+            }
+            self.diverged = false;
+            let found = self.check_expression(&branch.consequent, &expected);
+            always_diverges &= self.diverged;
+            if expected == Type::Inferred {
+                expected = found;
+            }
+            self.scopes.pop();
+            self.context = outer;
+        }
+        self.diverged = diverged || always_diverges;
+        expected
+    }
+
+    /// Checks the body of a loop, returning the type of the value it is
+    /// first broken out of with, if it is broken out of at all
+    fn check_loop_body(&mut self, label: &Option<String>, body: &Block) -> Option<Type> {
+        self.loops.push((label.clone(), None));
+        self.check_block(body, &Type::Inferred);
+        self.loops.pop().and_then(|(_, broken)| broken)
+    }
+
+    /// Indices and array lengths can be of any integer type
+    fn check_index(&mut self, found: Type) {
+        let found = self.describe(&found);
+        if !found.is_integer() && !self.is_unknown(&found) && !self.is_type_parameter(&found) {
+            self.error(SyntaxErrorType::TypeMismatch { expected: Type::UIntSize, found });
+        }
+    }
+
+    /// The type of an operand, which is that of the other operand if it is a
+    /// number literal of the same kind
+    fn check_operand(&mut self, operand: &Expression, other: &Type) -> Type {
+        let expected = if Self::adapts(operand, other) { other.clone() } else { Type::Inferred };
+        self.check_expression(operand, &expected)
+    }
+
+    fn is_comparison(op: Operator) -> bool {
+        matches!(op,
+            Operator::EqualOperator | Operator::NotEqualOperator
+            | Operator::LessThanOperator | Operator::GreaterThanOperator
+            | Operator::LessEqualOperator | Operator::GreaterEqualOperator)
+    }
+
+    /// Whether `expression` is made of number literals, like `-1`, `2 * 3`
+    /// or `[1, 2]`
+    fn is_number(expression: &Expression) -> bool {
+        Self::adapts(expression, &Type::Inferred)
+    }
+
+    /// Whether `expression` is made of number literals that can have the type
+    /// `type_`, or can have some type if it is `Type::Inferred`
+    fn adapts(expression: &Expression, type_: &Type) -> bool {
+        match (expression, type_) {
+            (Expression::IntLiteral { .. }, _) => type_.is_numeric() || *type_ == Type::Inferred,
+            (Expression::FloatLiteral { .. }, _) => type_.is_float() || *type_ == Type::Inferred,
+            (Expression::UnaryOp { op: Operator::NegateOperator, child, context: _ }, _) => Self::adapts(child, type_),
+            (Expression::BinaryOp { op: Operator::LeftShiftOperator | Operator::RightShiftOperator, left, right: _, context: _ }, _) => {
+                Self::adapts(left, type_)
+            },
+            (Expression::BinaryOp { op, left, right, context: _ }, _) if !Self::is_comparison(*op) => {
+                Self::adapts(left, type_) && Self::adapts(right, type_)
+            },
+            (Expression::ArrayExpression { type_: _, elements, context: _ }, Type::Array { type_, length: _ }) => {
+                !elements.is_empty() && elements.iter().all(|element| Self::adapts(element, type_))
+            },
+            (Expression::ArrayExpression { type_: _, elements, context: _ }, Type::Inferred) => {
+                !elements.is_empty() && elements.iter().all(Self::is_number)
+            },
+            (Expression::ArrayRepeatExpression { value, length: _, context: _ }, Type::Array { type_, length: _ }) => {
+                Self::adapts(value, type_)
+            },
+            (Expression::ArrayRepeatExpression { value, length: _, context: _ }, Type::Inferred) => Self::is_number(value),
+            (Expression::TupleExpression { elements }, Type::Tuple { types }) => {
+                !elements.is_empty() && elements.len() == types.len()
+                    && elements.iter().zip(types).all(|(element, type_)| Self::adapts(element, type_))
+            },
+            (Expression::TupleExpression { elements }, Type::Inferred) => {
+                !elements.is_empty() && elements.iter().all(Self::is_number)
+            },
+            _ => false,
+        }
+    }
+
+    /// The type of `left op right`, reporting operands the operator doesn't
+    /// work on. Both operands have to have the same type, except for the
+    /// amount of a shift
+    fn operator_type(&mut self, op: Operator, left: Type, right: Type) -> Type {
+        let shift = matches!(op, Operator::LeftShiftOperator | Operator::RightShiftOperator);
+        if !shift && !self.unify(&right, &left) {
+            self.error(SyntaxErrorType::InvalidOperands(self.describe(&left), self.describe(&right)));
+            return if Self::is_comparison(op) { Type::Boolean } else { left };
+        }
+        let (left, right) = (self.resolve(&left), self.resolve(&right));
+        self.check_operands(op, left.clone(), right.clone());
+        match (Self::is_comparison(op), &left) {
+            (true, _) => Type::Boolean,
+            (false, Type::Inferred) => right,
+            (false, _) => left,
+        }
+    }
+
+    /// Reports operands the operator doesn't work on, or leaves them to be
+    /// checked at the end of the item if their types aren't known yet. The
+    /// operand of a unary operator is passed as both operands
+    fn check_operands(&mut self, op: Operator, left: Type, right: Type) {
+        if left == Type::Inferred || right == Type::Inferred {
+            return;
+        }
+        if self.is_unknown(&left) || self.is_unknown(&right) {
+            self.deferred.push((op, left, right, self.context.clone()));
+        } else if !self.valid_operands(op, &left, &right) {
+            self.error(Self::invalid_operands(op, left, right));
+        }
+    }
+
+    /// The error for operands the operator doesn't work on, naming the
+    /// operand of a unary operator only once
+    fn invalid_operands(op: Operator, left: Type, right: Type) -> SyntaxErrorType {
+        match op {
+            Operator::NegateOperator | Operator::BitwiseNotOperator | Operator::NotOperator => {
+                SyntaxErrorType::InvalidOperand(left)
+            },
+            _ => SyntaxErrorType::InvalidOperands(left, right),
+        }
+    }
+
+    fn valid_operands(&self, op: Operator, left: &Type, right: &Type) -> bool {
+        use Operator::*;
+
+        // Type parameters may stand for any type, so any operator is allowed
+        // on them until the program runs
+        let any = self.is_type_parameter(left);
+        let string = Type::Type { name: String::from("str") };
+        match op {
+            EqualOperator | NotEqualOperator => right.conforms_to(left),
+            LeftShiftOperator | RightShiftOperator => {
+                (left.is_integer() || any) && (right.is_integer() || self.is_type_parameter(right))
+            },
+            _ if !right.conforms_to(left) => false,
+            AddOperator => left.is_numeric() || *left == string || any,
+            SubtractOperator | MultiplyOperator | DivideOperator | ModuloOperator | ExponentOperator => left.is_numeric() || any,
+            BitwiseAndOperator | BitwiseOrOperator | BitwiseXorOperator => left.is_integer() || any,
+            LessThanOperator | GreaterThanOperator | LessEqualOperator | GreaterEqualOperator => {
+                left.is_numeric() || left == &Type::Char || *left == string || any
+            },
+            NegateOperator => left.is_numeric() || any,
+            BitwiseNotOperator => left.is_integer() || any,
+            NotOperator => *left == Type::Boolean || any,
+            _ => false,
+        }
+    }
+
+    /// The type of a variable, or of the function, static or const it names.
+    /// Methods and builtins have no type of their own
+    fn variable_type(&mut self, name: &str, context: &TokenContext) -> Type {
+        match self.names.value(context).map(|declaration| declaration.kind) {
+            Some(DeclarationKind::Function) => match self.functions.get(name).cloned() {
+                Some(header) => self.function_type(&header),
+                None => Type::Inferred,
+            },
+            Some(DeclarationKind::Static | DeclarationKind::Const) => self.globals.get(name).cloned().unwrap_or(Type::Inferred),
+            Some(DeclarationKind::Variable | DeclarationKind::Parameter) => self.local(name).cloned().unwrap_or(Type::Inferred),
+            _ => Type::Inferred,
+        }
+    }
+
+    fn local(&self, name: &str) -> Option<&Type> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// The type of a function used as a value, whose type parameters are
+    /// solved by how the value is used
+    fn function_type(&mut self, header: &FunctionHeader) -> Type {
+        let bindings = self.instantiate(&Self::parameter_names(header));
+        let type_ = Type::Function { parameters: header.types.clone(), return_type: Box::new(header.return_type.clone()) };
+        type_.substitute(&bindings)
+    }
+
+    /// Checks a call the way the interpreter makes it: `x.f(y)` calls the
+    /// variable or function `f` if there is one and otherwise the method `f`
+    /// for the type of `x`, while `f(x)` calls what `f` was resolved to
+    fn check_call(&mut self, callee: &Expression, arguments: &[Expression], expected: &Type) -> Type {
+        match callee {
+            Expression::FieldExpression { left, right, context } if right.parse::<usize>().is_err() => {
+                let receiver = self.synthesize(left);
+                let outer = std::mem::replace(&mut self.context, context.clone());
+                let type_ = if let Some(callee) = self.local(right).or_else(|| self.globals.get(right)).cloned() {
+                    self.call_value(callee, Some(receiver), arguments)
+                } else if let Some(header) = self.functions.get(right).cloned() {
+                    self.call_function(&header, Some(receiver), arguments, expected)
+                } else {
+                    self.call_method(right, receiver, arguments, expected)
+                };
+                self.context = outer;
+                type_
+            },
+            Expression::Variable { name, context } => match self.names.value(context).map(|declaration| declaration.kind) {
+                Some(DeclarationKind::Function) => match self.functions.get(name).cloned() {
+                    Some(header) => self.call_function(&header, None, arguments, expected),
+                    None => self.synthesize_all(arguments),
+                },
+                Some(DeclarationKind::Method | DeclarationKind::Builtin) => match arguments.split_first() {
+                    Some((receiver, arguments)) => {
+                        let receiver = self.synthesize(receiver);
+                        self.call_method(name, receiver, arguments, expected)
+                    },
+                    None => self.call_builtin(name, Vec::new()),
+                },
+                _ => {
+                    let callee = self.variable_type(name, context);
+                    self.call_value(callee, None, arguments)
+                },
+            },
+            Expression::PathExpression { segments, context: _ } => self.check_variant(segments, arguments, expected),
+            _ => {
+                let callee = self.synthesize(callee);
+                self.call_value(callee, None, arguments)
+            },
+        }
+    }
+
+    fn call_value(&mut self, callee: Type, receiver: Option<Type>, arguments: &[Expression]) -> Type {
+        // The types of number literals in a closure are left open, so that
+        // the closure can still be called with other number types
+        match self.resolve(&callee) {
+            Type::Function { parameters, return_type } => {
+                self.check_arguments(&parameters, &return_type, receiver, arguments, &Type::Inferred)
+            },
+            // A value whose type isn't known yet, like a closure parameter,
+            // is a function of as many parameters as it is called with
+            Type::Variable { .. } => {
+                let count = arguments.len() + usize::from(receiver.is_some());
+                let parameters: Vec<Type> = (0..count).map(|_| self.fresh()).collect();
+                let return_type = self.fresh();
+                self.unify(&Type::Function { parameters: parameters.clone(), return_type: Box::new(return_type.clone()) }, &callee);
+                self.check_arguments(&parameters, &return_type, receiver, arguments, &Type::Inferred)
+            },
+            Type::Inferred => self.synthesize_all(arguments),
+            _ => {
+                self.error(SyntaxErrorType::NotCallable(self.describe(&callee)));
+                self.synthesize_all(arguments)
+            },
+        }
+    }
+
+    /// Calls a function with fresh type variables for its type parameters,
+    /// which have to be solved by the end of the item
+    fn call_function(&mut self, header: &FunctionHeader, receiver: Option<Type>, arguments: &[Expression], expected: &Type) -> Type {
+        let parameters = Self::parameter_names(header);
+        let bindings = self.instantiate(&parameters);
+        let types: Vec<Type> = header.types.iter().map(|type_| type_.substitute(&bindings)).collect();
+        let return_type = header.return_type.substitute(&bindings);
+        let arguments_ = parameters.into_iter().map(|parameter| {
+            let type_ = bindings[&parameter].clone();
+            (parameter, type_)
+        });
+        self.instantiations.push((self.context.clone(), arguments_.collect()));
+        for parameter in &header.type_parameters {
+            for bound in &parameter.bounds {
+                self.bounds.push((self.context.clone(), bindings[&parameter.name].clone(), bound.clone()));
+            }
+        }
+        self.check_arguments(&types, &return_type, receiver, arguments, expected)
+    }
+
+    /// Calls the method `name` for the type of `receiver`, or else the
+    /// builtin of that name
+    fn call_method(&mut self, name: &str, receiver: Type, arguments: &[Expression], expected: &Type) -> Type {
+        if let Some(header) = self.find_method(name, &receiver) {
+            return self.call_function(&header, Some(receiver), arguments, expected);
+        }
+        let mut types = vec![receiver];
+        types.extend(arguments.iter().map(|argument| self.synthesize(argument)));
+        self.call_builtin(name, types)
+    }
+
+    fn call_builtin(&mut self, name: &str, types: Vec<Type>) -> Type {
+        match name {
+            "print" | "println" => Type::unit(),
+            "panic" => {
+                self.diverged = true;
+                Type::Inferred
+            },
+            "unwrap" => match types.as_slice() {
+                [type_] => self.payload(type_, "Some").and_then(|payload| payload.into_iter().next()).unwrap_or(Type::Inferred),
+                _ => {
+                    self.error(SyntaxErrorType::ArgumentCountMismatch { expected: 1, found: types.len() });
+                    Type::Inferred
+                },
+            },
+            // Calls of methods the type doesn't have are left to the
+            // interpreter
+            _ => Type::Inferred,
+        }
+    }
+
+    /// The method `name` of the instance for the type of `receiver`, with
+    /// `Self` replaced by that type. The values of type parameters only have
+    /// the methods of their traits
+    fn find_method(&self, name: &str, receiver: &Type) -> Option<FunctionHeader> {
+        let receiver = &self.describe(receiver);
+        let methods = self.methods.get(name)?;
+        let instance = methods.iter().find(|(type_, _)| *type_ != Self::self_type() && receiver.conforms_to(type_));
+        let trait_ = methods.iter().find(|(type_, _)| *type_ == Self::self_type());
+        let (_, header) = if self.is_unknown(receiver) || self.is_type_parameter(receiver) {
+            trait_.or(instance)?
+        } else {
+            instance.or(trait_)?
+        };
+        Some(Self::instance_header(header, receiver))
+    }
+
+    /// Checks the arguments of a call against the types of the parameters,
+    /// solving the type variables in them along the way, and returns what
+    /// the call returns. A method call has its receiver checked already
+    fn check_arguments(&mut self, types: &[Type], return_type: &Type, receiver: Option<Type>, arguments: &[Expression], expected: &Type) -> Type {
+        let found = arguments.len() + usize::from(receiver.is_some());
+        if found != types.len() {
+            self.error(SyntaxErrorType::ArgumentCountMismatch { expected: types.len(), found });
+            self.synthesize_all(arguments);
+            return return_type.clone();
+        }
+
+        self.unify(return_type, expected);
+        let mut types = types.iter();
+        if let Some(receiver) = receiver {
+            if let Some(type_) = types.next() {
+                self.expect(&receiver, type_);
+            }
+        }
+        let arguments: Vec<(&Expression, &Type)> = arguments.iter().zip(types).collect();
+        self.check_bound_values(&arguments);
+        return_type.clone()
+    }
+
+    /// Checks values against the types they are given to, which may have
+    /// type variables in them. Values other than number literals solve the
+    /// variables first, so that literals can take on the types they are
+    /// solved as
+    fn check_bound_values(&mut self, values: &[(&Expression, &Type)]) {
+        let mut numbers = Vec::new();
+        for (value, type_) in values {
+            if Self::is_number(value) && !self.variables_in(type_).is_empty() {
+                numbers.push((value, type_));
+            } else {
+                self.check_expression(value, type_);
+            }
+        }
+        for (value, type_) in numbers {
+            self.check_expression(value, type_);
+        }
+    }
+
+    fn synthesize_all(&mut self, expressions: &[Expression]) -> Type {
+        for expression in expressions {
+            self.synthesize(expression);
+        }
+        Type::Inferred
+    }
+
+    /// Checks the payload of a variant such as `Shape::Circle(1.0)` and
+    /// returns the type of its enum
+    fn check_variant(&mut self, path: &[PathSegment], arguments: &[Expression], expected: &Type) -> Type {
+        let variant = PathSegment::variant(path).and_then(|(enum_, variant)| {
+            let (parameters, fields) = self.enums.get(enum_)?;
+            let field = fields.iter().find(|field| field.name == *variant)?;
+            Some((parameters.clone(), field.types.clone(), Self::generic_type(enum_, parameters)))
+        });
+        match variant {
+            Some((parameters, types, type_)) => {
+                let bindings = self.instantiate(&parameters);
+                let types: Vec<Type> = types.iter().map(|type_| type_.substitute(&bindings)).collect();
+                self.check_arguments(&types, &type_.substitute(&bindings), None, arguments, expected)
+            },
+            None => self.synthesize_all(arguments),
+        }
+    }
+
+    fn check_struct(&mut self, name: &str, fields: &[StructExpressionField], base: &Option<Box<Expression>>, expected: &Type) -> Type {
+        let (parameters, declared) = match self.structs.get(name) {
+            Some(struct_) => struct_.clone(),
+            None => {
+                for field in fields {
+                    self.synthesize(&field.expression);
+                }
+                return Type::Inferred;
+            },
+        };
+        let bindings = self.instantiate(&parameters);
+        let type_ = Self::generic_type(name, &parameters).substitute(&bindings);
+        self.unify(&type_, expected);
+
+        let mut values = Vec::new();
+        let mut given = HashSet::new();
+        for field in fields {
+            if !given.insert(field.name.as_str()) {
+                self.error(SyntaxErrorType::DuplicateField(field.name.clone()));
+            }
+            match declared.iter().find(|declared| declared.name == field.name) {
+                Some(declared) => values.push((&field.expression, declared.type_.substitute(&bindings))),
+                None => {
+                    self.error(SyntaxErrorType::NoSuchField(Type::Type { name: name.to_string() }, field.name.clone()));
+                    self.errors.suggest_name(&field.name, declared.iter().map(|declared| declared.name.as_str()));
+                    self.synthesize(&field.expression);
+                },
+            }
+        }
+        let values: Vec<(&Expression, &Type)> = values.iter().map(|(value, type_)| (*value, type_)).collect();
+        self.check_bound_values(&values);
+        // Fields that aren't given are copied from the base, if there is one
+        match base {
+            Some(base) => {
+                self.check_expression(base, &type_);
+            },
+            None => {
+                for field in declared.iter().filter(|field| !given.contains(field.name.as_str())) {
+                    self.error(SyntaxErrorType::MissingField { struct_: name.to_string(), field: field.name.clone() });
+                }
+            },
+        }
+        type_
+    }
+
+    /// The type of the field `name` of a value of type `type_`
+    fn field_type(&mut self, type_: Type, name: &str) -> Type {
+        let type_ = self.describe(&type_);
+        let struct_ = match &type_ {
+            Type::Inferred | Type::Variable { .. } => return Type::Inferred,
+            Type::Tuple { types } => {
+                if let Some(type_) = name.parse::<usize>().ok().and_then(|index| types.get(index)) {
+                    return type_.clone();
+                }
+                None
+            },
+            Type::Type { name } | Type::GenericType { name, types: _ } => self.structs.get(name),
+            _ => None,
+        };
+        let (parameters, fields) = match struct_ {
+            Some(struct_) => struct_.clone(),
+            None if self.is_type_parameter(&type_) => return Type::Inferred,
+            None => {
+                self.error(SyntaxErrorType::NoSuchField(type_, name.to_string()));
+                return Type::Inferred;
+            },
+        };
+        match fields.iter().find(|field| field.name == name) {
+            Some(field) => field.type_.substitute(&Self::type_arguments(&parameters, &type_)),
+            None => {
+                self.error(SyntaxErrorType::NoSuchField(type_, name.to_string()));
+                self.errors.suggest_name(name, fields.iter().map(|field| field.name.as_str()));
+                Type::Inferred
+            },
+        }
+    }
+
+    /// The types of the payload of `variant` for a value of the enum type
+    /// `type_`
+    fn payload(&self, type_: &Type, variant: &str) -> Option<Vec<Type>> {
+        let enum_ = match type_ {
+            Type::Type { name } | Type::GenericType { name, types: _ } => name,
+            _ => return None,
+        };
+        let (parameters, fields) = self.enums.get(enum_)?;
+        let field = fields.iter().find(|field| field.name == variant)?;
+        let bindings = Self::type_arguments(parameters, type_);
+        Some(field.types.iter().map(|type_| type_.substitute(&bindings)).collect())
+    }
+
+    /// Declares the variables of a pattern with the parts of `type_` they
+    /// match, reporting the parts of the pattern a value of `type_` can't
+    /// match
+    fn bind_pattern(&mut self, pattern: &Pattern, type_: &Type) {
+        match pattern {
+            Pattern::IdentifierPattern { name, context } => self.declare(name, type_, context),
+            Pattern::BindingPattern { name, pattern, context } => {
+                self.declare(name, type_, context);
+                self.bind_pattern(pattern, type_);
+            },
+            Pattern::TuplePattern { patterns } => {
+                let types = match self.describe(type_) {
+                    Type::Tuple { types } if types.len() == patterns.len() => types,
+                    // A value whose type isn't known yet is a tuple of as many
+                    // parts as the pattern has
+                    Type::Variable { .. } => {
+                        let types: Vec<Type> = patterns.iter().map(|_| self.fresh()).collect();
+                        self.unify(&Type::Tuple { types: types.clone() }, type_);
+                        types
+                    },
+                    _ => {
+                        if !self.is_type_parameter(type_) {
+                            self.expect(&Type::Tuple { types: vec![Type::Inferred; patterns.len()] }, type_);
+                        }
+                        vec![Type::Inferred; patterns.len()]
+                    },
+                };
+                for (pattern, type_) in patterns.iter().zip(&types) {
+                    self.bind_pattern(pattern, type_);
+                }
+            },
+            Pattern::EnumPattern { path, patterns, context } => {
+                let mut payload = None;
+                if let Some((enum_, variant)) = PathSegment::variant(path) {
+                    match &self.describe(type_) {
+                        Type::Type { name } | Type::GenericType { name, types: _ } if name == enum_ => {
+                            payload = self.payload(type_, variant);
+                        },
+                        _ if self.is_unknown(type_) || self.is_type_parameter(type_) => {},
+                        _ => {
+                            let found = match self.enums.get(enum_) {
+                                Some((parameters, _)) => Self::erase(&Self::generic_type(enum_, parameters), parameters),
+                                None => Type::Type { name: enum_.clone() },
+                            };
+                            let outer = std::mem::replace(&mut self.context, context.clone());
+                            self.expect(&found, type_);
+                            self.context = outer;
+                        },
+                    }
+                }
+                let payload = payload.unwrap_or_else(|| vec![Type::Inferred; patterns.len()]);
+                for (pattern, type_) in patterns.iter().zip(payload) {
+                    self.bind_pattern(pattern, &type_);
+                }
+            },
+            // Every alternative binds the same names
+            Pattern::OrPattern { patterns, context: _ } => {
+                for pattern in patterns {
+                    self.bind_pattern(pattern, type_);
+                }
+            },
+            Pattern::LiteralPattern { literal } => {
+                self.check_expression(literal, type_);
+            },
+            Pattern::RangePattern { start, end, inclusive: _, context } => {
+                let outer = std::mem::replace(&mut self.context, context.clone());
+                self.check_expression(start, type_);
+                self.check_expression(end, type_);
+                self.context = outer;
+            },
+            Pattern::BlankPattern => {},
+        }
+    }
+
+    fn declare(&mut self, name: &str, type_: &Type, context: &TokenContext) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), type_.clone());
+        }
+        self.bindings.push((name.to_string(), type_.clone(), context.clone()));
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.variables.push(None);
+        self.defaults.push(None);
+        Type::Variable { id: self.variables.len() - 1 }
+    }
+
+    /// A type variable for a number literal, which can only be solved as a
+    /// number type of the same kind as `default`
+    fn fresh_number(&mut self, default: Type) -> Type {
+        let type_ = self.fresh();
+        if let Some(last) = self.defaults.last_mut() {
+            *last = Some(default);
+        }
+        type_
+    }
+
+    /// Fresh type variables for the type parameters of a generic item
+    fn instantiate(&mut self, parameters: &[String]) -> HashMap<String, Type> {
+        parameters.iter().map(|parameter| (parameter.clone(), self.fresh())).collect()
+    }
+
+    /// `type_`, or what it was solved as if it is a type variable
+    fn shallow(&self, type_: &Type) -> Type {
+        match type_ {
+            Type::Variable { id } => match &self.variables[*id] {
+                Some(solved) => self.shallow(solved),
+                None => type_.clone(),
+            },
+            _ => type_.clone(),
+        }
+    }
+
+    /// `type_` with every type variable in it that is solved replaced by what
+    /// it was solved as
+    fn resolve(&self, type_: &Type) -> Type {
+        self.resolve_as(type_, false)
+    }
+
+    /// `type_` the way it is shown in errors, which is resolved with the type
+    /// variables of number literals as the types they default to
+    fn describe(&self, type_: &Type) -> Type {
+        self.resolve_as(type_, true)
+    }
+
+    fn resolve_as(&self, type_: &Type, defaults: bool) -> Type {
+        match self.shallow(type_) {
+            Type::Variable { id } if defaults => self.defaults[id].clone().unwrap_or(Type::Variable { id }),
+            Type::Tuple { types } => Type::Tuple { types: types.iter().map(|type_| self.resolve_as(type_, defaults)).collect() },
+            Type::Array { type_, length } => Type::Array { type_: Box::new(self.resolve_as(&type_, defaults)), length },
+            Type::Pointer { pointed } => Type::Pointer { pointed: Box::new(self.resolve_as(&pointed, defaults)) },
+            Type::Function { parameters, return_type } => Type::Function {
+                parameters: parameters.iter().map(|type_| self.resolve_as(type_, defaults)).collect(),
+                return_type: Box::new(self.resolve_as(&return_type, defaults)),
+            },
+            Type::GenericType { name, types } => Type::GenericType {
+                name,
+                types: types.iter().map(|type_| self.resolve_as(type_, defaults)).collect(),
+            },
+            type_ => type_,
+        }
+    }
+
+    /// Solves the type variables left in `type_` as `Type::Inferred`
+    fn forget(&mut self, type_: &Type) {
+        for id in self.variables_in(type_) {
+            self.variables[id] = Some(Type::Inferred);
+        }
+    }
+
+    /// The type variables in `type_` that aren't solved yet
+    fn variables_in(&self, type_: &Type) -> Vec<usize> {
+        match self.resolve(type_) {
+            Type::Variable { id } => vec![id],
+            Type::Tuple { types } | Type::GenericType { name: _, types } => {
+                types.iter().flat_map(|type_| self.variables_in(type_)).collect()
+            },
+            Type::Function { parameters, return_type } => {
+                parameters.iter().chain(std::iter::once(return_type.as_ref())).flat_map(|type_| self.variables_in(type_)).collect()
+            },
+            Type::Array { type_, length: _ } | Type::Pointer { pointed: type_ } => self.variables_in(&type_),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether nothing is known about `type_` yet
+    fn is_unknown(&self, type_: &Type) -> bool {
+        matches!(self.shallow(type_), Type::Inferred | Type::Variable { .. })
+    }
+
+    /// Solves the type variables in `found` and `expected` so that the two
+    /// are the same type, if they can be. Nothing is solved if they can't.
+    /// An `expected` of `Type::Inferred` expects nothing, while
+    /// `Type::Inferred` inside a type is a part that isn't known, which the
+    /// type variables it lines up with are solved as
+    fn unify(&mut self, found: &Type, expected: &Type) -> bool {
+        if *expected == Type::Inferred {
+            return true;
+        }
+        let solved = (self.variables.clone(), self.defaults.clone());
+        let unified = self.unify_types(found, expected);
+        if !unified {
+            (self.variables, self.defaults) = solved;
+        }
+        unified
+    }
+
+    fn unify_types(&mut self, x: &Type, y: &Type) -> bool {
+        match (self.shallow(x), self.shallow(y)) {
+            (Type::Variable { id: x }, Type::Variable { id: y }) if x == y => true,
+            (Type::Variable { id }, type_) | (type_, Type::Variable { id }) => {
+                if self.variables_in(&type_).contains(&id) || !self.accepts(id, &type_) {
+                    return false;
+                }
+                self.variables[id] = Some(type_);
+                true
+            },
+            (Type::Inferred, _) | (_, Type::Inferred) => true,
+            (Type::Function { parameters: x, return_type: x_return },
+             Type::Function { parameters: y, return_type: y_return }) => {
+                x.len() == y.len()
+                    && x.iter().zip(&y).all(|(x, y)| self.unify_types(x, y))
+                    && self.unify_types(&x_return, &y_return)
+            },
+            (Type::Tuple { types: x }, Type::Tuple { types: y }) => {
+                x.len() == y.len() && x.iter().zip(&y).all(|(x, y)| self.unify_types(x, y))
+            },
+            (Type::GenericType { name: x_name, types: x }, Type::GenericType { name: y_name, types: y }) => {
+                x_name == y_name && x.len() == y.len() && x.iter().zip(&y).all(|(x, y)| self.unify_types(x, y))
+            },
+            (Type::Array { type_: x, length: x_length }, Type::Array { type_: y, length: y_length }) => {
+                x_length == y_length && self.unify_types(&x, &y)
+            },
+            (Type::Pointer { pointed: x }, Type::Pointer { pointed: y }) => self.unify_types(&x, &y),
+            (x, y) => x == y,
+        }
+    }
+
+    /// Whether the type variable `id` can be solved as `type_`. That of a
+    /// number literal can only be a number, and a float if the literal is
+    /// one. It passes that on to another type variable it is solved as, so
+    /// that an integer literal solved as a float literal is a float too
+    fn accepts(&mut self, id: usize, type_: &Type) -> bool {
+        let default = match self.defaults[id].clone() {
+            Some(default) => default,
+            None => return true,
+        };
+        match type_ {
+            Type::Inferred => true,
+            Type::Variable { id: other } => {
+                if self.defaults[*other].as_ref().is_none_or(|other| default.is_float() && other.is_integer()) {
+                    self.defaults[*other] = Some(default);
+                }
+                true
+            },
+            type_ => type_.is_float() || default.is_integer() && type_.is_integer(),
+        }
+    }
+
+    /// Replaces the type parameters left in `type_` with `Type::Inferred`
+    fn erase(type_: &Type, parameters: &[String]) -> Type {
+        let bindings = parameters.iter().map(|parameter| (parameter.clone(), Type::Inferred)).collect();
+        type_.substitute(&bindings)
+    }
+
+    /// The types a value of the struct or enum type `type_` binds its type
+    /// parameters to, which are unknown if `type_` doesn't list them
+    fn type_arguments(parameters: &[String], type_: &Type) -> HashMap<String, Type> {
+        match type_ {
+            Type::GenericType { name: _, types } if types.len() == parameters.len() => {
+                parameters.iter().cloned().zip(types.iter().cloned()).collect()
+            },
+            _ => parameters.iter().map(|parameter| (parameter.clone(), Type::Inferred)).collect(),
+        }
+    }
+
+    /// The type of a struct or enum with its own type parameters as arguments
+    fn generic_type(name: &str, parameters: &[String]) -> Type {
+        if parameters.is_empty() {
+            Type::Type { name: name.to_string() }
+        } else {
+            let types = parameters.iter().map(|parameter| Type::Type { name: parameter.clone() }).collect();
+            Type::GenericType { name: name.to_string(), types }
+        }
+    }
+
+    fn parameter_names(header: &FunctionHeader) -> Vec<String> {
+        header.type_parameters.iter().map(|parameter| parameter.name.clone()).collect()
+    }
+
+    fn self_type() -> Type {
+        Type::Type { name: String::from("Self") }
+    }
+
+    /// A method header with `Self` replaced by the type it is called for
+    fn instance_header(header: &FunctionHeader, type_: &Type) -> FunctionHeader {
+        let bindings = HashMap::from([(String::from("Self"), type_.clone())]);
+        FunctionHeader {
+            types: header.types.iter().map(|parameter| parameter.substitute(&bindings)).collect(),
+            return_type: header.return_type.substitute(&bindings),
+            ..header.clone()
+        }
+    }
+
+    fn is_type_parameter(&self, type_: &Type) -> bool {
+        matches!(type_, Type::Type { name } if self.type_parameters.contains(name))
+    }
+
+    /// Whether `type_` is fully known, with no type variables left in it and
+    /// none of the type parameters of the item, whose bounds are checked when
+    /// the program runs
+    fn is_concrete(&self, type_: &Type) -> bool {
+        match type_ {
+            Type::Variable { .. } | Type::Inferred => false,
+            Type::Tuple { types } | Type::GenericType { name: _, types } => types.iter().all(|type_| self.is_concrete(type_)),
+            Type::Array { type_, length: _ } => self.is_concrete(type_),
+            Type::Function { parameters, return_type } => {
+                parameters.iter().all(|type_| self.is_concrete(type_)) && self.is_concrete(return_type)
+            },
+            _ => !self.is_type_parameter(type_),
+        }
+    }
+
+    fn expect(&mut self, found: &Type, expected: &Type) {
+        if !self.unify(found, expected) {
+            self.error(SyntaxErrorType::TypeMismatch { expected: self.describe(expected), found: self.describe(found) });
+        }
+    }
+
+    fn error(&mut self, error_type: SyntaxErrorType) {
+        self.error_at(error_type, self.context.clone());
+    }
+
+    fn error_at(&mut self, error_type: SyntaxErrorType, context: TokenContext) {
+        self.errors.errors.push(SyntaxError { error_type, context, suggestion: None });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn analyze(code: &str) -> Result<Vec<SyntaxWarning>, SyntaxErrorCollector> {
+        let items = Lexer::new(String::from("test.px"), code.to_string()).parse()?;
+        StaticAnalyzer::new(LintLevels::new()).analyze(&items)
+    }
+
+    fn resolve(code: &str) -> Result<NameTable, SyntaxErrorCollector> {
+        let items = Lexer::new(String::from("test.px"), code.to_string()).parse()?;
+        NameResolver::new().resolve(&items)
+    }
+
+    fn type_check(code: &str) -> Result<LiteralTypes, SyntaxErrorCollector> {
+        let items = Lexer::new(String::from("test.px"), code.to_string()).parse()?;
+        let names = NameResolver::new().resolve(&items)?;
+        TypeChecker::new(&names).check(&items)
+    }
+
+    /// The line and message of every type error in `code`
+    fn type_errors(code: &str) -> Vec<(usize, String)> {
+        match type_check(code) {
+            Ok(_) => Vec::new(),
+            Err(collector) => collector.errors.iter().map(|error| (error.context.line, error.error_type.to_string())).collect(),
+        }
+    }
+
+    /// The declaration of the name that starts `code` at its first `nth`
+    /// appearances, with its kind and line
+    fn declaration(table: &NameTable, code: &str, name: &str, nth: usize) -> Option<(DeclarationKind, usize)> {
+        let index = code.match_indices(name).nth(nth)?.0;
+        let declaration = table.value(&TokenContext::new(String::from("test.px"), index, 0, 0))?;
+        Some((declaration.kind, declaration.context.line))
+    }
+
+    #[test]
+    fn check_array_lengths() {
+        assert!(type_check("
+            fn main() {
+                let n = 8;
+                let a: [i32; 3] = [1, 2, 3];
+                let b: [[u8; 2]; 4] = [[0; 2]; 4];
+                let c: [isize; 8] = [0; n];
+            }
+        ").is_ok());
+
+        let errors = type_errors("
+            fn main() {
+                let a: [i32; 4] = [1, 2, 3];
+                let b: [i32; 3] = [0; 4];
+            }
+        ");
+        assert_eq!(errors, vec![
+            (3, String::from("expected a value of type `[i32; 4]`, found `[i32; 3]`")),
+            (4, String::from("expected a value of type `[i32; 3]`, found `[i32; 4]`")),
+        ]);
+    }
+
+    #[test]
+    fn check_nested_array_length() {
+        let errors = type_errors("
+            static GRID: [[isize; 3]; 2] = [[0; 3], [0; 2]];
+        ");
+        assert_eq!(errors, vec![(2, String::from("expected a value of type `[isize; 3]`, found `[isize; 2]`"))]);
+    }
+
+    #[test]
+    fn check_mixed_elements() {
+        let Err(collector) = type_check("
+            fn main() {
+                let a = [1, \"two\"];
+                let b = [1, true];
+                let c = ['a', 'b', \"c\"];
+            }
+        ") else { panic!("expected errors") };
+
+        // Each error is at the element that doesn't match the first one
+        let errors: Vec<(usize, usize, String)> = collector.errors.iter()
+            .map(|error| (error.context.line, error.context.column, error.error_type.to_string()))
+            .collect();
+        assert_eq!(errors, vec![
+            (3, 29, String::from("expected a value of type `isize`, found `str`")),
+            (4, 29, String::from("expected a value of type `isize`, found `bool`")),
+            (5, 36, String::from("expected a value of type `char`, found `str`")),
+        ]);
+    }
+
+    #[test]
+    fn analyze_enum_variants() {
+        let result = analyze("
+            enum Shape { Circle(f64), Empty }
+
+            fn main() {
+                let a = Shape::Circle(1.0, 2.0);
+                let b = Shape::Circle;
+                let c = Shape::Square(1.0);
+                let Shape::Empty(x) = Shape::Empty;
+                let d = Color::Red;
+            }
+        ");
+
+        assert!(matches!(result, Err(collector) if matches!(&collector.errors[..], [
+            SyntaxError { error_type: SyntaxErrorType::VariantArityMismatch { expected: 1, found: 2, .. }, .. },
+            SyntaxError { error_type: SyntaxErrorType::VariantArityMismatch { expected: 1, found: 0, .. }, .. },
+            SyntaxError { error_type: SyntaxErrorType::UndefinedVariant { .. }, .. },
+            SyntaxError { error_type: SyntaxErrorType::VariantArityMismatch { expected: 0, found: 1, .. }, .. },
+            SyntaxError { error_type: SyntaxErrorType::UndefinedVariable(_), .. },
+        ])));
+    }
+
+    #[test]
+    fn analyze_reachable_arms() {
+        let result = analyze("
+            enum Shape { Circle(f64), Square(f64), Empty }
+
+            fn main() {
+                let a = match x {
+                    1..=9 => 0,
+                    3 | 5 => 1,
+                    n if n > 10 => 2,
+                    10..20 => 3,
+                    19 => 4,
+                    _ => 5,
+                    20 => 6,
+                };
+                let b = match s {
+                    Shape::Circle(r) | Shape::Square(r) => r,
+                    s @ Shape::Square(_) => 0.0,
+                    Shape::Empty => 0.0,
+                };
+                let c = match (c, d) {
+                    ('a'..='z', _) => 0,
+                    ('q', true) => 1,
+                    (_, false) => 2,
+                };
+            }
+        ");
+
+        let Ok(warnings) = result else { panic!("expected no errors") };
+        let lines: Vec<usize> = warnings.iter()
+            .filter(|warning| matches!(warning.warning_type, SyntaxWarningType::UnreachablePattern { .. }))
+            .map(|warning| warning.context.line)
+            .collect();
+        assert_eq!(lines, vec![7, 10, 12, 16, 21]);
+    }
+
+    #[test]
+    fn analyze_invalid_patterns() {
+        let result = analyze("
+            fn main() {
+                let a = match p {
+                    (x, 1) | (1, y) => 0,
+                    5..5 => 1,
+                    'z'..='a' => 2,
+                    _ => 3,
+                };
+            }
+        ");
+
+        assert!(matches!(result, Err(collector) if matches!(&collector.errors[..], [
+            SyntaxError { error_type: SyntaxErrorType::InconsistentBindings(name), .. },
+            SyntaxError { error_type: SyntaxErrorType::EmptyRangePattern, .. },
+            SyntaxError { error_type: SyntaxErrorType::EmptyRangePattern, .. },
+        ] if name == "x")));
+    }
+
+    #[test]
+    fn resolve_scopes() {
+        let code = "
+            static LIMIT: isize = 3;
+
+            fn scale(x: isize): isize = x * LIMIT;
+
+            fn main() {
+                let x = 1;
+                let add = |y: isize| x + y;
+                let x = scale(x);
+                let n = match x {
+                    k if k > LIMIT => k,
+                    _ => add(x),
+                };
+                println(n);
+            }
+        ";
+        let Ok(table) = resolve(code) else { panic!("expected no errors") };
+
+        assert_eq!(declaration(&table, code, "x", 1), Some((DeclarationKind::Parameter, 4)));
+        assert_eq!(declaration(&table, code, "LIMIT", 1), Some((DeclarationKind::Static, 2)));
+        assert_eq!(declaration(&table, code, "x", 3), Some((DeclarationKind::Variable, 7)));
+        assert_eq!(declaration(&table, code, "y", 1), Some((DeclarationKind::Parameter, 8)));
+        assert_eq!(declaration(&table, code, "scale", 1), Some((DeclarationKind::Function, 4)));
+        assert_eq!(declaration(&table, code, "x", 5), Some((DeclarationKind::Variable, 7)));
+        assert_eq!(declaration(&table, code, "x", 6), Some((DeclarationKind::Variable, 9)));
+        assert_eq!(declaration(&table, code, "k", 1), Some((DeclarationKind::Variable, 11)));
+        assert_eq!(declaration(&table, code, "println", 0), Some((DeclarationKind::Builtin, 0)));
+    }
+
+    #[test]
+    fn resolve_items() {
+        let code = "
+            enum Shape { Circle(f64), Empty }
+            struct Point { x: isize }
+
+            trait Describe {
+                fn describe(x: Self): isize;
+            }
+
+            instance Describe Point {
+                fn describe(p: Point): isize = p.x;
+            }
+
+            fn main() {
+                let s = Shape::Circle(1.0);
+                let p = Point { x: 1 };
+                describe(p);
+            }
+        ";
+        let Ok(table) = resolve(code) else { panic!("expected no errors") };
+
+        assert_eq!(declaration(&table, code, "Shape::Circle", 0), Some((DeclarationKind::Variant, 2)));
+        assert_eq!(declaration(&table, code, "Point {", 2), Some((DeclarationKind::Struct, 3)));
+        assert_eq!(declaration(&table, code, "describe", 2), Some((DeclarationKind::Method, 6)));
+    }
+
+    #[test]
+    fn resolve_undefined_names() {
+        let result = resolve("
+            struct Point { x: isize }
+
+            fn area(p: Pointt): isize = 0;
+            fn check<T: Describe>(x: T) {}
+
+            fn main() {
+                let total = 1;
+                if false { println(totl); }
+                let q = Pont { x: total };
+                let f = |y: isize| y + z;
+                println(y);
+                println(fnn);
+            }
+        ");
+
+        assert!(matches!(result, Err(collector) if matches!(&collector.errors[..], [
+            SyntaxError { error_type: SyntaxErrorType::UndefinedType(type_), suggestion: Some(Suggestion { applicable: false, .. }), .. },
+            SyntaxError { error_type: SyntaxErrorType::UndefinedTrait(trait_), .. },
+            SyntaxError { error_type: SyntaxErrorType::UndefinedVariable(total), suggestion: Some(_), .. },
+            SyntaxError { error_type: SyntaxErrorType::UndefinedStruct(struct_), .. },
+            SyntaxError { error_type: SyntaxErrorType::UndefinedVariable(z), .. },
+            SyntaxError { error_type: SyntaxErrorType::UndefinedVariable(y), .. },
+            SyntaxError { error_type: SyntaxErrorType::UndefinedVariable(fnn), suggestion: None, .. },
+        ] if type_ == "Pointt" && trait_ == "Describe" && total == "totl" && struct_ == "Pont" && z == "z" && y == "y" && fnn == "fnn")));
+    }
+
+    #[test]
+    fn resolve_duplicate_names() {
+        let result = resolve("
+            fn area(): isize = 1;
+            fn area(): isize = 2;
+            struct area { x: isize }
+            enum Shape { Circle, Circle }
+
+            fn swap(a: isize, a: isize) {}
+
+            fn main() {
+                let (b, b) = (1, 2);
+                let c = 1;
+                let c = 2;
+                let d = match (1, 2) {
+                    (e, 1) | (1, e) => e,
+                    _ => 0,
+                };
+            }
+        ");
+
+        let Err(collector) = result else { panic!("expected errors") };
+        let duplicates: Vec<(&str, usize, usize)> = collector.errors.iter().map(|error| match &error.error_type {
+            SyntaxErrorType::DuplicateDefinition { name, previous } => (name.as_str(), error.context.line, previous.line),
+            error_type => panic!("unexpected error {:?}", error_type),
+        }).collect();
+        assert_eq!(duplicates, vec![("area", 3, 2), ("Shape::Circle", 5, 5), ("a", 7, 7), ("b", 10, 10)]);
+
+        // Parameters are reported where they are named
+        let SyntaxErrorType::DuplicateDefinition { name: _, previous } = &collector.errors[2].error_type else { unreachable!() };
+        assert_eq!((collector.errors[2].context.column, previous.column), (31, 21));
+    }
+
+    #[test]
+    fn resolve_type_positions() {
+        let result = resolve("
+            fn main() {
+                let v: Undefined = 1;
+                let f = |x: isize, x: isize| x;
+            }
+        ");
+
+        let Err(collector) = result else { panic!("expected errors") };
+        let positions: Vec<(usize, usize)> = collector.errors.iter().map(|error| (error.context.line, error.context.column)).collect();
+        assert_eq!(positions, vec![(3, 24), (4, 36)]);
+    }
+
+    #[test]
+    fn check_valid_types() {
+        let result = type_check("
+            enum Option<T> { Some(T), None }
+            struct Pair<T> { first: T, second: T }
+
+            trait Describe {
+                fn describe(x: Self): isize;
+                fn twice(x: Self): isize = x.describe() * 2;
+            }
+
+            instance Describe bool {
+                fn describe(x: bool): isize = if x { 1 } else { 0 };
+            }
+
+            fn max<T: Ord>(a: T, b: T): T = if a > b { a } else { b };
+
+            fn sign(x: i32): i32 {
+                if x < 0 {
+                    return -1;
+                }
+                match x {
+                    0 => 0,
+                    _ => 1,
+                }
+            }
+
+            fn main() {
+                let small: u8 = 2 * 3 + 1;
+                let pair: Pair<i32> = Pair { first: 1, second: sign(-4) };
+                let total = loop {
+                    break pair.first + 1;
+                };
+                let add = |a: isize, b: isize| a + b;
+                let value = Option::Some(max(3, add(1, 2)));
+                println(small, total, true.twice(), unwrap(value) * 2, [1, 2] == [small, small]);
+            }
+        ");
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn check_functions_and_calls() {
+        let errors = type_errors("
+            fn add(a: isize, b: isize): isize = a + b;
+            fn max<T: Ord>(a: T, b: T): T = if a > b { a } else { b };
+
+            fn name(): str {
+                5
+            }
+
+            fn main() {
+                let flag: bool = add(1, 2);
+                add(1);
+                add(true, 2);
+                max('a', \"b\");
+                flag();
+            }
+        ");
+
+        assert_eq!(errors, vec![
+            (6, String::from("expected a value of type `str`, found `isize`")),
+            (10, String::from("expected a value of type `bool`, found `isize`")),
+            (11, String::from("expected 2 arguments, found 1")),
+            (12, String::from("expected a value of type `isize`, found `bool`")),
+            (13, String::from("expected a value of type `char`, found `str`")),
+            (14, String::from("a value of type `bool` can't be called")),
+        ]);
+    }
+
+    #[test]
+    fn check_operators() {
+        let errors = type_errors("
+            fn main() {
+                let small: i32 = 5;
+                let big: i64 = 5;
+                let a = small + big;
+                let b = \"a\" - \"b\";
+                let c = 1.5 & 2.5;
+                let d = true < false;
+                let e = not 5;
+                let f = small + 1;
+                let g = 2.0 * 1.5;
+                let h = -\"s\";
+            }
+        ");
+
+        assert_eq!(errors, vec![
+            (5, String::from("this operator can't be used with `i32` and `i64`")),
+            (6, String::from("this operator can't be used with `str` and `str`")),
+            (7, String::from("this operator can't be used with `f64` and `f64`")),
+            (8, String::from("this operator can't be used with `bool` and `bool`")),
+            (9, String::from("this operator can't be used with `isize`")),
+            (12, String::from("this operator can't be used with `str`")),
+        ]);
+    }
+
+    #[test]
+    fn check_branches_and_fields() {
+        let errors = type_errors("
+            struct Point { x: isize, y: isize }
+
+            fn main() {
+                let p = Point { x: 1.5, y: 2 };
+                let q = Point { y: 5, ..p };
+                let c = if true { 1 } else { 'c' };
+                let m = match q.x {
+                    1 => 'a',
+                    _ => 2.0,
+                };
+                let n = match q.x {
+                    'a' => 1,
+                    _ => 2,
+                };
+                let z = p.z;
+            }
+        ");
+
+        assert_eq!(errors, vec![
+            (5, String::from("expected a value of type `isize`, found `f64`")),
+            (7, String::from("expected a value of type `isize`, found `char`")),
+            (10, String::from("expected a value of type `char`, found `f64`")),
+            (13, String::from("expected a value of type `isize`, found `char`")),
+            (16, String::from("`Point` has no field `z`")),
+        ]);
+    }
+
+    #[test]
+    fn check_inferred_types() {
+        let result = type_check("
+            enum Option<T> { Some(T), None }
+
+            fn none<T>(): Option<T> = Option::None;
+            fn apply<T, U>(x: T, f: fn(T): U): U = f(x);
+
+            fn main() {
+                let add = |a, b| a + b;
+                let twice = |f, x| f(f(x));
+                let empty = [];
+                let flags: [bool; 0] = empty;
+                let nothing = none();
+                let number: Option<i32> = nothing;
+                let (low, high) = (1, 2.5);
+                println(add(1, 2), twice(|n| n * 2, low), apply(high, |x| x / 2.0));
+            }
+        ");
+
+        assert!(result.is_ok());
+
+        // The literals in a closure take the type it is called with
+        let literals = type_check("
+            fn main() {
+                let next = |x| x + 1;
+                let double = |x| x * 2;
+                let byte: u8 = next(1);
+                let wide: u64 = double(3);
+            }
+        ").ok().unwrap();
+
+        assert_eq!(literals.types.values().filter(|type_| **type_ == Type::UInt8).count(), 2);
+        assert_eq!(literals.types.values().filter(|type_| **type_ == Type::UInt64).count(), 2);
+
+        let errors = type_errors("
+            enum Option<T> { Some(T), None }
+
+            fn none<T>(): Option<T> = Option::None;
+
+            fn main() {
+                let empty = [];
+                let same = |x| x;
+                let next = |x| x + 1;
+                next(true);
+                let nothing = none();
+                let times = |a, b| a * b;
+                times(\"a\", \"b\");
+            }
+        ");
+
+        assert_eq!(errors, vec![
+            (7, String::from("type annotations are needed for `empty`")),
+            (8, String::from("type annotations are needed for `x`")),
+            (10, String::from("expected a value of type `isize`, found `bool`")),
+            (11, String::from("the type parameter `T` can't be inferred")),
+            (12, String::from("this operator can't be used with `str` and `str`")),
+        ]);
+    }
+
+    #[test]
+    fn check_number_literals() {
+        let result = type_check("
+            fn half(x: f32): f32 = x / 2.0;
+            fn average(x: f64, y: f64): f64 = (x + y) / 2;
+
+            fn main() {
+                let count = 5;
+                let byte: u8 = count;
+                let lowest: i8 = -128;
+                let ratio = 1.5;
+                let sizes = [1, 2, 3];
+                let total: [u16; 3] = sizes;
+                let y: f32 = 1;
+                let scaled = 2.0 * 3;
+                println(byte + 1, lowest, half(ratio), total, y, scaled, average(2, 3), half(-4));
+            }
+        ");
+
+        assert!(result.is_ok());
+
+        let errors = type_errors("
+            fn main() {
+                let byte: u8 = 300;
+                let lowest: i8 = -129;
+                let unsigned: u32 = -1;
+                let ratio = 1.5;
+                let whole: i32 = ratio;
+                let index: usize = 3;
+                let mixed = 2.0 * index;
+            }
+        ");
+
+        assert_eq!(errors, vec![
+            (3, String::from("the literal `300` doesn't fit in `u8`")),
+            (4, String::from("the literal `-129` doesn't fit in `i8`")),
+            (5, String::from("the literal `-1` doesn't fit in `u32`")),
+            (7, String::from("expected a value of type `i32`, found `f64`")),
+            (9, String::from("this operator can't be used with `f64` and `usize`")),
+        ]);
+    }
+
+    #[test]
+    fn check_struct_fields() {
+        let errors = type_errors("
+            struct Point { x: isize, y: isize }
+
+            fn main() {
+                let origin = Point { x: 0, y: 0 };
+                let moved = Point { x: 1, ..origin };
+                if false {
+                    Point { x: 1 };
+                    Point { x: 1, y: 2, x: 3 };
+                }
+            }
+        ");
+
+        assert_eq!(errors, vec![
+            (8, String::from("missing field `y` of `Point`")),
+            (9, String::from("the field `x` is given more than once")),
+        ]);
+    }
+
+    #[test]
+    fn check_generic_struct_fields() {
+        let errors = type_errors("
+            struct Pair<T> { first: T, second: T }
+
+            fn main() {
+                let pair: Pair<bool> = Pair { first: true };
+                Pair { first: false, second: false, first: true };
+                Pair { first: 1, second: false };
+            }
+        ");
+
+        assert_eq!(errors, vec![
+            (5, String::from("missing field `second` of `Pair`")),
+            (6, String::from("the field `first` is given more than once")),
+            (7, String::from("expected a value of type `bool`, found `isize`")),
+        ]);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::syntax_errors::Suggestion;
+
+/// How many characters have to be inserted, removed or replaced to turn `a`
+/// into `b`, where swapping two neighbouring characters counts as one change
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // `rows[i][j]` is the distance between the first `i` characters of `a`
+    // and the first `j` characters of `b`
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut distance = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// The candidate `name` was most likely meant to be, if any is close enough
+/// to be a typo, along with whether it is the only candidate that close.
+/// Variables a macro renamed to `name#1` can't be written, so they are never
+/// candidates
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<(&'a str, bool)> {
+    let limit = (name.chars().count() / 3).max(1);
+    let mut best: Option<(&str, usize, bool)> = None;
+    for candidate in candidates {
+        if candidate == name || candidate.contains('#') {
+            continue;
+        }
+        let distance = edit_distance(name, candidate);
+        if distance > limit {
+            continue;
+        }
+        best = match best {
+            Some((_, closest, _)) if distance < closest => Some((candidate, distance, true)),
+            Some((best, closest, _)) if distance == closest && best != candidate => Some((best, closest, false)),
+            None => Some((candidate, distance, true)),
+            best => best,
+        };
+    }
+    best.map(|(candidate, _, unique)| (candidate, unique))
+}
+
+/// Makes every applicable suggestion in `code` whose span still holds the text
+/// it replaces, returning the new code and how many suggestions were made
+pub fn apply(code: &str, suggestions: &[&Suggestion]) -> (String, usize) {
+    let mut lines: Vec<String> = code.split('\n').map(str::to_string).collect();
+
+    // Later columns are replaced first so earlier ones don't move
+    let mut suggestions: Vec<&&Suggestion> = suggestions.iter().filter(|suggestion| suggestion.applicable).collect();
+    suggestions.sort_by_key(|suggestion| (suggestion.context.line, std::cmp::Reverse(suggestion.context.column)));
+    suggestions.dedup_by_key(|suggestion| (suggestion.context.line, suggestion.context.column));
+
+    let mut applied = 0;
+    for suggestion in suggestions {
+        let Some(line) = suggestion.context.line.checked_sub(1).and_then(|index| lines.get_mut(index)) else {
+            continue;
+        };
+        let start: usize = line.chars().take(suggestion.context.column.saturating_sub(1)).map(char::len_utf8).sum();
+        if line[start..].starts_with(&suggestion.original) {
+            line.replace_range(start..start + suggestion.original.len(), &suggestion.replacement);
+            applied += 1;
+        }
+    }
+    (lines.join("\n"), applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokens::TokenContext;
+
+    #[test]
+    fn distances() {
+        assert_eq!(edit_distance("total", "total"), 0);
+        assert_eq!(edit_distance("totl", "total"), 1);
+        assert_eq!(edit_distance("strcut", "struct"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn closest_names() {
+        assert_eq!(closest("totl", ["total", "count", "tot"]), Some(("total", false)));
+        assert_eq!(closest("lenght", ["length", "width"]), Some(("length", true)));
+        assert_eq!(closest("x", ["y", "xs"]), Some(("y", false)));
+        assert_eq!(closest("apple", ["banana", "apple"]), None);
+        assert_eq!(closest("secret", ["secret#0", "secrets"]), Some(("secrets", true)));
+    }
+
+    #[test]
+    fn apply_suggestions() {
+        let suggestion = |line, column, original: &str, replacement: &str| Suggestion {
+            context: TokenContext::new(String::from("test.px"), 0, column, line),
+            original: original.to_string(),
+            replacement: replacement.to_string(),
+            applicable: true,
+        };
+        let suggestions = [
+            suggestion(2, 10, "totl", "total"),
+            suggestion(2, 5, "prnt", "print"),
+            suggestion(3, 5, "missing", "found"),
+        ];
+
+        let code = "fn main() {\n    prnt(totl);\n    x;\n}";
+        let (fixed, applied) = apply(code, &suggestions.iter().collect::<Vec<_>>());
+        assert_eq!(fixed, "fn main() {\n    print(total);\n    x;\n}");
+        assert_eq!(applied, 2);
+    }
+}
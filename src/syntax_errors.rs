@@ -1,18 +1,144 @@
 use crate::lexer::*;
-use crate::tokens::TokenContext;
+use crate::lints::Lint;
+use crate::suggestions;
+use crate::nodes::{Trait, Type};
+use crate::tokens::{TokenContent, TokenContext};
+use std::fmt;
 
+#[derive(Debug)]
 pub struct SyntaxErrorCollector {
-    errors: Vec<SyntaxError>,
+    pub errors: Vec<SyntaxError>,
 }
 
+#[derive(Debug)]
 pub struct SyntaxError {
-    error_type: SyntaxErrorType,
-    context: TokenContext,
+    pub error_type: SyntaxErrorType,
+    pub context: TokenContext,
+    pub suggestion: Option<Suggestion>,
 }
 
+/// Replaces `original`, which starts at `context`, with `replacement`
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub context: TokenContext,
+    pub original: String,
+    pub replacement: String,
+    /// Whether `polarix fix` can make the replacement without asking, which
+    /// is when it is the only likely fix
+    pub applicable: bool,
+}
+
+impl Suggestion {
+    pub fn message(&self) -> String {
+        format!("did you mean `{}`?", self.replacement)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum SyntaxErrorType {
     LexerError(LexerErrorType),
     AtomExpected,
+    TokenExpected(TokenContent),
+    IdentifierExpected,
+    TypeExpected,
+    PatternExpected,
+    ArrayLengthExpected,
+    ItemExpected,
+    StatementExpected,
+    UnexpectedVisibility,
+    AttributeExpected,
+    LiteralExpected,
+    RepetitionExpected,
+    LoopExpected,
+    InvalidAssignmentTarget,
+    MissingMainFunction,
+    UndefinedVariable(String),
+    NotCallable(Type),
+    ArgumentCountMismatch { expected: usize, found: usize },
+    TypeMismatch { expected: Type, found: Type },
+    InvalidOperands(Type, Type),
+    InvalidOperand(Type),
+    NoSuchField(Type, String),
+    InvalidControlFlow,
+    UndefinedLabel(String),
+    InconsistentBindings(String),
+    EmptyRangePattern,
+    InvalidBreakValue,
+    UndefinedTrait(String),
+    UnsatisfiedTraitBound { type_: Type, trait_: Trait },
+    UninferredTypeParameter(String),
+    MissingTraitMethod { trait_: String, method: String },
+    NonConstantExpression,
+    ImpureFunctionCall(String),
+    ConstantCycle(Vec<String>),
+    /// A constant whose evaluation ran out of calls or loop iterations
+    ConstantEvaluationLimit,
+    StaticCycle(Vec<String>),
+    InvalidArrayLength(i128),
+    NotIndexable(Type),
+    NotIterable(Type),
+    UndefinedStruct(String),
+    MissingField { struct_: String, field: String },
+    DuplicateField(String),
+    ImmutableAssignment(String),
+    UndefinedVariant { enum_: String, variant: String },
+    VariantArityMismatch { variant: String, expected: usize, found: usize },
+    UnresolvedPath(String),
+    ModuleNotFound(String),
+    AmbiguousModule(String),
+    ModuleCycle(Vec<String>),
+    PrivateItem { item: String, module: String },
+    InvalidCfgPredicate,
+    UndefinedMacro(String),
+    NoMatchingMacroRule(String),
+    UndefinedMacroVariable(String),
+    MacroRepetitionMismatch(String),
+    InvalidFragmentKind(String),
+    MacroRecursionLimit(String),
+    /// A warning whose lint is set to `deny`
+    DeniedLint(SyntaxWarningType),
+    /// An expression the interpreter can't run yet
+    UnsupportedExpression(String),
+    /// A name declared again where it is already declared at `previous`
+    DuplicateDefinition { name: String, previous: TokenContext },
+    UndefinedType(String),
+    /// A variable whose type nothing in the program decides
+    TypeAnnotationsNeeded(String),
+    /// A number literal, as written, too large or small for its type
+    LiteralOutOfRange { literal: String, type_: Type },
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxWarning {
+    pub warning_type: SyntaxWarningType,
+    pub context: TokenContext,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxWarningType {
+    UnknownAttribute(String),
+    /// An arm hidden by the earlier arm at `covered_by`
+    UnreachablePattern { covered_by: TokenContext },
+    UnknownLint(String),
+}
+
+impl SyntaxWarningType {
+    pub fn lint(&self) -> Lint {
+        match self {
+            SyntaxWarningType::UnknownAttribute(_) => Lint::UnknownAttributes,
+            SyntaxWarningType::UnreachablePattern { .. } => Lint::UnreachablePatterns,
+            SyntaxWarningType::UnknownLint(_) => Lint::UnknownLints,
+        }
+    }
+
+    /// The stable code of the warning, which it keeps if its lint is denied
+    pub fn code(&self) -> &'static str {
+        match self {
+            SyntaxWarningType::UnknownAttribute(_) => "PX0075",
+            SyntaxWarningType::UnreachablePattern { .. } => "PX0076",
+            SyntaxWarningType::UnknownLint(_) => "PX0077",
+        }
+    }
 }
 
 impl SyntaxErrorCollector {
@@ -28,11 +154,214 @@ impl SyntaxErrorCollector {
 
     pub fn from_error(error_type: SyntaxErrorType, context: TokenContext) -> SyntaxErrorCollector {
         let mut error_collector = Self::new();
-        error_collector.errors.push(SyntaxError { error_type, context });
+        error_collector.errors.push(SyntaxError { error_type, context, suggestion: None });
         error_collector
     }
 
+    /// Suggests replacing `name`, which starts at the context of the last
+    /// error, with whichever of `candidates` it is most likely a typo of
+    pub fn suggest_name<'a>(&mut self, name: &str, candidates: impl IntoIterator<Item = &'a str>) {
+        if let Some((closest, unique)) = suggestions::closest(name, candidates) {
+            self.suggest(name.to_string(), closest.to_string(), unique);
+        }
+    }
+
+    /// Suggests replacing `original` at the last error with `replacement`
+    pub fn suggest(&mut self, original: String, replacement: String, applicable: bool) {
+        if let Some(error) = self.errors.last_mut() {
+            error.suggestion = Some(Suggestion { context: error.context.clone(), original, replacement, applicable });
+        }
+    }
+
     pub fn from_lexer_error(lexer_error: LexerError) -> SyntaxErrorCollector {
         Self::from_error(SyntaxErrorType::LexerError(lexer_error.error_type), lexer_error.context)
     }
-}
\ No newline at end of file
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl SyntaxErrorType {
+    /// The stable code of the error, which `polarix explain` describes. Codes
+    /// are never reused, so new errors get the next unused one. The codes
+    /// missing here belong to `RuntimeErrorType`
+    pub fn code(&self) -> &'static str {
+        use SyntaxErrorType::*;
+
+        match self {
+            LexerError(error_type) => error_type.code(),
+            AtomExpected => "PX0006",
+            TokenExpected(_) => "PX0007",
+            IdentifierExpected => "PX0008",
+            TypeExpected => "PX0009",
+            PatternExpected => "PX0010",
+            ArrayLengthExpected => "PX0011",
+            ItemExpected => "PX0012",
+            StatementExpected => "PX0078",
+            UnexpectedVisibility => "PX0013",
+            AttributeExpected => "PX0014",
+            LiteralExpected => "PX0015",
+            RepetitionExpected => "PX0016",
+            LoopExpected => "PX0017",
+            InvalidAssignmentTarget => "PX0018",
+            MissingMainFunction => "PX0019",
+            UndefinedVariable(_) => "PX0020",
+            NotCallable(_) => "PX0021",
+            ArgumentCountMismatch { .. } => "PX0022",
+            TypeMismatch { .. } => "PX0023",
+            InvalidOperands(..) | InvalidOperand(_) => "PX0024",
+            NoSuchField(..) => "PX0025",
+            InvalidControlFlow => "PX0030",
+            UndefinedLabel(_) => "PX0031",
+            InconsistentBindings(_) => "PX0032",
+            EmptyRangePattern => "PX0033",
+            InvalidBreakValue => "PX0034",
+            UndefinedTrait(_) => "PX0035",
+            UnsatisfiedTraitBound { .. } => "PX0036",
+            UninferredTypeParameter(_) => "PX0037",
+            MissingTraitMethod { .. } => "PX0038",
+            NonConstantExpression => "PX0039",
+            ImpureFunctionCall(_) => "PX0040",
+            ConstantCycle(_) => "PX0041",
+            StaticCycle(_) => "PX0042",
+            InvalidArrayLength(_) => "PX0043",
+            NotIndexable(_) => "PX0044",
+            NotIterable(_) => "PX0045",
+            UndefinedStruct(_) => "PX0047",
+            MissingField { .. } => "PX0048",
+            DuplicateField(_) => "PX0049",
+            ImmutableAssignment(_) => "PX0050",
+            UndefinedVariant { .. } => "PX0051",
+            VariantArityMismatch { .. } => "PX0052",
+            UnresolvedPath(_) => "PX0053",
+            ModuleNotFound(_) => "PX0054",
+            AmbiguousModule(_) => "PX0055",
+            ModuleCycle(_) => "PX0056",
+            PrivateItem { .. } => "PX0057",
+            InvalidCfgPredicate => "PX0058",
+            UndefinedMacro(_) => "PX0059",
+            NoMatchingMacroRule(_) => "PX0060",
+            UndefinedMacroVariable(_) => "PX0061",
+            MacroRepetitionMismatch(_) => "PX0062",
+            InvalidFragmentKind(_) => "PX0063",
+            MacroRecursionLimit(_) => "PX0064",
+            DeniedLint(warning_type) => warning_type.code(),
+            UnsupportedExpression(_) => "PX0066",
+            DuplicateDefinition { .. } => "PX0069",
+            UndefinedType(_) => "PX0070",
+            TypeAnnotationsNeeded(_) => "PX0071",
+            LiteralOutOfRange { .. } => "PX0072",
+            ConstantEvaluationLimit => "PX0074",
+        }
+    }
+}
+
+impl fmt::Display for SyntaxErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use SyntaxErrorType::*;
+
+        match self {
+            LexerError(error_type) => write!(f, "{}", error_type),
+            AtomExpected => write!(f, "expected an expression"),
+            TokenExpected(content) => write!(f, "expected `{}`", content.as_string()),
+            IdentifierExpected => write!(f, "expected a name"),
+            TypeExpected => write!(f, "expected a type"),
+            PatternExpected => write!(f, "expected a pattern"),
+            ArrayLengthExpected => write!(f, "expected the length of the array"),
+            ItemExpected => write!(f, "expected an item"),
+            StatementExpected => write!(f, "expected a statement"),
+            UnexpectedVisibility => write!(f, "`pub` isn't allowed here"),
+            AttributeExpected => write!(f, "expected an attribute"),
+            LiteralExpected => write!(f, "expected a literal"),
+            RepetitionExpected => write!(f, "expected `*`, `+` or `?` after the repetition"),
+            LoopExpected => write!(f, "expected `loop`, `while` or `for` after the label"),
+            InvalidAssignmentTarget => write!(f, "can't assign to this expression"),
+            MissingMainFunction => write!(f, "no `main` function was found"),
+            UndefinedVariable(name) => write!(f, "`{}` is not defined", name),
+            NotCallable(type_) => write!(f, "a value of type `{}` can't be called", type_.as_string()),
+            ArgumentCountMismatch { expected, found } => {
+                write!(f, "expected {} argument{}, found {}", expected, if *expected == 1 { "" } else { "s" }, found)
+            },
+            TypeMismatch { expected, found } => {
+                write!(f, "expected a value of type `{}`, found `{}`", expected.as_string(), found.as_string())
+            },
+            InvalidOperands(left, right) => {
+                write!(f, "this operator can't be used with `{}` and `{}`", left.as_string(), right.as_string())
+            },
+            InvalidOperand(type_) => write!(f, "this operator can't be used with `{}`", type_.as_string()),
+            NoSuchField(type_, field) => write!(f, "`{}` has no field `{}`", type_.as_string(), field),
+            InvalidControlFlow => write!(f, "`break`, `continue` or `return` can't be used here"),
+            UndefinedLabel(label) => write!(f, "no loop around this is labeled `'{}`", label),
+            InconsistentBindings(name) => write!(f, "`{}` isn't bound by every alternative of the pattern", name),
+            EmptyRangePattern => write!(f, "the range pattern matches nothing"),
+            InvalidBreakValue => write!(f, "only `loop` can be broken out of with a value"),
+            UndefinedTrait(name) => write!(f, "trait `{}` is not defined", name),
+            UnsatisfiedTraitBound { type_, trait_ } => {
+                write!(f, "`{}` doesn't implement `{}`", type_.as_string(), trait_.as_string())
+            },
+            UninferredTypeParameter(name) => write!(f, "the type parameter `{}` can't be inferred", name),
+            MissingTraitMethod { trait_, method } => write!(f, "the instance of `{}` is missing `{}`", trait_, method),
+            NonConstantExpression => write!(f, "this expression can't be evaluated before the program runs"),
+            ImpureFunctionCall(name) => write!(f, "`{}` can't be called in a constant", name),
+            ConstantCycle(cycle) => write!(f, "the constants depend on each other: {}", cycle.join(" -> ")),
+            ConstantEvaluationLimit => write!(f, "evaluating this constant takes too long"),
+            StaticCycle(cycle) => write!(f, "the statics depend on each other: {}", cycle.join(" -> ")),
+            InvalidArrayLength(length) => write!(f, "`{}` isn't a valid array length", length),
+            NotIndexable(type_) => write!(f, "a value of type `{}` can't be indexed", type_.as_string()),
+            NotIterable(type_) => write!(f, "a value of type `{}` can't be iterated over", type_.as_string()),
+            UndefinedStruct(name) => write!(f, "struct `{}` is not defined", name),
+            MissingField { struct_, field } => write!(f, "missing field `{}` of `{}`", field, struct_),
+            DuplicateField(field) => write!(f, "the field `{}` is given more than once", field),
+            ImmutableAssignment(name) => write!(f, "`{}` can't be assigned to", name),
+            UndefinedVariant { enum_, variant } => write!(f, "`{}` has no variant `{}`", enum_, variant),
+            VariantArityMismatch { variant, expected, found } => {
+                write!(f, "`{}` carries {} value{}, found {}", variant, expected, if *expected == 1 { "" } else { "s" }, found)
+            },
+            UnresolvedPath(path) => write!(f, "`{}` doesn't lead to an item", path),
+            ModuleNotFound(name) => write!(f, "no file was found for the module `{}`", name),
+            AmbiguousModule(name) => write!(f, "the module `{}` has more than one file", name),
+            ModuleCycle(files) => write!(f, "the module files include each other: {}", files.join(" -> ")),
+            PrivateItem { item, module } => write!(f, "`{}` is private to `{}`", item, module),
+            InvalidCfgPredicate => write!(f, "invalid `cfg` predicate"),
+            UndefinedMacro(name) => write!(f, "macro `{}` is not defined", name),
+            NoMatchingMacroRule(name) => write!(f, "no rule of `{}!` matches these arguments", name),
+            UndefinedMacroVariable(name) => write!(f, "`${}` is not a variable of the macro", name),
+            MacroRepetitionMismatch(name) => write!(f, "`${}` is repeated a different number of times", name),
+            InvalidFragmentKind(kind) => write!(f, "`{}` isn't a kind of fragment", kind),
+            MacroRecursionLimit(name) => write!(f, "`{}!` is expanded too many times inside itself", name),
+            DeniedLint(warning_type) => write!(f, "{}", warning_type),
+            UnsupportedExpression(kind) => write!(f, "{} aren't supported yet", kind),
+            DuplicateDefinition { name, previous: _ } => write!(f, "`{}` is defined more than once", name),
+            UndefinedType(name) => write!(f, "type `{}` is not defined", name),
+            TypeAnnotationsNeeded(name) => write!(f, "type annotations are needed for `{}`", name),
+            LiteralOutOfRange { literal, type_ } => write!(f, "the literal `{}` doesn't fit in `{}`", literal, type_.as_string()),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxWarningType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyntaxWarningType::UnknownAttribute(name) => write!(f, "unknown attribute `{}`", name),
+            SyntaxWarningType::UnreachablePattern { .. } => write!(f, "unreachable pattern"),
+            SyntaxWarningType::UnknownLint(name) => write!(f, "unknown lint `{}`", name),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let context = &self.context;
+        write!(f, "{}:{}:{}: error[{}]: {}", context.filename, context.line, context.column, self.error_type.code(), self.error_type)
+    }
+}
+
+impl fmt::Display for SyntaxErrorCollector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for error in &self.errors {
+            writeln!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
@@ -1,28 +1,62 @@
 use TokenContent::*;
 use crate::lexer::ProgramContext;
+use std::cell::Cell;
+use std::rc::Rc;
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct Token {
     pub content: TokenContent,
     pub context: TokenContext,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct TokenContext {
     pub filename: String,
     pub index: usize,
     pub column: usize,
     pub line: usize,
+    /// The macro invocation the token was produced by, if any
+    pub expansion: Option<Rc<Expansion>>,
+}
+
+/// Code produced by expanding a macro, which replaces the invocation in the
+/// source and spans up to `end`
+#[derive(Debug)]
+pub struct Expansion {
+    pub name: String,
+    pub call: TokenContext,
+    pub end: Cell<usize>,
+    /// Where the code after the invocation starts in the original source
+    pub line: usize,
+    pub column: usize,
+}
+
+impl TokenContext {
+    pub fn new(filename: String, index: usize, column: usize, line: usize) -> TokenContext {
+        TokenContext { filename, index, column, line, expansion: None }
+    }
+
+    /// Every macro invocation that led to this position, innermost first
+    pub fn backtrace(&self) -> Vec<(String, TokenContext)> {
+        let mut backtrace = Vec::new();
+        let mut expansion = self.expansion.clone();
+        while let Some(current) = expansion {
+            backtrace.push((current.name.clone(), current.call.clone()));
+            expansion = current.call.expansion.clone();
+        }
+        backtrace
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum TokenContent {
     // Literals and Identifiers
     
-    IntToken(isize),
+    IntToken(i128),
     FloatToken(f64),
     StringToken(String),
     CharToken(char),
+    LabelToken(String),
     Identifier(String),
 
     // Keywords
@@ -31,6 +65,10 @@ pub enum TokenContent {
     FalseKeyword,
     ImportKeyword,
     UseKeyword,
+    ModKeyword,
+    MacroKeyword,
+    AsKeyword,
+    PubKeyword,
     FnKeyword,
     StructKeyword,
     EnumKeyword,
@@ -38,13 +76,16 @@ pub enum TokenContent {
     InstanceKeyword,
     TypeKeyword,
     ConstKeyword,
+    StaticKeyword,
     LetKeyword,
+    DeferKeyword,
     ForKeyword,
     InKeyword,
     IfKeyword,
     ElseKeyword,
     WhileKeyword,
     LoopKeyword,
+    MatchKeyword,
     TryKeyword,
     CatchKeyword,
     OrKeyword,
@@ -118,10 +159,35 @@ pub enum TokenContent {
     DoubleBangOperator, // !!
     QuestionOperator, // ?
     DotOperator, // .
+    DoubleDotOperator, // ..
+    DoubleDotEqualOperator, // ..=
     ScopeResolutionOperator, // ::
     DoubleArrowOperator, // =>
+    HashOperator, // #
+    DollarOperator, // $
+    AtOperator, // @
 }
 
+/// Every word that is lexed as a keyword rather than an identifier
+#[allow(dead_code)]
+pub const KEYWORDS: &[&str] = &[
+    "true", "false", "import", "use", "mod", "macro", "as", "pub", "fn", "struct", "enum", "trait", "instance",
+    "type", "const", "static", "let", "defer", "for", "in", "if", "else", "while", "loop", "match", "try",
+    "catch", "or", "and", "not", "return", "break", "continue", "super", "self",
+];
+
+/// The keywords an item can start with, which a misspelled word at the start
+/// of an item is compared with
+pub const ITEM_KEYWORDS: &[&str] = &[
+    "use", "mod", "macro", "pub", "fn", "struct", "enum", "trait", "instance", "const", "static",
+];
+
+/// The keywords a statement can start with, which a misspelled word at the
+/// start of a statement is compared with
+pub const STATEMENT_KEYWORDS: &[&str] = &[
+    "let", "const", "defer", "for", "while", "loop", "if", "match", "return", "break", "continue",
+];
+
 impl Token {
     pub fn new(content: TokenContent, context: TokenContext) -> Token {
         Token {
@@ -131,112 +197,20 @@ impl Token {
     }
 
     pub fn as_string(&self) -> String {
-        match &self.content {
-            IntToken(n)                     => n.to_string(),
-            FloatToken(n)                   => n.to_string(),
-            StringToken(string)             => string.clone(),
-            CharToken(character)            => character.to_string(),
-            Identifier(identifier)          => identifier.clone(),
-            TrueKeyword                     => String::from("true"),
-            FalseKeyword                    => String::from("false"),
-            ImportKeyword                   => String::from("import"),
-            UseKeyword                      => String::from("use"),
-            FnKeyword                       => String::from("fn"),
-            StructKeyword                   => String::from("struct"),
-            EnumKeyword                     => String::from("enum"),
-            TraitKeyword                    => String::from("trait"),
-            InstanceKeyword                 => String::from("instance"),
-            TypeKeyword                     => String::from("type"),
-            ConstKeyword                    => String::from("const"),
-            LetKeyword                      => String::from("let"),
-            ForKeyword                      => String::from("for"),
-            InKeyword                       => String::from("in"),
-            IfKeyword                       => String::from("if"),
-            ElseKeyword                     => String::from("else"),
-            WhileKeyword                    => String::from("while"),
-            LoopKeyword                     => String::from("loop"),
-            TryKeyword                      => String::from("try"),
-            CatchKeyword                    => String::from("catch"),
-            OrKeyword                       => String::from("or"),
-            AndKeyword                      => String::from("and"),
-            NotKeyword                      => String::from("not"),
-            ReturnKeyword                   => String::from("return"),
-            BreakKeyword                    => String::from("break"),
-            ContinueKeyword                 => String::from("continue"),
-            SuperKeyword                    => String::from("super"),
-            SelfKeyword                     => String::from("self"),
-            I8Keyword                       => String::from("i8"),
-            I16Keyword                      => String::from("i16"),
-            I32Keyword                      => String::from("i32"),
-            I64Keyword                      => String::from("i64"),
-            I128Keyword                     => String::from("i128"),
-            ISizeKeyword                    => String::from("isize"),
-            U8Keyword                       => String::from("u8"),
-            U16Keyword                      => String::from("u16"),
-            U32Keyword                      => String::from("u32"),
-            U64Keyword                      => String::from("u64"),
-            U128Keyword                     => String::from("u128"),
-            USizeKeyword                    => String::from("usize"),
-            F32Keyword                      => String::from("f32"),
-            F64Keyword                      => String::from("f64"),
-            BoolKeyword                     => String::from("bool"),
-            CharKeyword                     => String::from("char"),
-            LeftCurlyBracketOperator        => String::from("{"),
-            RightCurlyBracketOperator       => String::from("}"),
-            LeftSquareBracketOperator       => String::from("["),
-            RightSquareBracketOperator      => String::from("]"),
-            LeftParenthesisOperator         => String::from("("),
-            RightParenthesisOperator        => String::from(")"),
-            SemicolonOperator               => String::from(";"),
-            CommaOperator                   => String::from(","),
-            ColonOperator                   => String::from(":"),
-            EqualOperator                   => String::from("="),
-            PipeOperator                    => String::from("|"),
-            DoubleEqualOperator             => String::from("=="),
-            BangEqualOperator               => String::from("!="),
-            LeftChevronOperator             => String::from("<"),
-            RightChevronOperator            => String::from(">"),
-            LeftChevronEqualOperator        => String::from("<="),
-            RightChevronEqualOperator       => String::from(">="),
-            DoubleLeftChevronOperator       => String::from("<<"),
-            DoubleRightChevronOperator      => String::from(">>"),
-            PlusOperator                    => String::from("+"),
-            MinusOperator                   => String::from("-"),
-            StarOperator                    => String::from("*"),
-            SlashOperator                   => String::from("/"),
-            PercentOperator                 => String::from("%"),
-            DoubleStarOperator              => String::from("**"),
-            CaretOperator                   => String::from("^"),
-            AmpersandOperator               => String::from("&"),
-            TildeOperator                   => String::from("~"),
-            DoubleLeftChevronEqualOperator  => String::from("<<="),
-            DoubleRightChevronEqualOperator => String::from(">>="),
-            PlusEqualOperator               => String::from("+="),
-            MinusEqualOperator              => String::from("-="),
-            StarEqualOperator               => String::from("*="),
-            SlashEqualOperator              => String::from("/="),
-            PercentEqualOperator            => String::from("%="),
-            DoubleStarEqualOperator         => String::from("**="),
-            CaretEqualOperator              => String::from("^="),
-            AmpersandEqualOperator          => String::from("&="),
-            TildeEqualOperator              => String::from("~="),
-            PipeEqualOperator               => String::from("|="),
-            BangOperator                    => String::from("!"),
-            DoubleBangOperator              => String::from("!!"),
-            QuestionOperator                => String::from("?"),
-            DotOperator                     => String::from("."),
-            ScopeResolutionOperator         => String::from("::"),
-            DoubleArrowOperator             => String::from("=>"),
-        }
+        self.content.as_string()
     }
 
     pub fn string_to_token_content(s: String, context: &ProgramContext) -> Option<TokenContent> {
         match context {
-            ProgramContext::NormalContext => match s.as_str() {
+            ProgramContext::NormalContext | ProgramContext::FieldContext => match s.as_str() {
                 "true"       => Some(TrueKeyword),
                 "false"      => Some(FalseKeyword),
                 "import"     => Some(ImportKeyword),
                 "use"        => Some(UseKeyword),
+                "mod"        => Some(ModKeyword),
+                "macro"      => Some(MacroKeyword),
+                "as"         => Some(AsKeyword),
+                "pub"        => Some(PubKeyword),
                 "fn"         => Some(FnKeyword),
                 "struct"     => Some(StructKeyword),
                 "enum"       => Some(EnumKeyword),
@@ -244,13 +218,16 @@ impl Token {
                 "instance"   => Some(InstanceKeyword),
                 "type"       => Some(TypeKeyword),
                 "const"      => Some(ConstKeyword),
+                "static"     => Some(StaticKeyword),
                 "let"        => Some(LetKeyword),
+                "defer"      => Some(DeferKeyword),
                 "for"        => Some(ForKeyword),
                 "in"         => Some(InKeyword),
                 "if"         => Some(IfKeyword),
                 "else"       => Some(ElseKeyword),
                 "while"      => Some(WhileKeyword),
                 "loop"       => Some(LoopKeyword),
+                "match"      => Some(MatchKeyword),
                 "try"        => Some(TryKeyword),
                 "catch"      => Some(CatchKeyword),
                 "or"         => Some(OrKeyword),
@@ -304,8 +281,13 @@ impl Token {
                 "!"          => Some(BangOperator),
                 "!!"         => Some(DoubleBangOperator),
                 "."          => Some(DotOperator),
+                ".."         => Some(DoubleDotOperator),
+                "..="        => Some(DoubleDotEqualOperator),
                 "::"         => Some(ScopeResolutionOperator),
                 "=>"         => Some(DoubleArrowOperator),
+                "#"          => Some(HashOperator),
+                "$"          => Some(DollarOperator),
+                "@"          => Some(AtOperator),
                 _            => None,
             },
 
@@ -326,6 +308,13 @@ impl Token {
                 "f64"        => Some(F64Keyword),
                 "bool"       => Some(BoolKeyword),
                 "char"       => Some(CharKeyword),
+                "fn"         => Some(FnKeyword),
+                "("          => Some(LeftParenthesisOperator),
+                ")"          => Some(RightParenthesisOperator),
+                ","          => Some(CommaOperator),
+                ":"          => Some(ColonOperator),
+                "::"         => Some(ScopeResolutionOperator),
+                ";"          => Some(SemicolonOperator),
                 "["          => Some(LeftSquareBracketOperator),
                 "]"          => Some(RightSquareBracketOperator),
                 "<"          => Some(LeftChevronOperator),
@@ -339,3 +328,118 @@ impl Token {
         }
     }
 }
+
+impl TokenContent {
+    pub fn as_string(&self) -> String {
+        match self {
+            IntToken(n)                     => n.to_string(),
+            FloatToken(n)                   => n.to_string(),
+            StringToken(string)             => string.clone(),
+            CharToken(character)            => character.to_string(),
+            LabelToken(label)               => format!("'{}", label),
+            Identifier(identifier)          => identifier.clone(),
+            TrueKeyword                     => String::from("true"),
+            FalseKeyword                    => String::from("false"),
+            ImportKeyword                   => String::from("import"),
+            UseKeyword                      => String::from("use"),
+            ModKeyword                      => String::from("mod"),
+            MacroKeyword                    => String::from("macro"),
+            AsKeyword                       => String::from("as"),
+            PubKeyword                      => String::from("pub"),
+            FnKeyword                       => String::from("fn"),
+            StructKeyword                   => String::from("struct"),
+            EnumKeyword                     => String::from("enum"),
+            TraitKeyword                    => String::from("trait"),
+            InstanceKeyword                 => String::from("instance"),
+            TypeKeyword                     => String::from("type"),
+            ConstKeyword                    => String::from("const"),
+            StaticKeyword                   => String::from("static"),
+            LetKeyword                      => String::from("let"),
+            DeferKeyword                    => String::from("defer"),
+            ForKeyword                      => String::from("for"),
+            InKeyword                       => String::from("in"),
+            IfKeyword                       => String::from("if"),
+            ElseKeyword                     => String::from("else"),
+            WhileKeyword                    => String::from("while"),
+            LoopKeyword                     => String::from("loop"),
+            MatchKeyword                    => String::from("match"),
+            TryKeyword                      => String::from("try"),
+            CatchKeyword                    => String::from("catch"),
+            OrKeyword                       => String::from("or"),
+            AndKeyword                      => String::from("and"),
+            NotKeyword                      => String::from("not"),
+            ReturnKeyword                   => String::from("return"),
+            BreakKeyword                    => String::from("break"),
+            ContinueKeyword                 => String::from("continue"),
+            SuperKeyword                    => String::from("super"),
+            SelfKeyword                     => String::from("self"),
+            I8Keyword                       => String::from("i8"),
+            I16Keyword                      => String::from("i16"),
+            I32Keyword                      => String::from("i32"),
+            I64Keyword                      => String::from("i64"),
+            I128Keyword                     => String::from("i128"),
+            ISizeKeyword                    => String::from("isize"),
+            U8Keyword                       => String::from("u8"),
+            U16Keyword                      => String::from("u16"),
+            U32Keyword                      => String::from("u32"),
+            U64Keyword                      => String::from("u64"),
+            U128Keyword                     => String::from("u128"),
+            USizeKeyword                    => String::from("usize"),
+            F32Keyword                      => String::from("f32"),
+            F64Keyword                      => String::from("f64"),
+            BoolKeyword                     => String::from("bool"),
+            CharKeyword                     => String::from("char"),
+            LeftCurlyBracketOperator        => String::from("{"),
+            RightCurlyBracketOperator       => String::from("}"),
+            LeftSquareBracketOperator       => String::from("["),
+            RightSquareBracketOperator      => String::from("]"),
+            LeftParenthesisOperator         => String::from("("),
+            RightParenthesisOperator        => String::from(")"),
+            SemicolonOperator               => String::from(";"),
+            CommaOperator                   => String::from(","),
+            ColonOperator                   => String::from(":"),
+            EqualOperator                   => String::from("="),
+            PipeOperator                    => String::from("|"),
+            DoubleEqualOperator             => String::from("=="),
+            BangEqualOperator               => String::from("!="),
+            LeftChevronOperator             => String::from("<"),
+            RightChevronOperator            => String::from(">"),
+            LeftChevronEqualOperator        => String::from("<="),
+            RightChevronEqualOperator       => String::from(">="),
+            DoubleLeftChevronOperator       => String::from("<<"),
+            DoubleRightChevronOperator      => String::from(">>"),
+            PlusOperator                    => String::from("+"),
+            MinusOperator                   => String::from("-"),
+            StarOperator                    => String::from("*"),
+            SlashOperator                   => String::from("/"),
+            PercentOperator                 => String::from("%"),
+            DoubleStarOperator              => String::from("**"),
+            CaretOperator                   => String::from("^"),
+            AmpersandOperator               => String::from("&"),
+            TildeOperator                   => String::from("~"),
+            DoubleLeftChevronEqualOperator  => String::from("<<="),
+            DoubleRightChevronEqualOperator => String::from(">>="),
+            PlusEqualOperator               => String::from("+="),
+            MinusEqualOperator              => String::from("-="),
+            StarEqualOperator               => String::from("*="),
+            SlashEqualOperator              => String::from("/="),
+            PercentEqualOperator            => String::from("%="),
+            DoubleStarEqualOperator         => String::from("**="),
+            CaretEqualOperator              => String::from("^="),
+            AmpersandEqualOperator          => String::from("&="),
+            TildeEqualOperator              => String::from("~="),
+            PipeEqualOperator               => String::from("|="),
+            BangOperator                    => String::from("!"),
+            DoubleBangOperator              => String::from("!!"),
+            QuestionOperator                => String::from("?"),
+            DotOperator                     => String::from("."),
+            DoubleDotOperator               => String::from(".."),
+            DoubleDotEqualOperator          => String::from("..="),
+            ScopeResolutionOperator         => String::from("::"),
+            DoubleArrowOperator             => String::from("=>"),
+            HashOperator                    => String::from("#"),
+            DollarOperator                  => String::from("$"),
+            AtOperator                      => String::from("@"),
+        }
+    }
+}
@@ -1,16 +1,60 @@
 use crate::nodes::*;
+use std::collections::HashMap;
+use std::rc::Rc;
 
+#[derive(Debug, Clone)]
 pub struct Value {
     pub type_: Type,
     pub value: ValueData,
 }
 
+#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum ValueData {
-    IntegerValue(isize),
+    IntegerValue(i128),
     FloatValue(f64),
     StringValue(String),
     CharValue(char),
     BooleanValue(bool),
+    TupleValue(Vec<Value>),
+    ArrayValue(Vec<Value>),
+    StructValue(String, Vec<(String, Value)>),
+    EnumValue(String, String, Vec<Value>),
+    FunctionValue(Rc<Function>),
+    ClosureValue(Rc<Closure>),
+}
+
+#[derive(Debug)]
+pub struct Function {
+    pub header: FunctionHeader,
+    pub body: Block,
+}
+
+#[derive(Debug)]
+pub struct Closure {
+    pub parameters: Vec<String>,
+    pub types: Vec<Type>,
+    pub return_type: Type,
+    pub body: Expression,
+    pub captured: HashMap<String, Value>,
+}
+
+impl Function {
+    pub fn type_(&self) -> Type {
+        Type::Function {
+            parameters: self.header.types.clone(),
+            return_type: Box::new(self.header.return_type.clone()),
+        }
+    }
+}
+
+impl Closure {
+    pub fn type_(&self) -> Type {
+        Type::Function {
+            parameters: self.types.clone(),
+            return_type: Box::new(self.return_type.clone()),
+        }
+    }
 }
 
 impl Value {
@@ -20,4 +64,74 @@ impl Value {
             value,
         }
     }
+
+    pub fn unit() -> Value {
+        Value::new(Type::unit(), ValueData::TupleValue(Vec::new()))
+    }
+
+    pub fn tuple(elements: Vec<Value>) -> Value {
+        let types = elements.iter().map(|element| element.type_.clone()).collect();
+        Value::new(Type::Tuple { types }, ValueData::TupleValue(elements))
+    }
+
+    pub fn array(type_: Type, elements: Vec<Value>) -> Value {
+        let type_ = Type::Array { type_: Box::new(type_), length: elements.len() };
+        Value::new(type_, ValueData::ArrayValue(elements))
+    }
+
+    pub fn equals(&self, other: &Value) -> bool {
+        match (&self.value, &other.value) {
+            (ValueData::IntegerValue(x), ValueData::IntegerValue(y)) => x == y,
+            (ValueData::FloatValue(x), ValueData::FloatValue(y)) => x == y,
+            (ValueData::StringValue(x), ValueData::StringValue(y)) => x == y,
+            (ValueData::CharValue(x), ValueData::CharValue(y)) => x == y,
+            (ValueData::BooleanValue(x), ValueData::BooleanValue(y)) => x == y,
+            (ValueData::TupleValue(x), ValueData::TupleValue(y))
+            | (ValueData::ArrayValue(x), ValueData::ArrayValue(y)) => {
+                x.len() == y.len() && x.iter().zip(y).all(|(x, y)| x.equals(y))
+            },
+            (ValueData::StructValue(x_name, x), ValueData::StructValue(y_name, y)) => {
+                x_name == y_name && x.iter().zip(y).all(|((_, x), (_, y))| x.equals(y))
+            },
+            (ValueData::EnumValue(x_enum, x_variant, x), ValueData::EnumValue(y_enum, y_variant, y)) => {
+                x_enum == y_enum && x_variant == y_variant && x.iter().zip(y).all(|(x, y)| x.equals(y))
+            },
+            (ValueData::FunctionValue(x), ValueData::FunctionValue(y)) => Rc::ptr_eq(x, y),
+            (ValueData::ClosureValue(x), ValueData::ClosureValue(y)) => Rc::ptr_eq(x, y),
+            _ => false,
+        }
+    }
+
+    pub fn as_string(&self) -> String {
+        match &self.value {
+            ValueData::IntegerValue(n) => n.to_string(),
+            ValueData::FloatValue(n)   => n.to_string(),
+            ValueData::StringValue(s)  => s.clone(),
+            ValueData::CharValue(c)    => c.to_string(),
+            ValueData::BooleanValue(b) => b.to_string(),
+            ValueData::TupleValue(elements) if elements.len() == 1 => {
+                format!("({},)", elements[0].as_string())
+            },
+            ValueData::TupleValue(elements) => {
+                let elements: Vec<String> = elements.iter().map(|element| element.as_string()).collect();
+                format!("({})", elements.join(", "))
+            },
+            ValueData::ArrayValue(elements) => {
+                let elements: Vec<String> = elements.iter().map(|element| element.as_string()).collect();
+                format!("[{}]", elements.join(", "))
+            },
+            ValueData::StructValue(name, fields) if fields.is_empty() => format!("{} {{}}", name),
+            ValueData::StructValue(name, fields) => {
+                let fields: Vec<String> = fields.iter().map(|(field, value)| format!("{}: {}", field, value.as_string())).collect();
+                format!("{} {{ {} }}", name, fields.join(", "))
+            },
+            ValueData::EnumValue(enum_, variant, payload) if payload.is_empty() => format!("{}::{}", enum_, variant),
+            ValueData::EnumValue(enum_, variant, payload) => {
+                let payload: Vec<String> = payload.iter().map(|value| value.as_string()).collect();
+                format!("{}::{}({})", enum_, variant, payload.join(", "))
+            },
+            ValueData::FunctionValue(function) => format!("<fn {}>", function.header.name),
+            ValueData::ClosureValue(_) => String::from("<closure>"),
+        }
+    }
 }